@@ -20,6 +20,7 @@ fn main() {
         top_p: None,
         n: Some(1),
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: None,
         stop: None,
@@ -52,6 +53,7 @@ fn main() {
         top_p: None,
         n: Some(1),
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: None,
         stop: None,
@@ -79,6 +81,7 @@ fn main() {
         top_p: None,
         n: Some(1),
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: None,
         stop: None,
@@ -106,6 +109,7 @@ fn main() {
         top_p: None,
         n: Some(3),
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: None,
         stop: None,
@@ -136,6 +140,7 @@ fn main() {
         top_p: None,
         n: Some(1),
         stream: None,
+        stream_options: None,
         logprobs: Some(3),
         echo: None,
         stop: None,
@@ -174,6 +179,7 @@ fn main() {
         top_p: None,
         n: Some(1),
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: Some(true),
         stop: None,
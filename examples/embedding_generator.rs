@@ -25,7 +25,10 @@ fn main() {
     println!("Model: {}", response.model);
     println!("Number of embeddings: {}", response.data.len());
     println!("Embedding dimensions: {}", response.data[0].embedding.len());
-    println!("First few values: {:?}", &response.data[0].embedding[0..5]);
+    println!(
+        "First few values: {:?}",
+        &response.data[0].embedding.to_floats()[0..5]
+    );
     println!("Usage: {} tokens\n", response.usage.prompt_tokens);
 
     // Example 2: Multiple string inputs
@@ -139,11 +142,11 @@ fn main() {
     println!("Same input should produce identical embeddings:");
     println!(
         "First call - first 5 values: {:?}",
-        &response1.data[0].embedding[0..5]
+        &response1.data[0].embedding.to_floats()[0..5]
     );
     println!(
         "Second call - first 5 values: {:?}",
-        &response2.data[0].embedding[0..5]
+        &response2.data[0].embedding.to_floats()[0..5]
     );
     println!(
         "Embeddings are identical: {}",
@@ -161,7 +164,7 @@ fn main() {
     };
 
     let response = EmbeddingGenerator::generate_response(&vector_request);
-    let vector = &response.data[0].embedding;
+    let vector = response.data[0].embedding.to_floats();
 
     // Calculate vector magnitude (should be close to 1.0 since vectors are normalized)
     let magnitude: f64 = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
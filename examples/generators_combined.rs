@@ -46,7 +46,7 @@ fn main() {
     );
 
     // Show first few values as a preview
-    let query_vector = &query_embedding_response.data[0].embedding;
+    let query_vector = query_embedding_response.data[0].embedding.to_floats();
     println!("   - First 5 values: {:?}", &query_vector[0..5]);
 
     // Step 2: Generate embeddings for knowledge base entries
@@ -93,6 +93,7 @@ fn main() {
         top_p: None,
         n: Some(1),
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: None,
         stop: None,
@@ -221,6 +222,7 @@ fn main() {
             top_p: None,
             n: Some(1),
             stream: None,
+            stream_options: None,
             logprobs: None,
             echo: None,
             stop: None,
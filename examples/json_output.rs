@@ -1,6 +1,6 @@
 use openai_mock::models::{
     CompletionChoice, CompletionUsage, CreateCompletionResponse, CreateEmbeddingResponse,
-    EmbeddingData, EmbeddingUsage,
+    EmbeddingData, EmbeddingUsage, EmbeddingVector,
 };
 
 fn main() {
@@ -20,7 +20,7 @@ fn main() {
 
     // Test embedding response
     let usage = EmbeddingUsage::new(8);
-    let embedding = EmbeddingData::new(vec![0.1, 0.2, 0.3, -0.1, -0.2], 0);
+    let embedding = EmbeddingData::new(EmbeddingVector::Float(vec![0.1, 0.2, 0.3, -0.1, -0.2]), 0);
     let response =
         CreateEmbeddingResponse::new(vec![embedding], "text-embedding-ada-002".to_string(), usage);
 
@@ -105,6 +105,71 @@ where
     }
 }
 
+/// Authentication middleware for the admin API
+///
+/// Admin routes are guarded by their own key, passed as a Bearer token like the API key,
+/// so that the clients under test cannot reconfigure the mock by accident.
+#[derive(Clone)]
+pub struct AdminAuthMiddleware {
+    admin_key: String,
+}
+
+impl AdminAuthMiddleware {
+    /// Create a new admin authentication middleware with the specified admin key
+    pub fn new(admin_key: &str) -> Self {
+        Self {
+            admin_key: admin_key.to_string(),
+        }
+    }
+}
+
+impl<E> Middleware<E> for AdminAuthMiddleware
+where
+    E: Endpoint,
+{
+    type Output = AdminAuthEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        AdminAuthEndpoint {
+            inner: ep,
+            admin_key: self.admin_key.clone(),
+        }
+    }
+}
+
+/// Wrapper endpoint that performs admin authentication
+pub struct AdminAuthEndpoint<E> {
+    inner: E,
+    admin_key: String,
+}
+
+impl<E> Endpoint for AdminAuthEndpoint<E>
+where
+    E: Endpoint,
+{
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        if let Err(err) = extract_api_key(&req, &self.admin_key) {
+            let message = match err {
+                AuthError::MissingHeader => {
+                    "You didn't provide an admin key. Provide it in an Authorization header using Bearer auth (i.e. Authorization: Bearer ADMIN_KEY)."
+                }
+                AuthError::InvalidFormat => {
+                    "You must provide the admin key using Bearer authentication (i.e. Authorization: Bearer ADMIN_KEY)."
+                }
+                AuthError::InvalidKey => "Incorrect admin key provided.",
+            };
+            return Ok(create_auth_error_response(message));
+        }
+
+        match self.inner.call(req).await {
+            Ok(response) => Ok(response.into_response()),
+            Err(err) => Err(err),
+        }
+    }
+}
+
 /// Authentication error type
 #[derive(Debug)]
 pub enum AuthError {
@@ -362,4 +427,43 @@ mod tests {
         let result = extract_api_key(&req, "different-key");
         assert!(matches!(result, Err(AuthError::InvalidKey)));
     }
+
+    #[tokio::test]
+    async fn test_admin_auth_middleware() {
+        let admin_key = "sk-admin-test-key";
+        let app = Route::new()
+            .at("/test", poem::get(test_handler))
+            .with(AdminAuthMiddleware::new(admin_key));
+
+        // The API key does not grant admin access
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri(Uri::from_static("/test"))
+            .header("authorization", format!("Bearer {DEFAULT_MOCK_API_KEY}"))
+            .finish();
+
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+        let body = resp.into_body().into_string().await.unwrap();
+        assert!(body.contains("Incorrect admin key provided"));
+
+        // Missing header
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri(Uri::from_static("/test"))
+            .finish();
+
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::UNAUTHORIZED);
+
+        // Admin key
+        let req = Request::builder()
+            .method(Method::GET)
+            .uri(Uri::from_static("/test"))
+            .header("authorization", format!("Bearer {admin_key}"))
+            .finish();
+
+        let resp = app.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+    }
 }
@@ -58,6 +58,9 @@ fn print_startup_info(config: &Config) {
     println!("  GET  /health             - Health check");
     println!("  POST /v1/completions     - Text completion");
     println!("  POST /v1/embeddings      - Text embeddings");
+    println!("  GET  /v1/models          - Model catalog");
+    println!("  *    /admin/expectations - Runtime response expectations (admin key)");
+    println!("  *    /admin/requests     - Request journal (admin key)");
     println!();
     println!();
     println!("Press Ctrl+C to stop the server");
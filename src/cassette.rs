@@ -0,0 +1,543 @@
+//! # Record and Replay
+//!
+//! This module lets the mock stand in for a real OpenAI-compatible upstream. In record mode
+//! (`--record <upstream-url>`), API requests are forwarded to the upstream and every
+//! request/response pair is appended to a cassette file (`--cassette`). In replay mode
+//! (`--replay <cassette>`), requests are answered from a cassette without any network access.
+//!
+//! ## Cassette Format
+//!
+//! A cassette is a JSON Lines file holding one exchange per line:
+//!
+//! ```json
+//! {"request":{"method":"POST","endpoint":"chat/completions","body":{"model":"gpt-4o","messages":[...]}},"response":{"status":200,"content_type":"application/json","body":{"id":"chatcmpl-...",...}}}
+//! ```
+//!
+//! JSON response bodies are stored as JSON; other bodies, such as server-sent event streams,
+//! are stored verbatim as strings. Streamed responses are buffered while recording and
+//! replayed in one piece.
+//!
+//! ## Matching
+//!
+//! A request matches an exchange when the method, the endpoint and the JSON body are equal
+//! (key order does not matter). Repeated identical requests replay the matching exchanges in
+//! recording order, and the last one is repeated once they are used up. Requests matching no
+//! exchange get a `404` error with the `cassette_miss` code.
+
+use poem::{
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+    http::{StatusCode, header},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::config::Config;
+use crate::journal::normalize_endpoint;
+use crate::models::responses::ErrorResponse;
+
+/// A recorded request/response pair
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteEntry {
+    /// The request sent by the client
+    pub request: CassetteRequest,
+
+    /// The response returned by the upstream
+    pub response: CassetteResponse,
+}
+
+/// A recorded request
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteRequest {
+    /// The HTTP method
+    pub method: String,
+
+    /// The endpoint, relative to `/v1` (e.g. `chat/completions`)
+    pub endpoint: String,
+
+    /// The request body, parsed as JSON when possible
+    pub body: serde_json::Value,
+}
+
+/// A recorded response
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CassetteResponse {
+    /// The HTTP status code
+    pub status: u16,
+
+    /// The response content type
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_type: Option<String>,
+
+    /// The response body, as JSON for JSON responses and as a string otherwise
+    pub body: serde_json::Value,
+}
+
+impl CassetteRequest {
+    /// Check whether a request matches this recorded request
+    pub fn matches(&self, method: &str, endpoint: &str, body: &serde_json::Value) -> bool {
+        self.method.eq_ignore_ascii_case(method) && self.endpoint == endpoint && &self.body == body
+    }
+}
+
+impl CassetteResponse {
+    /// Build a recorded response from a status, content type and raw body
+    pub fn new(status: u16, content_type: Option<String>, body: &[u8]) -> Self {
+        let is_json = content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.contains("json"));
+        let body = match serde_json::from_slice(body) {
+            Ok(value) if is_json => value,
+            _ => serde_json::Value::String(String::from_utf8_lossy(body).into_owned()),
+        };
+
+        Self {
+            status,
+            content_type,
+            body,
+        }
+    }
+
+    /// Convert the recorded response back into an HTTP response
+    pub fn to_response(&self) -> Response {
+        let body = match &self.body {
+            serde_json::Value::String(text) if !self.is_json() => text.clone(),
+            value => value.to_string(),
+        };
+
+        let mut builder =
+            Response::builder().status(StatusCode::from_u16(self.status).unwrap_or(StatusCode::OK));
+        if let Some(content_type) = &self.content_type {
+            builder = builder.header(header::CONTENT_TYPE, content_type.as_str());
+        }
+        builder.body(body)
+    }
+
+    fn is_json(&self) -> bool {
+        self.content_type
+            .as_deref()
+            .is_some_and(|content_type| content_type.contains("json"))
+    }
+}
+
+/// A cassette loaded for replay
+#[derive(Debug, Default)]
+pub struct Cassette {
+    entries: Vec<CassetteEntry>,
+    plays: Mutex<Vec<u32>>,
+}
+
+impl Cassette {
+    /// Create a cassette from recorded exchanges
+    pub fn new(entries: Vec<CassetteEntry>) -> Self {
+        let plays = Mutex::new(vec![0; entries.len()]);
+        Self { entries, plays }
+    }
+
+    /// Load a cassette from a JSON Lines file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, CassetteError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| CassetteError::Io(format!("{}: {e}", path.display())))?;
+        Self::parse(&contents)
+    }
+
+    /// Parse a cassette from JSON Lines
+    pub fn parse(contents: &str) -> Result<Self, CassetteError> {
+        let entries = contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(number, line)| {
+                serde_json::from_str(line)
+                    .map_err(|e| CassetteError::Parse(format!("line {}: {e}", number + 1)))
+            })
+            .collect::<Result<Vec<CassetteEntry>, _>>()?;
+        Ok(Self::new(entries))
+    }
+
+    /// The recorded exchanges
+    pub fn entries(&self) -> &[CassetteEntry] {
+        &self.entries
+    }
+
+    /// Find the recorded response for a request
+    ///
+    /// The first matching exchange that has not been played yet is used; once all matching
+    /// exchanges have been played, the last one is repeated.
+    pub fn play(
+        &self,
+        method: &str,
+        endpoint: &str,
+        body: &serde_json::Value,
+    ) -> Option<CassetteResponse> {
+        let mut plays = self.plays.lock().unwrap_or_else(|e| e.into_inner());
+        let matching: Vec<usize> = self
+            .entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.request.matches(method, endpoint, body))
+            .map(|(index, _)| index)
+            .collect();
+
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&index| plays[index] == 0)
+            .or_else(|| matching.last().copied())?;
+
+        plays[index] += 1;
+        Some(self.entries[index].response.clone())
+    }
+}
+
+/// Forwards requests to an upstream and appends the exchanges to a cassette file
+#[derive(Debug)]
+pub struct CassetteRecorder {
+    upstream: String,
+    api_key: String,
+    client: reqwest::Client,
+    file: Mutex<File>,
+}
+
+impl CassetteRecorder {
+    /// Create a recorder forwarding to `upstream` (e.g. `https://api.openai.com/v1`)
+    ///
+    /// Exchanges are appended to the cassette file, which is created if needed. Forwarded
+    /// requests are authorized with `api_key`; the client's `Authorization` header only ever
+    /// holds the mock's own key, so it is never forwarded.
+    pub fn new<P: AsRef<Path>>(
+        upstream: &str,
+        cassette_path: P,
+        api_key: String,
+    ) -> Result<Self, CassetteError> {
+        let path = cassette_path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| CassetteError::Io(format!("{}: {e}", path.display())))?;
+
+        Ok(Self {
+            upstream: upstream.trim_end_matches('/').to_string(),
+            api_key,
+            client: reqwest::Client::new(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Forward a request to the upstream, record the exchange and return the response
+    pub async fn forward(
+        &self,
+        request: CassetteRequest,
+    ) -> Result<CassetteResponse, CassetteError> {
+        let url = format!("{}/{}", self.upstream, request.endpoint);
+        let method = reqwest::Method::from_bytes(request.method.as_bytes())
+            .map_err(|e| CassetteError::Upstream(e.to_string()))?;
+
+        let mut upstream_request = self
+            .client
+            .request(method, &url)
+            .header(header::AUTHORIZATION, format!("Bearer {}", self.api_key));
+        if !request.body.is_null() {
+            upstream_request = upstream_request.json(&request.body);
+        }
+
+        let upstream_response = upstream_request
+            .send()
+            .await
+            .map_err(|e| CassetteError::Upstream(e.to_string()))?;
+        let status = upstream_response.status().as_u16();
+        let content_type = upstream_response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string);
+        let body = upstream_response
+            .bytes()
+            .await
+            .map_err(|e| CassetteError::Upstream(e.to_string()))?;
+
+        let response = CassetteResponse::new(status, content_type, &body);
+        self.append(&CassetteEntry {
+            request,
+            response: response.clone(),
+        })?;
+
+        Ok(response)
+    }
+
+    /// Append an exchange to the cassette file
+    fn append(&self, entry: &CassetteEntry) -> Result<(), CassetteError> {
+        let line = serde_json::to_string(entry).map_err(|e| CassetteError::Parse(e.to_string()))?;
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        writeln!(file, "{line}").map_err(|e| CassetteError::Io(e.to_string()))
+    }
+}
+
+/// How the cassette middleware answers requests
+#[derive(Debug)]
+pub enum CassetteMode {
+    /// Forward requests to an upstream and record them
+    Record(CassetteRecorder),
+    /// Answer requests from a recorded cassette
+    Replay(Cassette),
+}
+
+/// Middleware that records or replays API traffic
+///
+/// When disabled, requests pass through to the mock handlers.
+#[derive(Clone, Default)]
+pub struct CassetteMiddleware {
+    mode: Option<Arc<CassetteMode>>,
+}
+
+impl CassetteMiddleware {
+    /// Create a middleware that passes requests through
+    pub fn disabled() -> Self {
+        Self::default()
+    }
+
+    /// Create a middleware in the given mode
+    pub fn new(mode: CassetteMode) -> Self {
+        Self {
+            mode: Some(Arc::new(mode)),
+        }
+    }
+
+    /// Create the middleware from the server configuration
+    ///
+    /// A cassette that fails to load is logged and leaves the mock handlers in charge;
+    /// `Config::validate` reports such problems before the server starts.
+    pub fn from_config(config: &Config) -> Self {
+        let mode = if let Some(upstream) = &config.record {
+            match &config.upstream_api_key {
+                Some(api_key) => {
+                    CassetteRecorder::new(upstream, &config.cassette_path, api_key.clone())
+                        .map(CassetteMode::Record)
+                }
+                None => Err(CassetteError::Upstream(
+                    "Record mode requires an upstream API key".to_string(),
+                )),
+            }
+        } else if let Some(path) = &config.replay {
+            Cassette::load(path).map(CassetteMode::Replay)
+        } else {
+            return Self::disabled();
+        };
+
+        match mode {
+            Ok(mode) => Self::new(mode),
+            Err(e) => {
+                tracing::error!("{e}");
+                Self::disabled()
+            }
+        }
+    }
+}
+
+impl<E> Middleware<E> for CassetteMiddleware
+where
+    E: Endpoint,
+{
+    type Output = CassetteEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        CassetteEndpoint {
+            inner: ep,
+            mode: self.mode.clone(),
+        }
+    }
+}
+
+/// Wrapper endpoint that records or replays requests
+pub struct CassetteEndpoint<E> {
+    inner: E,
+    mode: Option<Arc<CassetteMode>>,
+}
+
+impl<E> Endpoint for CassetteEndpoint<E>
+where
+    E: Endpoint,
+{
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        let Some(mode) = &self.mode else {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        };
+
+        let bytes = req.take_body().into_bytes().await.unwrap_or_default();
+        let body = if bytes.is_empty() {
+            serde_json::Value::Null
+        } else {
+            serde_json::from_slice(&bytes).unwrap_or_else(|_| {
+                serde_json::Value::String(String::from_utf8_lossy(&bytes).into_owned())
+            })
+        };
+        let request = CassetteRequest {
+            method: req.method().to_string(),
+            endpoint: normalize_endpoint(req.uri().path()),
+            body,
+        };
+
+        match mode.as_ref() {
+            CassetteMode::Replay(cassette) => {
+                match cassette.play(&request.method, &request.endpoint, &request.body) {
+                    Some(response) => Ok(response.to_response()),
+                    None => Ok(create_error_response(
+                        StatusCode::NOT_FOUND,
+                        ErrorResponse::invalid_request_error(format!(
+                            "No recorded response in the cassette matches {} {}",
+                            request.method, request.endpoint
+                        ))
+                        .with_code("cassette_miss".to_string()),
+                    )),
+                }
+            }
+            CassetteMode::Record(recorder) => match recorder.forward(request).await {
+                Ok(response) => Ok(response.to_response()),
+                Err(e) => {
+                    tracing::error!("{e}");
+                    Ok(create_error_response(
+                        StatusCode::BAD_GATEWAY,
+                        ErrorResponse::new(e.to_string(), "server_error".to_string()),
+                    ))
+                }
+            },
+        }
+    }
+}
+
+/// Create an OpenAI-formatted error response
+fn create_error_response(status: StatusCode, error_response: ErrorResponse) -> Response {
+    let json_body = match serde_json::to_string(&error_response) {
+        Ok(json) => json,
+        Err(_) => {
+            r#"{"error":{"message":"Internal server error","type":"server_error"}}"#.to_string()
+        }
+    };
+
+    Response::builder()
+        .status(status)
+        .header("content-type", "application/json")
+        .body(json_body)
+}
+
+/// Record and replay error types
+#[derive(Debug, Clone)]
+pub enum CassetteError {
+    Io(String),
+    Parse(String),
+    Upstream(String),
+}
+
+impl fmt::Display for CassetteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CassetteError::Io(msg) => write!(f, "Failed to access cassette: {msg}"),
+            CassetteError::Parse(msg) => write!(f, "Invalid cassette: {msg}"),
+            CassetteError::Upstream(msg) => write!(f, "Upstream request failed: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for CassetteError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{EndpointExt, Route, handler, post, test::TestClient};
+
+    #[handler]
+    async fn mock_handler() -> &'static str {
+        "mock"
+    }
+
+    const CASSETTE: &str = r#"
+{"request":{"method":"POST","endpoint":"chat/completions","body":{"model":"gpt-4o","n":1}},"response":{"status":200,"content_type":"application/json","body":{"answer":1}}}
+{"request":{"method":"POST","endpoint":"chat/completions","body":{"n":1,"model":"gpt-4o"}},"response":{"status":200,"content_type":"application/json","body":{"answer":2}}}
+{"request":{"method":"POST","endpoint":"completions","body":{"stream":true}},"response":{"status":200,"content_type":"text/event-stream","body":"data: {}\n\ndata: [DONE]\n\n"}}
+"#;
+
+    #[test]
+    fn test_parse_and_play_in_order() {
+        let cassette = Cassette::parse(CASSETTE).unwrap();
+        assert_eq!(cassette.entries().len(), 3);
+
+        let body = serde_json::json!({"model": "gpt-4o", "n": 1});
+        let first = cassette.play("POST", "chat/completions", &body).unwrap();
+        let second = cassette.play("POST", "chat/completions", &body).unwrap();
+        let third = cassette.play("POST", "chat/completions", &body).unwrap();
+        assert_eq!(first.body["answer"], 1);
+        assert_eq!(second.body["answer"], 2);
+        assert_eq!(third.body["answer"], 2);
+
+        let other = serde_json::json!({"model": "gpt-4"});
+        assert!(cassette.play("POST", "chat/completions", &other).is_none());
+    }
+
+    #[test]
+    fn test_parse_invalid_line() {
+        let result = Cassette::parse("{\"request\": 1}\n");
+        assert!(matches!(result, Err(CassetteError::Parse(msg)) if msg.starts_with("line 1")));
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        let response = CassetteResponse::new(
+            200,
+            Some("text/event-stream".to_string()),
+            b"data: [DONE]\n\n",
+        );
+        assert_eq!(response.body, "data: [DONE]\n\n");
+
+        let response =
+            CassetteResponse::new(201, Some("application/json".to_string()), br#"{"ok":true}"#);
+        assert_eq!(response.body, serde_json::json!({"ok": true}));
+        assert_eq!(response.to_response().status(), StatusCode::CREATED);
+    }
+
+    #[tokio::test]
+    async fn test_replay_middleware() {
+        let cassette = Cassette::parse(CASSETTE).unwrap();
+        let app = Route::new()
+            .at("/completions", post(mock_handler))
+            .at("/chat/completions", post(mock_handler))
+            .with(CassetteMiddleware::new(CassetteMode::Replay(cassette)));
+        let client = TestClient::new(app);
+
+        let resp = client
+            .post("/completions")
+            .body_json(&serde_json::json!({"stream": true}))
+            .send()
+            .await;
+        resp.assert_status_is_ok();
+        resp.assert_content_type("text/event-stream");
+        resp.assert_text("data: {}\n\ndata: [DONE]\n\n").await;
+
+        let resp = client
+            .post("/chat/completions")
+            .body_json(&serde_json::json!({"model": "unknown"}))
+            .send()
+            .await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["error"]["code"], "cassette_miss");
+    }
+
+    #[tokio::test]
+    async fn test_disabled_middleware_passes_through() {
+        let app = Route::new()
+            .at("/completions", post(mock_handler))
+            .with(CassetteMiddleware::disabled());
+        let client = TestClient::new(app);
+
+        let resp = client.post("/completions").send().await;
+        resp.assert_status_is_ok();
+        resp.assert_text("mock").await;
+    }
+}
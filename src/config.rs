@@ -9,10 +9,28 @@
 //! - `--host`: Server host address (default: 0.0.0.0)
 //! - `--port`: Server port (default: 13673)
 //! - `--api-key`: API key for authentication (default: sk-mock-openai-api-key-12345)
+//! - `--admin-key`: Key guarding the `/admin` API (default: sk-mock-admin-key-12345)
 //! - `--request-timeout-secs`: Request timeout in seconds (default: 30)
 //! - `--enable-cors`: Enable CORS support (default: true)
+//! - `--cors-allowed-origins`: Comma-separated origins allowed by CORS (default: *)
+//! - `--cors-allowed-methods`: Comma-separated methods allowed by CORS (default: GET,POST,DELETE,OPTIONS)
+//! - `--cors-allowed-headers`: Comma-separated request headers allowed by CORS
+//!   (default: content-type,authorization,x-api-key)
 //! - `--enable-logging`: Enable request logging (default: true)
 //! - `--log-level`: Logging level (default: info)
+//! - `--journal-capacity`: Number of requests kept by the request journal (default: 1000)
+//! - `--fixtures`: Path to a YAML or JSON file of scripted response fixtures (default: none)
+//! - `--faults`: Path to a YAML or JSON file of fault injection rules (default: none)
+//! - `--models`: Path to a YAML or JSON model catalog (default: built-in OpenAI models)
+//! - `--latency`: Path to a YAML or JSON file of per-model latency profiles (default: none)
+//! - `--deterministic-latency`: Disable latency jitter (default: false)
+//! - `--embedding-mode`: How embedding vectors are derived, `hash` or `semantic` (default: hash)
+//! - `--rate-limit-rpm`: Requests per minute allowed per API key (default: unlimited)
+//! - `--rate-limit-tpm`: Tokens per minute allowed per API key (default: unlimited)
+//! - `--record`: Forward API requests to this upstream URL and record them (default: none)
+//! - `--replay`: Answer API requests from this cassette file (default: none)
+//! - `--cassette`: Cassette file written in record mode (default: cassette.jsonl)
+//! - `--upstream-api-key`: API key sent to the upstream in record mode (required with `--record`)
 //!
 //! ## Usage
 //!
@@ -27,13 +45,26 @@
 use clap::Parser;
 use std::time::Duration;
 
+use crate::cassette::Cassette;
+use crate::faults::FaultInjector;
+use crate::fixtures::FixtureSet;
+use crate::generators::EmbeddingMode;
+use crate::journal::DEFAULT_JOURNAL_CAPACITY;
+use crate::latency::LatencyProfiles;
+use crate::registry::ModelRegistry;
+
 const DEFAULT_HOST: &str = "0.0.0.0";
 const DEFAULT_PORT: u16 = 13673;
 const DEFAULT_API_KEY: &str = "sk-mock-openai-api-key-12345";
+const DEFAULT_ADMIN_KEY: &str = "sk-mock-admin-key-12345";
 const DEFAULT_REQUEST_TIMEOUT_SECS: u64 = 30;
 const DEFAULT_ENABLE_CORS: bool = true;
+const DEFAULT_CORS_ALLOWED_ORIGINS: &str = "*";
+const DEFAULT_CORS_ALLOWED_METHODS: &str = "GET,POST,DELETE,OPTIONS";
+const DEFAULT_CORS_ALLOWED_HEADERS: &str = "content-type,authorization,x-api-key";
 const DEFAULT_ENABLE_LOGGING: bool = true;
 const DEFAULT_LOG_LEVEL: &str = "info";
+const DEFAULT_CASSETTE_PATH: &str = "cassette.jsonl";
 
 /// Server configuration with all settings
 #[derive(Debug, Clone, Parser)]
@@ -53,6 +84,10 @@ pub struct Config {
     #[arg(long, default_value = DEFAULT_API_KEY)]
     pub api_key: String,
 
+    /// Admin key guarding the /admin API
+    #[arg(long, default_value = DEFAULT_ADMIN_KEY)]
+    pub admin_key: String,
+
     /// Request timeout duration in seconds
     #[arg(long, default_value_t = DEFAULT_REQUEST_TIMEOUT_SECS)]
     pub request_timeout_secs: u64,
@@ -61,6 +96,18 @@ pub struct Config {
     #[arg(long, default_value_t = DEFAULT_ENABLE_CORS, action = clap::ArgAction::Set)]
     pub enable_cors: bool,
 
+    /// Origins allowed by CORS; `*` allows any origin, and entries may use `*` wildcards
+    #[arg(long, value_delimiter = ',', default_value = DEFAULT_CORS_ALLOWED_ORIGINS)]
+    pub cors_allowed_origins: Vec<String>,
+
+    /// HTTP methods allowed by CORS
+    #[arg(long, value_delimiter = ',', default_value = DEFAULT_CORS_ALLOWED_METHODS)]
+    pub cors_allowed_methods: Vec<String>,
+
+    /// Request headers allowed by CORS
+    #[arg(long, value_delimiter = ',', default_value = DEFAULT_CORS_ALLOWED_HEADERS)]
+    pub cors_allowed_headers: Vec<String>,
+
     /// Enable request logging middleware
     #[arg(long, default_value_t = DEFAULT_ENABLE_LOGGING, action = clap::ArgAction::Set)]
     pub enable_logging: bool,
@@ -68,6 +115,58 @@ pub struct Config {
     /// Logging level
     #[arg(long, default_value = DEFAULT_LOG_LEVEL)]
     pub log_level: String,
+
+    /// Number of requests kept by the request journal (0 disables it)
+    #[arg(long, default_value_t = DEFAULT_JOURNAL_CAPACITY)]
+    pub journal_capacity: usize,
+
+    /// Path to a YAML or JSON file of scripted response fixtures
+    #[arg(long = "fixtures")]
+    pub fixtures_path: Option<String>,
+
+    /// Path to a YAML or JSON file of fault injection rules
+    #[arg(long = "faults")]
+    pub faults_path: Option<String>,
+
+    /// Path to a YAML or JSON model catalog replacing the built-in models
+    #[arg(long = "models")]
+    pub models_path: Option<String>,
+
+    /// Path to a YAML or JSON file of per-model latency profiles
+    #[arg(long = "latency")]
+    pub latency_path: Option<String>,
+
+    /// Disable latency jitter so responses take the same time on every run
+    #[arg(long)]
+    pub deterministic_latency: bool,
+
+    /// How embedding vectors are derived from the input text
+    #[arg(long, value_enum, default_value_t = EmbeddingMode::Hash)]
+    pub embedding_mode: EmbeddingMode,
+
+    /// Requests per minute allowed per API key (unlimited when unset)
+    #[arg(long)]
+    pub rate_limit_rpm: Option<u32>,
+
+    /// Tokens per minute allowed per API key (unlimited when unset)
+    #[arg(long)]
+    pub rate_limit_tpm: Option<u32>,
+
+    /// Forward API requests to this upstream URL (e.g. https://api.openai.com/v1) and record them
+    #[arg(long, value_name = "UPSTREAM_URL")]
+    pub record: Option<String>,
+
+    /// Answer API requests from this cassette file instead of the generators
+    #[arg(long, value_name = "CASSETTE")]
+    pub replay: Option<String>,
+
+    /// Cassette file that recorded exchanges are appended to
+    #[arg(long = "cassette", default_value = DEFAULT_CASSETTE_PATH)]
+    pub cassette_path: String,
+
+    /// API key sent to the upstream in record mode (required with --record)
+    #[arg(long)]
+    pub upstream_api_key: Option<String>,
 }
 
 impl Default for Config {
@@ -76,10 +175,27 @@ impl Default for Config {
             host: DEFAULT_HOST.to_string(),
             port: DEFAULT_PORT,
             api_key: DEFAULT_API_KEY.to_string(),
+            admin_key: DEFAULT_ADMIN_KEY.to_string(),
             request_timeout_secs: DEFAULT_REQUEST_TIMEOUT_SECS,
             enable_cors: DEFAULT_ENABLE_CORS,
+            cors_allowed_origins: split_list(DEFAULT_CORS_ALLOWED_ORIGINS),
+            cors_allowed_methods: split_list(DEFAULT_CORS_ALLOWED_METHODS),
+            cors_allowed_headers: split_list(DEFAULT_CORS_ALLOWED_HEADERS),
             enable_logging: DEFAULT_ENABLE_LOGGING,
             log_level: DEFAULT_LOG_LEVEL.to_string(),
+            journal_capacity: DEFAULT_JOURNAL_CAPACITY,
+            fixtures_path: None,
+            faults_path: None,
+            models_path: None,
+            latency_path: None,
+            deterministic_latency: false,
+            embedding_mode: EmbeddingMode::Hash,
+            rate_limit_rpm: None,
+            rate_limit_tpm: None,
+            record: None,
+            replay: None,
+            cassette_path: DEFAULT_CASSETTE_PATH.to_string(),
+            upstream_api_key: None,
         }
     }
 }
@@ -122,6 +238,12 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set the admin key
+    pub fn admin_key<S: Into<String>>(mut self, admin_key: S) -> Self {
+        self.config.admin_key = admin_key.into();
+        self
+    }
+
     /// Set the request timeout in seconds
     pub fn request_timeout_secs(mut self, secs: u64) -> Self {
         self.config.request_timeout_secs = secs;
@@ -134,6 +256,36 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set the origins allowed by CORS
+    pub fn cors_allowed_origins<I, S>(mut self, origins: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.cors_allowed_origins = origins.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the HTTP methods allowed by CORS
+    pub fn cors_allowed_methods<I, S>(mut self, methods: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.cors_allowed_methods = methods.into_iter().map(Into::into).collect();
+        self
+    }
+
+    /// Set the request headers allowed by CORS
+    pub fn cors_allowed_headers<I, S>(mut self, headers: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config.cors_allowed_headers = headers.into_iter().map(Into::into).collect();
+        self
+    }
+
     /// Set logging enablement
     pub fn enable_logging(mut self, enable: bool) -> Self {
         self.config.enable_logging = enable;
@@ -146,6 +298,84 @@ impl ConfigBuilder {
         self
     }
 
+    /// Set the request journal capacity
+    pub fn journal_capacity(mut self, capacity: usize) -> Self {
+        self.config.journal_capacity = capacity;
+        self
+    }
+
+    /// Set the path of the response fixtures file
+    pub fn fixtures_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.config.fixtures_path = Some(path.into());
+        self
+    }
+
+    /// Set the path of the fault injection rules file
+    pub fn faults_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.config.faults_path = Some(path.into());
+        self
+    }
+
+    /// Set the path of the model catalog file
+    pub fn models_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.config.models_path = Some(path.into());
+        self
+    }
+
+    /// Set the path of the latency profiles file
+    pub fn latency_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.config.latency_path = Some(path.into());
+        self
+    }
+
+    /// Enable or disable deterministic latency
+    pub fn deterministic_latency(mut self, deterministic: bool) -> Self {
+        self.config.deterministic_latency = deterministic;
+        self
+    }
+
+    /// Set how embedding vectors are derived
+    pub fn embedding_mode(mut self, mode: EmbeddingMode) -> Self {
+        self.config.embedding_mode = mode;
+        self
+    }
+
+    /// Set the requests per minute allowed per API key
+    pub fn rate_limit_rpm(mut self, limit: u32) -> Self {
+        self.config.rate_limit_rpm = Some(limit);
+        self
+    }
+
+    /// Set the tokens per minute allowed per API key
+    pub fn rate_limit_tpm(mut self, limit: u32) -> Self {
+        self.config.rate_limit_tpm = Some(limit);
+        self
+    }
+
+    /// Set the upstream URL to forward and record requests to
+    pub fn record<S: Into<String>>(mut self, upstream: S) -> Self {
+        self.config.record = Some(upstream.into());
+        self
+    }
+
+    /// Set the cassette file to replay responses from
+    pub fn replay<S: Into<String>>(mut self, path: S) -> Self {
+        self.config.replay = Some(path.into());
+        self
+    }
+
+    /// Set the cassette file written in record mode
+    pub fn cassette_path<S: Into<String>>(mut self, path: S) -> Self {
+        self.config.cassette_path = path.into();
+        self
+    }
+
+    /// Set the API key sent to the upstream in record mode
+    pub fn upstream_api_key<S: Into<String>>(mut self, api_key: S) -> Self {
+        self.config.upstream_api_key = Some(api_key.into());
+        self
+    }
+
     /// Build the configuration
     pub fn build(self) -> Config {
         self.config
@@ -186,6 +416,46 @@ impl Config {
         format!("http://{}:{}", host, self.port)
     }
 
+    /// Load the response fixtures, if a fixtures file is configured
+    pub fn load_fixtures(&self) -> Result<FixtureSet, ConfigError> {
+        match &self.fixtures_path {
+            Some(path) => {
+                FixtureSet::load(path).map_err(|e| ConfigError::InvalidFixtures(e.to_string()))
+            }
+            None => Ok(FixtureSet::default()),
+        }
+    }
+
+    /// Load the fault injection rules, if a faults file is configured
+    pub fn load_faults(&self) -> Result<FaultInjector, ConfigError> {
+        match &self.faults_path {
+            Some(path) => {
+                FaultInjector::load(path).map_err(|e| ConfigError::InvalidFaults(e.to_string()))
+            }
+            None => Ok(FaultInjector::default()),
+        }
+    }
+
+    /// Load the model registry, falling back to the built-in models
+    pub fn load_models(&self) -> Result<ModelRegistry, ConfigError> {
+        match &self.models_path {
+            Some(path) => {
+                ModelRegistry::load(path).map_err(|e| ConfigError::InvalidModels(e.to_string()))
+            }
+            None => Ok(ModelRegistry::default()),
+        }
+    }
+
+    /// Load the latency profiles, if a latency file is configured
+    pub fn load_latency(&self) -> Result<LatencyProfiles, ConfigError> {
+        let profiles = match &self.latency_path {
+            Some(path) => LatencyProfiles::load(path)
+                .map_err(|e| ConfigError::InvalidLatency(e.to_string()))?,
+            None => LatencyProfiles::default(),
+        };
+        Ok(profiles.with_deterministic(self.deterministic_latency))
+    }
+
     /// Validate the configuration
     pub fn validate(&self) -> Result<(), ConfigError> {
         // Validate port range
@@ -206,6 +476,19 @@ impl Config {
             ));
         }
 
+        // Validate admin key
+        if self.admin_key.is_empty() {
+            return Err(ConfigError::InvalidAdminKey(
+                "Admin key cannot be empty".to_string(),
+            ));
+        }
+
+        if self.admin_key == self.api_key {
+            return Err(ConfigError::InvalidAdminKey(
+                "Admin key must differ from the API key".to_string(),
+            ));
+        }
+
         // Validate timeout
         if self.request_timeout_secs == 0 {
             return Err(ConfigError::InvalidTimeout(
@@ -213,6 +496,31 @@ impl Config {
             ));
         }
 
+        // Validate CORS lists
+        for origin in &self.cors_allowed_origins {
+            if origin != "*" && !origin.starts_with("http://") && !origin.starts_with("https://") {
+                return Err(ConfigError::InvalidCors(format!(
+                    "Origin must be '*' or start with http:// or https://, got '{origin}'"
+                )));
+            }
+        }
+
+        for method in &self.cors_allowed_methods {
+            if poem::http::Method::from_bytes(method.as_bytes()).is_err() {
+                return Err(ConfigError::InvalidCors(format!(
+                    "Invalid method: '{method}'"
+                )));
+            }
+        }
+
+        for header in &self.cors_allowed_headers {
+            if poem::http::HeaderName::from_bytes(header.as_bytes()).is_err() {
+                return Err(ConfigError::InvalidCors(format!(
+                    "Invalid header: '{header}'"
+                )));
+            }
+        }
+
         // Validate log level
         match self.log_level.to_lowercase().as_str() {
             "trace" | "debug" | "info" | "warn" | "error" => {}
@@ -224,6 +532,52 @@ impl Config {
             }
         }
 
+        // Validate fixtures file
+        self.load_fixtures()?;
+
+        // Validate faults file
+        self.load_faults()?;
+
+        // Validate models file
+        self.load_models()?;
+
+        // Validate latency file
+        self.load_latency()?;
+
+        // Validate rate limits
+        if self.rate_limit_rpm == Some(0) || self.rate_limit_tpm == Some(0) {
+            return Err(ConfigError::InvalidRateLimit(
+                "Rate limits must be greater than 0".to_string(),
+            ));
+        }
+
+        // Validate record and replay modes
+        if self.record.is_some() && self.replay.is_some() {
+            return Err(ConfigError::InvalidCassette(
+                "Record and replay modes cannot be combined".to_string(),
+            ));
+        }
+
+        if let Some(upstream) = &self.record
+            && !upstream.starts_with("http://")
+            && !upstream.starts_with("https://")
+        {
+            return Err(ConfigError::InvalidUpstream(format!(
+                "Upstream URL must start with http:// or https://, got '{upstream}'"
+            )));
+        }
+
+        // Clients authenticate with the mock's own key, which the upstream would reject
+        if self.record.is_some() && self.upstream_api_key.is_none() {
+            return Err(ConfigError::InvalidUpstream(
+                "Record mode requires --upstream-api-key".to_string(),
+            ));
+        }
+
+        if let Some(path) = &self.replay {
+            Cassette::load(path).map_err(|e| ConfigError::InvalidCassette(e.to_string()))?;
+        }
+
         Ok(())
     }
 
@@ -238,20 +592,74 @@ impl Config {
             "  API Key: {}***",
             &self.api_key[..self.api_key.len().min(10)]
         );
+        println!(
+            "  Admin Key: {}***",
+            &self.admin_key[..self.admin_key.len().min(10)]
+        );
         println!("  Request Timeout: {}s", self.request_timeout_secs);
         println!("  CORS Enabled: {}", self.enable_cors);
+        if self.enable_cors {
+            println!("  CORS Origins: {}", self.cors_allowed_origins.join(", "));
+        }
         println!("  Logging Enabled: {}", self.enable_logging);
         println!("  Log Level: {}", self.log_level);
+        println!("  Journal Capacity: {}", self.journal_capacity);
+        if let Some(path) = &self.fixtures_path {
+            println!("  Fixtures: {path}");
+        }
+        if let Some(path) = &self.faults_path {
+            println!("  Faults: {path}");
+        }
+        if let Some(path) = &self.models_path {
+            println!("  Models: {path}");
+        }
+        if let Some(path) = &self.latency_path {
+            let mode = if self.deterministic_latency {
+                " (deterministic)"
+            } else {
+                ""
+            };
+            println!("  Latency: {path}{mode}");
+        }
+        if self.embedding_mode == EmbeddingMode::Semantic {
+            println!("  Embedding Mode: semantic");
+        }
+        if let Some(limit) = self.rate_limit_rpm {
+            println!("  Rate Limit: {limit} requests/min");
+        }
+        if let Some(limit) = self.rate_limit_tpm {
+            println!("  Rate Limit: {limit} tokens/min");
+        }
+        if let Some(upstream) = &self.record {
+            println!("  Recording: {upstream} -> {}", self.cassette_path);
+        }
+        if let Some(path) = &self.replay {
+            println!("  Replaying: {path}");
+        }
     }
 }
 
+/// Split a comma-separated default value into a list
+fn split_list(value: &str) -> Vec<String> {
+    value.split(',').map(str::to_string).collect()
+}
+
 /// Configuration error types
 #[derive(Debug, Clone)]
 pub enum ConfigError {
     InvalidPort(String),
     InvalidApiKey(String),
+    InvalidAdminKey(String),
     InvalidTimeout(String),
+    InvalidCors(String),
     InvalidLogLevel(String),
+    InvalidFixtures(String),
+    InvalidFaults(String),
+    InvalidModels(String),
+    InvalidLatency(String),
+    InvalidRateLimit(String),
+    InvalidUpstream(String),
+    InvalidCassette(String),
 }
 
 impl std::fmt::Display for ConfigError {
@@ -259,8 +667,17 @@ impl std::fmt::Display for ConfigError {
         match self {
             ConfigError::InvalidPort(msg) => write!(f, "Invalid port: {msg}"),
             ConfigError::InvalidApiKey(msg) => write!(f, "Invalid API key: {msg}"),
+            ConfigError::InvalidAdminKey(msg) => write!(f, "Invalid admin key: {msg}"),
             ConfigError::InvalidTimeout(msg) => write!(f, "Invalid timeout: {msg}"),
+            ConfigError::InvalidCors(msg) => write!(f, "Invalid CORS setting: {msg}"),
             ConfigError::InvalidLogLevel(msg) => write!(f, "Invalid log level: {msg}"),
+            ConfigError::InvalidFixtures(msg) => write!(f, "Invalid fixtures: {msg}"),
+            ConfigError::InvalidFaults(msg) => write!(f, "Invalid faults: {msg}"),
+            ConfigError::InvalidModels(msg) => write!(f, "Invalid models: {msg}"),
+            ConfigError::InvalidLatency(msg) => write!(f, "Invalid latency: {msg}"),
+            ConfigError::InvalidRateLimit(msg) => write!(f, "Invalid rate limit: {msg}"),
+            ConfigError::InvalidUpstream(msg) => write!(f, "Invalid upstream: {msg}"),
+            ConfigError::InvalidCassette(msg) => write!(f, "Invalid cassette: {msg}"),
         }
     }
 }
@@ -277,10 +694,22 @@ mod tests {
         assert_eq!(config.host, DEFAULT_HOST);
         assert_eq!(config.port, DEFAULT_PORT);
         assert_eq!(config.api_key, DEFAULT_API_KEY);
+        assert_eq!(config.admin_key, DEFAULT_ADMIN_KEY);
         assert_eq!(config.request_timeout_secs, DEFAULT_REQUEST_TIMEOUT_SECS);
         assert_eq!(config.enable_cors, DEFAULT_ENABLE_CORS);
         assert_eq!(config.enable_logging, DEFAULT_ENABLE_LOGGING);
         assert_eq!(config.log_level, DEFAULT_LOG_LEVEL);
+        assert_eq!(config.journal_capacity, DEFAULT_JOURNAL_CAPACITY);
+        assert_eq!(config.embedding_mode, EmbeddingMode::Hash);
+    }
+
+    #[test]
+    fn test_embedding_mode_argument() {
+        let config =
+            Config::try_parse_from(["openai-mock", "--embedding-mode", "semantic"]).unwrap();
+        assert_eq!(config.embedding_mode, EmbeddingMode::Semantic);
+
+        assert!(Config::try_parse_from(["openai-mock", "--embedding-mode", "fuzzy"]).is_err());
     }
 
     #[test]
@@ -325,6 +754,20 @@ mod tests {
             Err(ConfigError::InvalidApiKey(_))
         ));
 
+        // Invalid admin key (empty)
+        let config = Config::builder().admin_key("").build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidAdminKey(_))
+        ));
+
+        // Invalid admin key (same as API key)
+        let config = Config::builder().admin_key(DEFAULT_API_KEY).build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidAdminKey(_))
+        ));
+
         // Invalid timeout
         let config = Config::builder().request_timeout_secs(0).build();
         assert!(matches!(
@@ -332,12 +775,110 @@ mod tests {
             Err(ConfigError::InvalidTimeout(_))
         ));
 
+        // Invalid CORS origin and method
+        let config = Config::builder()
+            .cors_allowed_origins(["example.com"])
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidCors(_))
+        ));
+
+        let config = Config::builder()
+            .cors_allowed_methods(["GET", "NOT A METHOD"])
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidCors(_))
+        ));
+
         // Invalid log level
         let config = Config::builder().log_level("invalid").build();
         assert!(matches!(
             config.validate(),
             Err(ConfigError::InvalidLogLevel(_))
         ));
+
+        // Missing fixtures file
+        let config = Config::builder()
+            .fixtures_path("/nonexistent/fixtures.yaml")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidFixtures(_))
+        ));
+
+        // Missing faults file
+        let config = Config::builder()
+            .faults_path("/nonexistent/faults.yaml")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidFaults(_))
+        ));
+
+        // Missing models file
+        let config = Config::builder()
+            .models_path("/nonexistent/models.yaml")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidModels(_))
+        ));
+
+        // Missing latency file
+        let config = Config::builder()
+            .latency_path("/nonexistent/latency.yaml")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidLatency(_))
+        ));
+
+        // Zero rate limit
+        let config = Config::builder().rate_limit_rpm(0).build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidRateLimit(_))
+        ));
+
+        // Record and replay together
+        let config = Config::builder()
+            .record("http://localhost:8080/v1")
+            .replay("cassette.jsonl")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidCassette(_))
+        ));
+
+        // Invalid upstream URL
+        let config = Config::builder()
+            .record("api.openai.com")
+            .upstream_api_key("sk-upstream")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidUpstream(_))
+        ));
+
+        // Record mode without an upstream API key
+        let config = Config::builder()
+            .record("https://api.openai.com/v1")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidUpstream(_))
+        ));
+
+        // Missing replay cassette
+        let config = Config::builder()
+            .replay("/nonexistent/cassette.jsonl")
+            .build();
+        assert!(matches!(
+            config.validate(),
+            Err(ConfigError::InvalidCassette(_))
+        ));
     }
 
     #[test]
@@ -0,0 +1,270 @@
+//! # Runtime Response Expectations
+//!
+//! This module holds response expectations registered at runtime through the admin API
+//! (`/admin/expectations`). An expectation is a [fixture](crate::fixtures) with an ID and an
+//! optional number of times it may be used. Expectations are checked in registration order
+//! before the fixtures loaded from the `--fixtures` file, so tests sharing one server can script
+//! each scenario in place without restarting it.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use openai_mock::expectations::{CreateExpectationRequest, ExpectationStore};
+//!
+//! let store = ExpectationStore::default();
+//! let request: CreateExpectationRequest = serde_json::from_str(
+//!     r#"{"match": {"model": "gpt-4o"}, "response": {"content": "Scripted"}, "times": 1}"#,
+//! )
+//! .unwrap();
+//!
+//! let expectation = store.add(request).unwrap();
+//! assert!(expectation.id.starts_with("exp_"));
+//! assert_eq!(store.list().len(), 1);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use uuid::Uuid;
+
+use crate::fixtures::{Fixture, FixtureDefinition, FixtureError};
+use crate::models::requests::CreateChatCompletionRequest;
+
+/// Request body for registering an expectation
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct CreateExpectationRequest {
+    /// The fixture to answer with
+    #[serde(flatten)]
+    pub fixture: FixtureDefinition,
+
+    /// How many requests the expectation answers before it is removed (unlimited if omitted)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub times: Option<u32>,
+}
+
+/// A registered expectation, as reported by the admin API
+#[derive(Debug, Clone, Serialize)]
+pub struct ExpectationInfo {
+    /// Unique expectation ID
+    pub id: String,
+
+    /// The fixture the expectation answers with
+    #[serde(flatten)]
+    pub fixture: FixtureDefinition,
+
+    /// How many more requests the expectation answers (unlimited if absent)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u32>,
+
+    /// How many requests the expectation has answered so far
+    pub match_count: u32,
+}
+
+/// A registered expectation
+#[derive(Debug, Clone)]
+struct Expectation {
+    id: String,
+    fixture: Fixture,
+    remaining: Option<u32>,
+    match_count: u32,
+}
+
+impl Expectation {
+    fn info(&self) -> ExpectationInfo {
+        ExpectationInfo {
+            id: self.id.clone(),
+            fixture: self.fixture.definition().clone(),
+            remaining: self.remaining,
+            match_count: self.match_count,
+        }
+    }
+}
+
+/// Thread-safe store of runtime expectations
+#[derive(Debug, Default)]
+pub struct ExpectationStore {
+    expectations: RwLock<Vec<Expectation>>,
+}
+
+impl ExpectationStore {
+    /// Register a new expectation
+    pub fn add(&self, request: CreateExpectationRequest) -> Result<ExpectationInfo, FixtureError> {
+        if request.times == Some(0) {
+            return Err(FixtureError::InvalidFixture(
+                "'times' must be at least 1".to_string(),
+            ));
+        }
+
+        let expectation = Expectation {
+            id: format!("exp_{}", &Uuid::new_v4().to_string().replace("-", "")[..24]),
+            fixture: Fixture::new(request.fixture)?,
+            remaining: request.times,
+            match_count: 0,
+        };
+        let info = expectation.info();
+
+        self.write().push(expectation);
+        Ok(info)
+    }
+
+    /// List all registered expectations in matching order
+    pub fn list(&self) -> Vec<ExpectationInfo> {
+        self.read().iter().map(Expectation::info).collect()
+    }
+
+    /// Remove an expectation by ID, returning whether it existed
+    pub fn remove(&self, id: &str) -> bool {
+        let mut expectations = self.write();
+        let before = expectations.len();
+        expectations.retain(|expectation| expectation.id != id);
+        expectations.len() != before
+    }
+
+    /// Remove all expectations, returning how many were removed
+    pub fn clear(&self) -> usize {
+        let mut expectations = self.write();
+        let count = expectations.len();
+        expectations.clear();
+        count
+    }
+
+    /// Find the first expectation matching a request and record the use
+    ///
+    /// Expectations whose `times` are used up are removed.
+    pub fn take_match(&self, request: &CreateChatCompletionRequest) -> Option<Fixture> {
+        let mut expectations = self.write();
+        let position = expectations
+            .iter()
+            .position(|expectation| expectation.fixture.matches(request))?;
+
+        let expectation = &mut expectations[position];
+        expectation.match_count += 1;
+        let fixture = expectation.fixture.clone();
+
+        if let Some(remaining) = expectation.remaining.as_mut() {
+            *remaining -= 1;
+            if *remaining == 0 {
+                expectations.remove(position);
+            }
+        }
+
+        Some(fixture)
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, Vec<Expectation>> {
+        self.expectations
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, Vec<Expectation>> {
+        self.expectations
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::{ChatCompletionMessage, ChatCompletionRole};
+
+    fn create_test_request(model: &str) -> CreateChatCompletionRequest {
+        CreateChatCompletionRequest {
+            model: model.to_string(),
+            messages: vec![ChatCompletionMessage {
+                role: ChatCompletionRole::User,
+                content: Some("Hello".into()),
+                name: None,
+                tool_calls: None,
+                tool_call_id: None,
+            }],
+            temperature: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stream_options: None,
+            stop: None,
+            max_tokens: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            logit_bias: None,
+            user: None,
+            response_format: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+        }
+    }
+
+    fn expectation(json: serde_json::Value) -> CreateExpectationRequest {
+        serde_json::from_value(json).unwrap()
+    }
+
+    #[test]
+    fn test_add_list_remove() {
+        let store = ExpectationStore::default();
+        let first = store
+            .add(expectation(serde_json::json!({
+                "name": "first",
+                "response": {"content": "one"}
+            })))
+            .unwrap();
+        store
+            .add(expectation(serde_json::json!({
+                "response": {"content": "two"}
+            })))
+            .unwrap();
+
+        let listed = store.list();
+        assert_eq!(listed.len(), 2);
+        assert_eq!(listed[0].id, first.id);
+        assert_eq!(listed[0].fixture.name.as_deref(), Some("first"));
+
+        assert!(store.remove(&first.id));
+        assert!(!store.remove(&first.id));
+        assert_eq!(store.clear(), 1);
+        assert!(store.list().is_empty());
+    }
+
+    #[test]
+    fn test_take_match_respects_times() {
+        let store = ExpectationStore::default();
+        store
+            .add(expectation(serde_json::json!({
+                "match": {"model": "gpt-4o"},
+                "response": {"content": "scripted"},
+                "times": 2
+            })))
+            .unwrap();
+
+        assert!(store.take_match(&create_test_request("gpt-4")).is_none());
+
+        let request = create_test_request("gpt-4o");
+        let fixture = store.take_match(&request).unwrap();
+        assert_eq!(fixture.response().content.as_deref(), Some("scripted"));
+        assert_eq!(store.list()[0].remaining, Some(1));
+        assert_eq!(store.list()[0].match_count, 1);
+
+        assert!(store.take_match(&request).is_some());
+        assert!(store.take_match(&request).is_none());
+        assert!(store.list().is_empty());
+    }
+
+    #[test]
+    fn test_add_invalid_expectation() {
+        let store = ExpectationStore::default();
+
+        let result = store.add(expectation(serde_json::json!({
+            "response": {"content": "a"},
+            "times": 0
+        })));
+        assert!(result.is_err());
+
+        let result = store.add(expectation(serde_json::json!({
+            "match": {"user_message": "("},
+            "response": {"content": "a"}
+        })));
+        assert!(matches!(result, Err(FixtureError::InvalidRegex(_))));
+        assert!(store.list().is_empty());
+    }
+}
@@ -0,0 +1,604 @@
+//! # Fault Injection
+//!
+//! This module makes the mock fail on purpose so client retry and backoff logic can be tested.
+//! Fault rules are loaded from a YAML or JSON file given by the `--faults` configuration option
+//! and applied by [`FaultMiddleware`] in front of the API handlers. For each request, the first
+//! rule that matches and triggers decides the fault; requests no rule triggers on are served
+//! normally.
+//!
+//! ## File Format
+//!
+//! ```yaml
+//! faults:
+//!   - name: throttled-chat
+//!     match:
+//!       endpoint: chat/completions
+//!       model: gpt-4o
+//!     every: 3            # fail every 3rd matching request
+//!     fault:
+//!       kind: rate_limit
+//!       retry_after: 2    # seconds, sent as the retry-after header
+//!
+//!   - name: flaky-embeddings
+//!     match:
+//!       endpoint: embeddings
+//!     probability: 0.1    # fail 10% of matching requests
+//!     fault:
+//!       kind: server_error
+//! ```
+//!
+//! A rule without `every` or `probability` triggers on every matching request.
+//!
+//! ## Fault Kinds
+//!
+//! - `rate_limit`: `429` with code `rate_limit_exceeded` and a `retry-after` header
+//! - `server_error`: `500` with type `server_error`
+//! - `overloaded`: `503` with code `overloaded`
+//! - `timeout`: hangs for `delay_ms` (default 60 seconds), then returns `504`
+//! - `drop`: closes the connection without sending a complete response
+//!
+//! The `status` and `message` of HTTP faults can be overridden per rule.
+
+use poem::{
+    Body, Endpoint, IntoResponse, Middleware, Request, Response, Result,
+    http::{StatusCode, header},
+};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use crate::journal::normalize_endpoint;
+use crate::models::responses::ErrorResponse;
+
+/// Default time a `timeout` fault hangs before responding
+pub const DEFAULT_TIMEOUT_DELAY_MS: u64 = 60_000;
+
+/// Default `retry-after` value, in seconds, sent with `rate_limit` faults
+pub const DEFAULT_RETRY_AFTER_SECS: u64 = 1;
+
+/// The on-disk representation of a faults file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FaultFile {
+    /// The fault rules, in matching order
+    #[serde(default)]
+    pub faults: Vec<FaultRule>,
+}
+
+/// A fault rule as written in a faults file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FaultRule {
+    /// An optional name used in logs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The requests this rule applies to
+    #[serde(default, rename = "match")]
+    pub matcher: FaultMatcher,
+
+    /// Trigger on every Nth matching request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub every: Option<u64>,
+
+    /// Trigger on matching requests with this probability (0.0 to 1.0)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub probability: Option<f64>,
+
+    /// The fault to inject
+    pub fault: Fault,
+}
+
+/// Conditions a request must meet for a fault rule to apply
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FaultMatcher {
+    /// Endpoint relative to `/v1` (e.g. `chat/completions`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub endpoint: Option<String>,
+
+    /// Exact model name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+}
+
+/// The fault injected when a rule triggers
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Fault {
+    /// The kind of fault
+    pub kind: FaultKind,
+
+    /// Override the HTTP status of the error response
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<u16>,
+
+    /// Override the error message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+
+    /// Value of the `retry-after` header, in seconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_after: Option<u64>,
+
+    /// How long a `timeout` fault hangs, in milliseconds
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub delay_ms: Option<u64>,
+}
+
+/// Kinds of injectable faults
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FaultKind {
+    RateLimit,
+    ServerError,
+    Overloaded,
+    Timeout,
+    Drop,
+}
+
+impl Fault {
+    /// Build the error response for this fault
+    ///
+    /// `drop` faults have no response; the middleware closes the connection instead.
+    pub fn to_response(&self) -> Response {
+        let (status, message, error_type, code) = match self.kind {
+            FaultKind::RateLimit => (
+                StatusCode::TOO_MANY_REQUESTS,
+                "Rate limit reached for requests. Please try again later.",
+                "requests",
+                Some("rate_limit_exceeded"),
+            ),
+            FaultKind::ServerError => (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "The server had an error while processing your request. Sorry about that!",
+                "server_error",
+                None,
+            ),
+            FaultKind::Overloaded => (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "The engine is currently overloaded, please try again later.",
+                "server_error",
+                Some("overloaded"),
+            ),
+            FaultKind::Timeout | FaultKind::Drop => (
+                StatusCode::GATEWAY_TIMEOUT,
+                "The server timed out while processing your request.",
+                "server_error",
+                Some("timeout"),
+            ),
+        };
+
+        let status = self
+            .status
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .unwrap_or(status);
+        let message = self.message.clone().unwrap_or_else(|| message.to_string());
+
+        let mut error_response = ErrorResponse::new(message, error_type.to_string());
+        if let Some(code) = code {
+            error_response = error_response.with_code(code.to_string());
+        }
+
+        let json_body = match serde_json::to_string(&error_response) {
+            Ok(json) => json,
+            Err(_) => {
+                r#"{"error":{"message":"Internal server error","type":"server_error"}}"#.to_string()
+            }
+        };
+
+        let mut builder = Response::builder()
+            .status(status)
+            .header("content-type", "application/json");
+        let retry_after = match self.kind {
+            FaultKind::RateLimit => Some(self.retry_after.unwrap_or(DEFAULT_RETRY_AFTER_SECS)),
+            _ => self.retry_after,
+        };
+        if let Some(retry_after) = retry_after {
+            builder = builder.header("retry-after", retry_after.to_string());
+        }
+        builder.body(json_body)
+    }
+}
+
+/// A fault rule with its trigger counter
+#[derive(Debug)]
+struct ActiveRule {
+    rule: FaultRule,
+    matched: AtomicU64,
+}
+
+impl ActiveRule {
+    fn matches(&self, endpoint: &str, model: Option<&str>) -> bool {
+        let matcher = &self.rule.matcher;
+
+        if let Some(expected) = &matcher.endpoint
+            && normalize_endpoint(expected) != endpoint
+        {
+            return false;
+        }
+
+        if let Some(expected) = &matcher.model
+            && model != Some(expected.as_str())
+        {
+            return false;
+        }
+
+        true
+    }
+
+    /// Count a matching request and decide whether the fault triggers
+    fn triggers(&self) -> bool {
+        let count = self.matched.fetch_add(1, Ordering::Relaxed) + 1;
+
+        if let Some(every) = self.rule.every
+            && !count.is_multiple_of(every)
+        {
+            return false;
+        }
+
+        if let Some(probability) = self.rule.probability {
+            return rand::thread_rng().gen_bool(probability);
+        }
+
+        true
+    }
+}
+
+/// A set of fault rules checked in order against incoming requests
+#[derive(Debug, Default)]
+pub struct FaultInjector {
+    rules: Vec<ActiveRule>,
+}
+
+impl FaultInjector {
+    /// Create a fault injector from fault rules
+    pub fn new(rules: Vec<FaultRule>) -> Result<Self, FaultError> {
+        for rule in &rules {
+            let label = rule.name.as_deref().unwrap_or("<unnamed>");
+            if rule.every == Some(0) {
+                return Err(FaultError::InvalidRule(format!(
+                    "fault '{label}': 'every' must be at least 1"
+                )));
+            }
+            if let Some(probability) = rule.probability
+                && !(0.0..=1.0).contains(&probability)
+            {
+                return Err(FaultError::InvalidRule(format!(
+                    "fault '{label}': 'probability' must be between 0 and 1"
+                )));
+            }
+            if let Some(status) = rule.fault.status
+                && !(400..=599).contains(&status)
+            {
+                return Err(FaultError::InvalidRule(format!(
+                    "fault '{label}': status {status} is not an HTTP error status"
+                )));
+            }
+        }
+
+        Ok(Self {
+            rules: rules
+                .into_iter()
+                .map(|rule| ActiveRule {
+                    rule,
+                    matched: AtomicU64::new(0),
+                })
+                .collect(),
+        })
+    }
+
+    /// Load fault rules from a YAML (`.yaml`/`.yml`) or JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FaultError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| FaultError::Io(format!("{}: {e}", path.display())))?;
+
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+
+        let file: FaultFile = if is_yaml {
+            serde_yaml::from_str(&contents).map_err(|e| FaultError::Parse(e.to_string()))?
+        } else {
+            serde_json::from_str(&contents).map_err(|e| FaultError::Parse(e.to_string()))?
+        };
+        Self::new(file.faults)
+    }
+
+    /// Whether the injector holds no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+
+    /// Find the fault to inject for a request, if any
+    ///
+    /// Rules are checked in order and the first that triggers wins. Each rule counts the
+    /// matching requests it is checked against, so rules after a triggered one do not see
+    /// the request.
+    pub fn check(&self, endpoint: &str, model: Option<&str>) -> Option<&FaultRule> {
+        self.rules
+            .iter()
+            .find(|active| active.matches(endpoint, model) && active.triggers())
+            .map(|active| &active.rule)
+    }
+}
+
+/// Middleware that injects faults into API responses
+#[derive(Clone, Default)]
+pub struct FaultMiddleware {
+    injector: Arc<FaultInjector>,
+}
+
+impl FaultMiddleware {
+    /// Create a new fault middleware with the given injector
+    pub fn new(injector: FaultInjector) -> Self {
+        Self {
+            injector: Arc::new(injector),
+        }
+    }
+}
+
+impl<E> Middleware<E> for FaultMiddleware
+where
+    E: Endpoint,
+{
+    type Output = FaultEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        FaultEndpoint {
+            inner: ep,
+            injector: self.injector.clone(),
+        }
+    }
+}
+
+/// Wrapper endpoint that performs fault injection
+pub struct FaultEndpoint<E> {
+    inner: E,
+    injector: Arc<FaultInjector>,
+}
+
+impl<E> Endpoint for FaultEndpoint<E>
+where
+    E: Endpoint,
+{
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        if self.injector.is_empty() {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        }
+
+        // Read the model from the body, then restore the body for the handler
+        let bytes = req.take_body().into_bytes().await.unwrap_or_default();
+        let model = serde_json::from_slice::<serde_json::Value>(&bytes)
+            .ok()
+            .and_then(|body| body.get("model")?.as_str().map(str::to_string));
+        req.set_body(bytes);
+
+        let endpoint = normalize_endpoint(req.uri().path());
+        let Some(rule) = self.injector.check(&endpoint, model.as_deref()) else {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        };
+
+        tracing::debug!(
+            "Injecting {:?} fault {:?} into {endpoint}",
+            rule.fault.kind,
+            rule.name
+        );
+
+        match rule.fault.kind {
+            FaultKind::Timeout => {
+                let delay = rule.fault.delay_ms.unwrap_or(DEFAULT_TIMEOUT_DELAY_MS);
+                tokio::time::sleep(Duration::from_millis(delay)).await;
+                Ok(rule.fault.to_response())
+            }
+            FaultKind::Drop => {
+                // A body that fails on its first read makes the server close this connection
+                // without sending a response. The declared length matters: a chunked body would
+                // be ended cleanly instead.
+                let error = futures::stream::once(async {
+                    Err::<Vec<u8>, _>(io::Error::new(
+                        io::ErrorKind::ConnectionAborted,
+                        "connection dropped by fault injection",
+                    ))
+                });
+                Ok(Response::builder()
+                    .header(header::CONTENT_LENGTH, 1)
+                    .body(Body::from_bytes_stream(error)))
+            }
+            _ => Ok(rule.fault.to_response()),
+        }
+    }
+}
+
+/// Fault configuration error types
+#[derive(Debug, Clone)]
+pub enum FaultError {
+    Io(String),
+    Parse(String),
+    InvalidRule(String),
+}
+
+impl fmt::Display for FaultError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FaultError::Io(msg) => write!(f, "Failed to read faults: {msg}"),
+            FaultError::Parse(msg) => write!(f, "Failed to parse faults: {msg}"),
+            FaultError::InvalidRule(msg) => write!(f, "Invalid fault rule: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for FaultError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{EndpointExt, Route, handler, post, test::TestClient};
+
+    #[handler]
+    async fn ok_handler() -> &'static str {
+        "ok"
+    }
+
+    fn rules(json: serde_json::Value) -> FaultInjector {
+        let file: FaultFile = serde_json::from_value(json).unwrap();
+        FaultInjector::new(file.faults).unwrap()
+    }
+
+    #[test]
+    fn test_every_nth_request() {
+        let injector = rules(serde_json::json!({
+            "faults": [{"every": 3, "fault": {"kind": "server_error"}}]
+        }));
+
+        let triggered: Vec<bool> = (0..6)
+            .map(|_| injector.check("completions", None).is_some())
+            .collect();
+        assert_eq!(triggered, vec![false, false, true, false, false, true]);
+    }
+
+    #[test]
+    fn test_first_triggered_rule_wins() {
+        let injector = rules(serde_json::json!({
+            "faults": [
+                {"match": {"model": "gpt-4"}, "fault": {"kind": "overloaded"}},
+                {"every": 3, "fault": {"kind": "server_error"}}
+            ]
+        }));
+
+        // Requests the first rule faults do not count towards the second rule's `every`
+        let kinds: Vec<_> = ["gpt-4", "gpt-4o", "gpt-4", "gpt-4o", "gpt-4o", "gpt-4"]
+            .into_iter()
+            .map(|model| {
+                injector
+                    .check("completions", Some(model))
+                    .map(|rule| rule.fault.kind)
+            })
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                Some(FaultKind::Overloaded),
+                None,
+                Some(FaultKind::Overloaded),
+                None,
+                Some(FaultKind::ServerError),
+                Some(FaultKind::Overloaded),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_match_endpoint_and_model() {
+        let injector = rules(serde_json::json!({
+            "faults": [{
+                "match": {"endpoint": "/v1/chat/completions", "model": "gpt-4"},
+                "fault": {"kind": "overloaded"}
+            }]
+        }));
+
+        assert!(injector.check("chat/completions", Some("gpt-4")).is_some());
+        assert!(injector.check("chat/completions", Some("gpt-4o")).is_none());
+        assert!(injector.check("embeddings", Some("gpt-4")).is_none());
+    }
+
+    #[test]
+    fn test_probability_bounds() {
+        let always = rules(serde_json::json!({
+            "faults": [{"probability": 1.0, "fault": {"kind": "server_error"}}]
+        }));
+        let never = rules(serde_json::json!({
+            "faults": [{"probability": 0.0, "fault": {"kind": "server_error"}}]
+        }));
+
+        for _ in 0..10 {
+            assert!(always.check("completions", None).is_some());
+            assert!(never.check("completions", None).is_none());
+        }
+    }
+
+    #[test]
+    fn test_invalid_rules() {
+        let file: FaultFile = serde_json::from_value(serde_json::json!({
+            "faults": [{"every": 0, "fault": {"kind": "drop"}}]
+        }))
+        .unwrap();
+        assert!(matches!(
+            FaultInjector::new(file.faults),
+            Err(FaultError::InvalidRule(_))
+        ));
+
+        let file: FaultFile = serde_json::from_value(serde_json::json!({
+            "faults": [{"probability": 1.5, "fault": {"kind": "drop"}}]
+        }))
+        .unwrap();
+        assert!(matches!(
+            FaultInjector::new(file.faults),
+            Err(FaultError::InvalidRule(_))
+        ));
+
+        assert!(matches!(
+            FaultInjector::load("/nonexistent/faults.yaml"),
+            Err(FaultError::Io(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_response() {
+        let app = Route::new()
+            .at("/v1/chat/completions", post(ok_handler))
+            .with(FaultMiddleware::new(rules(serde_json::json!({
+                "faults": [{"fault": {"kind": "rate_limit", "retry_after": 7}}]
+            }))));
+        let client = TestClient::new(app);
+
+        let resp = client
+            .post("/v1/chat/completions")
+            .body_json(&serde_json::json!({"model": "gpt-4o"}))
+            .send()
+            .await;
+        resp.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        resp.assert_header("retry-after", "7");
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_passthrough() {
+        let app = Route::new()
+            .at("/v1/completions", post(ok_handler))
+            .at("/v1/embeddings", post(ok_handler))
+            .with(FaultMiddleware::new(rules(serde_json::json!({
+                "faults": [{
+                    "match": {"endpoint": "completions"},
+                    "fault": {"kind": "timeout", "delay_ms": 10}
+                }]
+            }))));
+        let client = TestClient::new(app);
+
+        let resp = client.post("/v1/completions").send().await;
+        resp.assert_status(StatusCode::GATEWAY_TIMEOUT);
+
+        let resp = client.post("/v1/embeddings").send().await;
+        resp.assert_status_is_ok();
+        resp.assert_text("ok").await;
+    }
+
+    #[tokio::test]
+    async fn test_drop_response_body_fails() {
+        let app = Route::new()
+            .at("/v1/embeddings", post(ok_handler))
+            .with(FaultMiddleware::new(rules(serde_json::json!({
+                "faults": [{"fault": {"kind": "drop"}}]
+            }))));
+        let client = TestClient::new(app);
+
+        let resp = client.post("/v1/embeddings").send().await;
+        assert!(resp.0.into_body().into_bytes().await.is_err());
+    }
+}
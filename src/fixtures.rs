@@ -0,0 +1,605 @@
+//! # Scripted Response Fixtures
+//!
+//! This module lets tests script exactly what the mock answers to a chat completion request.
+//! Fixtures are loaded from a YAML or JSON file given by the `--fixtures` configuration option
+//! and are checked in file order before the built-in generators run. The first fixture whose
+//! matcher accepts the request decides the reply; requests that match no fixture fall back to
+//! the keyword-based responses of [`ChatCompletionGenerator`](crate::generators::ChatCompletionGenerator).
+//!
+//! ## File Format
+//!
+//! ```yaml
+//! fixtures:
+//!   - name: greeting
+//!     match:
+//!       model: gpt-4o
+//!       user_message: "(?i)^hello"
+//!     response:
+//!       content: "Hi! This reply comes from a fixture."
+//!
+//!   - name: weather-tool
+//!     match:
+//!       tool: get_weather
+//!     response:
+//!       tool_calls:
+//!         - name: get_weather
+//!           arguments: {"location": "Paris, France"}
+//!
+//!   - name: overloaded
+//!     match:
+//!       system_prompt: "simulate outage"
+//!     response:
+//!       error:
+//!         status: 503
+//!         message: "The server is overloaded"
+//!         type: server_error
+//! ```
+//!
+//! ## Matchers
+//!
+//! All matcher fields are optional and every field that is present must match:
+//!
+//! - `model`: exact model name
+//! - `user_message`: regular expression searched in the last user message
+//! - `system_prompt`: regular expression searched in the system messages
+//! - `has_tools`: whether the request declares any tools
+//! - `tool`: name of a tool the request must declare
+//!
+//! ## Responses
+//!
+//! Each fixture returns exactly one of `content`, `tool_calls` or `error`.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::Path;
+
+use crate::models::{
+    requests::{ChatCompletionRole, CreateChatCompletionRequest},
+    responses::ErrorResponse,
+};
+
+/// A set of fixtures checked in order against incoming requests
+#[derive(Debug, Clone, Default)]
+pub struct FixtureSet {
+    fixtures: Vec<Fixture>,
+}
+
+/// The on-disk representation of a fixture file
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FixtureFile {
+    /// The fixtures, in matching order
+    #[serde(default)]
+    pub fixtures: Vec<FixtureDefinition>,
+}
+
+/// A single fixture as written in a fixture file
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FixtureDefinition {
+    /// An optional name used in logs and error messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+
+    /// The conditions a request must meet for this fixture to apply
+    #[serde(default, rename = "match")]
+    pub matcher: FixtureMatcher,
+
+    /// The reply returned when the fixture matches
+    pub response: FixtureResponse,
+}
+
+/// Conditions a request must meet for a fixture to apply
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FixtureMatcher {
+    /// Exact model name
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// Regular expression searched in the last user message
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_message: Option<String>,
+
+    /// Regular expression searched in the system messages
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+
+    /// Whether the request declares any tools
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub has_tools: Option<bool>,
+
+    /// Name of a tool the request must declare
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<String>,
+}
+
+/// The reply a fixture returns: exactly one of `content`, `tool_calls` or `error`
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct FixtureResponse {
+    /// Fixed assistant message content
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+
+    /// Tool calls the assistant makes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<FixtureToolCall>>,
+
+    /// An API error returned instead of a completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<FixtureErrorReply>,
+}
+
+/// A tool call returned by a fixture
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FixtureToolCall {
+    /// The name of the function to call
+    pub name: String,
+
+    /// The arguments, either as a JSON object or as an already encoded JSON string
+    #[serde(default = "empty_arguments")]
+    pub arguments: serde_json::Value,
+}
+
+/// An API error returned by a fixture
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct FixtureErrorReply {
+    /// The HTTP status code of the error response
+    #[serde(default = "default_error_status")]
+    pub status: u16,
+
+    /// The error message
+    pub message: String,
+
+    /// The error type
+    #[serde(rename = "type", default = "default_error_type")]
+    pub error_type: String,
+
+    /// The error code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<String>,
+
+    /// The parameter the error relates to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub param: Option<String>,
+}
+
+fn empty_arguments() -> serde_json::Value {
+    serde_json::Value::Object(serde_json::Map::new())
+}
+
+fn default_error_status() -> u16 {
+    400
+}
+
+fn default_error_type() -> String {
+    "invalid_request_error".to_string()
+}
+
+/// A fixture with its regular expressions compiled
+#[derive(Debug, Clone)]
+pub struct Fixture {
+    definition: FixtureDefinition,
+    user_message: Option<Regex>,
+    system_prompt: Option<Regex>,
+}
+
+impl Fixture {
+    /// Compile and validate a fixture definition
+    pub fn new(definition: FixtureDefinition) -> Result<Self, FixtureError> {
+        let label = definition
+            .name
+            .clone()
+            .unwrap_or_else(|| "<unnamed>".to_string());
+
+        let response = &definition.response;
+        let replies = [
+            response.content.is_some(),
+            response.tool_calls.is_some(),
+            response.error.is_some(),
+        ];
+        if replies.iter().filter(|set| **set).count() != 1 {
+            return Err(FixtureError::InvalidFixture(format!(
+                "fixture '{label}' must define exactly one of content, tool_calls or error"
+            )));
+        }
+
+        if let Some(tool_calls) = &response.tool_calls {
+            if tool_calls.is_empty() {
+                return Err(FixtureError::InvalidFixture(format!(
+                    "fixture '{label}' must define at least one tool call"
+                )));
+            }
+            if let Some(call) = tool_calls
+                .iter()
+                .find(|call| !call.arguments.is_object() && !call.arguments.is_string())
+            {
+                return Err(FixtureError::InvalidFixture(format!(
+                    "fixture '{label}': arguments of tool call '{}' must be an object or a string",
+                    call.name
+                )));
+            }
+        }
+
+        if let Some(error) = &response.error
+            && !(400..=599).contains(&error.status)
+        {
+            return Err(FixtureError::InvalidFixture(format!(
+                "fixture '{label}': error status {} is not an HTTP error status",
+                error.status
+            )));
+        }
+
+        let compile = |pattern: &Option<String>| {
+            pattern
+                .as_deref()
+                .map(Regex::new)
+                .transpose()
+                .map_err(|e| FixtureError::InvalidRegex(format!("fixture '{label}': {e}")))
+        };
+        let user_message = compile(&definition.matcher.user_message)?;
+        let system_prompt = compile(&definition.matcher.system_prompt)?;
+
+        Ok(Self {
+            definition,
+            user_message,
+            system_prompt,
+        })
+    }
+
+    /// The fixture's name, if it has one
+    pub fn name(&self) -> Option<&str> {
+        self.definition.name.as_deref()
+    }
+
+    /// The definition this fixture was compiled from
+    pub fn definition(&self) -> &FixtureDefinition {
+        &self.definition
+    }
+
+    /// The reply this fixture returns
+    pub fn response(&self) -> &FixtureResponse {
+        &self.definition.response
+    }
+
+    /// Check whether this fixture applies to a chat completion request
+    pub fn matches(&self, request: &CreateChatCompletionRequest) -> bool {
+        let matcher = &self.definition.matcher;
+
+        if let Some(model) = &matcher.model
+            && model != &request.model
+        {
+            return false;
+        }
+
+        if let Some(regex) = &self.user_message {
+            let last_user_message = request
+                .messages
+                .iter()
+                .rev()
+                .find(|msg| matches!(msg.role, ChatCompletionRole::User))
+                .and_then(|msg| msg.text());
+            if !last_user_message.is_some_and(|content| regex.is_match(&content)) {
+                return false;
+            }
+        }
+
+        if let Some(regex) = &self.system_prompt {
+            let any_system_match = request
+                .messages
+                .iter()
+                .filter(|msg| matches!(msg.role, ChatCompletionRole::System))
+                .filter_map(|msg| msg.text())
+                .any(|content| regex.is_match(&content));
+            if !any_system_match {
+                return false;
+            }
+        }
+
+        let tools = request.tools.as_deref().unwrap_or_default();
+
+        if let Some(has_tools) = matcher.has_tools
+            && has_tools == tools.is_empty()
+        {
+            return false;
+        }
+
+        if let Some(tool) = &matcher.tool
+            && !tools.iter().any(|t| &t.function.name == tool)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+impl FixtureToolCall {
+    /// The arguments encoded as a JSON string, as sent in tool calls
+    pub fn arguments_json(&self) -> String {
+        match &self.arguments {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        }
+    }
+}
+
+impl FixtureErrorReply {
+    /// The OpenAI error body returned for this fixture
+    pub fn to_error_response(&self) -> ErrorResponse {
+        let mut error = ErrorResponse::new(self.message.clone(), self.error_type.clone());
+        if let Some(param) = &self.param {
+            error = error.with_param(param.clone());
+        }
+        if let Some(code) = &self.code {
+            error = error.with_code(code.clone());
+        }
+        error
+    }
+}
+
+impl FixtureSet {
+    /// Create a fixture set from fixture definitions
+    pub fn new(definitions: Vec<FixtureDefinition>) -> Result<Self, FixtureError> {
+        let fixtures = definitions
+            .into_iter()
+            .map(Fixture::new)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Self { fixtures })
+    }
+
+    /// Load fixtures from a YAML (`.yaml`/`.yml`) or JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, FixtureError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| FixtureError::Io(format!("{}: {e}", path.display())))?;
+
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+
+        if is_yaml {
+            Self::from_yaml(&contents)
+        } else {
+            Self::from_json(&contents)
+        }
+    }
+
+    /// Parse fixtures from a YAML document
+    pub fn from_yaml(contents: &str) -> Result<Self, FixtureError> {
+        let file: FixtureFile =
+            serde_yaml::from_str(contents).map_err(|e| FixtureError::Parse(e.to_string()))?;
+        Self::new(file.fixtures)
+    }
+
+    /// Parse fixtures from a JSON document
+    pub fn from_json(contents: &str) -> Result<Self, FixtureError> {
+        let file: FixtureFile =
+            serde_json::from_str(contents).map_err(|e| FixtureError::Parse(e.to_string()))?;
+        Self::new(file.fixtures)
+    }
+
+    /// Find the first fixture that applies to a chat completion request
+    pub fn find_match(&self, request: &CreateChatCompletionRequest) -> Option<&Fixture> {
+        self.fixtures
+            .iter()
+            .find(|fixture| fixture.matches(request))
+    }
+
+    /// The fixtures in matching order
+    pub fn fixtures(&self) -> &[Fixture] {
+        &self.fixtures
+    }
+
+    /// Number of fixtures in the set
+    pub fn len(&self) -> usize {
+        self.fixtures.len()
+    }
+
+    /// Whether the set holds no fixtures
+    pub fn is_empty(&self) -> bool {
+        self.fixtures.is_empty()
+    }
+}
+
+/// Fixture loading error types
+#[derive(Debug, Clone)]
+pub enum FixtureError {
+    Io(String),
+    Parse(String),
+    InvalidRegex(String),
+    InvalidFixture(String),
+}
+
+impl fmt::Display for FixtureError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FixtureError::Io(msg) => write!(f, "Failed to read fixtures: {msg}"),
+            FixtureError::Parse(msg) => write!(f, "Failed to parse fixtures: {msg}"),
+            FixtureError::InvalidRegex(msg) => write!(f, "Invalid fixture regex: {msg}"),
+            FixtureError::InvalidFixture(msg) => write!(f, "Invalid fixture: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for FixtureError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::{
+        ChatCompletionFunction, ChatCompletionMessage, ChatCompletionTool,
+    };
+
+    fn message(role: ChatCompletionRole, content: &str) -> ChatCompletionMessage {
+        ChatCompletionMessage {
+            role,
+            content: Some(content.into()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    fn create_test_request(user_message: &str) -> CreateChatCompletionRequest {
+        CreateChatCompletionRequest {
+            model: "gpt-4o".to_string(),
+            messages: vec![
+                message(ChatCompletionRole::System, "You are a pirate."),
+                message(ChatCompletionRole::User, user_message),
+            ],
+            temperature: None,
+            top_p: None,
+            n: None,
+            stream: None,
+            stream_options: None,
+            stop: None,
+            max_tokens: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            logit_bias: None,
+            user: None,
+            response_format: None,
+            tools: None,
+            tool_choice: None,
+            parallel_tool_calls: None,
+        }
+    }
+
+    const YAML: &str = r#"
+fixtures:
+  - name: pirate-greeting
+    match:
+      system_prompt: "(?i)pirate"
+      user_message: "(?i)^hello"
+    response:
+      content: "Ahoy!"
+  - name: weather
+    match:
+      tool: get_weather
+    response:
+      tool_calls:
+        - name: get_weather
+          arguments: {"location": "Paris"}
+  - name: gpt4-outage
+    match:
+      model: gpt-4
+    response:
+      error:
+        status: 503
+        message: "The server is overloaded"
+        type: server_error
+"#;
+
+    #[test]
+    fn test_load_yaml_and_match_content() {
+        let fixtures = FixtureSet::from_yaml(YAML).unwrap();
+        assert_eq!(fixtures.len(), 3);
+
+        let request = create_test_request("Hello there");
+        let fixture = fixtures.find_match(&request).unwrap();
+        assert_eq!(fixture.name(), Some("pirate-greeting"));
+        assert_eq!(fixture.response().content.as_deref(), Some("Ahoy!"));
+
+        // User message regex must match the last user message
+        let request = create_test_request("Goodbye");
+        assert!(fixtures.find_match(&request).is_none());
+    }
+
+    #[test]
+    fn test_match_tool_presence() {
+        let fixtures = FixtureSet::from_yaml(YAML).unwrap();
+
+        let mut request = create_test_request("Is it raining?");
+        request.tools = Some(vec![ChatCompletionTool {
+            tool_type: "function".to_string(),
+            function: ChatCompletionFunction {
+                name: "get_weather".to_string(),
+                description: None,
+                parameters: None,
+                strict: None,
+            },
+        }]);
+
+        let fixture = fixtures.find_match(&request).unwrap();
+        let tool_calls = fixture.response().tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls[0].arguments_json(), r#"{"location":"Paris"}"#);
+    }
+
+    #[test]
+    fn test_match_model_error() {
+        let fixtures = FixtureSet::from_yaml(YAML).unwrap();
+
+        let mut request = create_test_request("Anything");
+        request.model = "gpt-4".to_string();
+
+        let error = fixtures
+            .find_match(&request)
+            .unwrap()
+            .response()
+            .error
+            .clone();
+        let error = error.unwrap();
+        assert_eq!(error.status, 503);
+
+        let body = error.to_error_response();
+        assert_eq!(body.error.message, "The server is overloaded");
+        assert_eq!(body.error.error_type, "server_error");
+    }
+
+    #[test]
+    fn test_load_json() {
+        let json =
+            r#"{"fixtures": [{"match": {"has_tools": false}, "response": {"content": "fixed"}}]}"#;
+        let fixtures = FixtureSet::from_json(json).unwrap();
+
+        let request = create_test_request("Hi");
+        assert_eq!(
+            fixtures
+                .find_match(&request)
+                .unwrap()
+                .response()
+                .content
+                .as_deref(),
+            Some("fixed")
+        );
+    }
+
+    #[test]
+    fn test_invalid_fixtures() {
+        // No reply
+        let json = r#"{"fixtures": [{"response": {}}]}"#;
+        assert!(matches!(
+            FixtureSet::from_json(json),
+            Err(FixtureError::InvalidFixture(_))
+        ));
+
+        // Two replies
+        let json = r#"{"fixtures": [{"response": {"content": "a", "error": {"message": "b"}}}]}"#;
+        assert!(matches!(
+            FixtureSet::from_json(json),
+            Err(FixtureError::InvalidFixture(_))
+        ));
+
+        // Bad regex
+        let json =
+            r#"{"fixtures": [{"match": {"user_message": "("}, "response": {"content": "a"}}]}"#;
+        assert!(matches!(
+            FixtureSet::from_json(json),
+            Err(FixtureError::InvalidRegex(_))
+        ));
+
+        // Not a document
+        assert!(matches!(
+            FixtureSet::from_json("not json"),
+            Err(FixtureError::Parse(_))
+        ));
+    }
+
+    #[test]
+    fn test_load_missing_file() {
+        assert!(matches!(
+            FixtureSet::load("/nonexistent/fixtures.yaml"),
+            Err(FixtureError::Io(_))
+        ));
+    }
+}
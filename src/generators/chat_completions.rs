@@ -23,7 +23,7 @@
 //!     messages: vec![
 //!         ChatCompletionMessage {
 //!             role: ChatCompletionRole::User,
-//!             content: Some("Hello!".to_string()),
+//!             content: Some("Hello!".into()),
 //!             name: None,
 //!             tool_calls: None,
 //!             tool_call_id: None,
@@ -33,29 +33,44 @@
 //!     top_p: None,
 //!     n: None,
 //!     stream: None,
+//!     stream_options: None,
 //!     stop: None,
 //!     max_tokens: None,
 //!     presence_penalty: None,
 //!     frequency_penalty: None,
 //!     logit_bias: None,
 //!     user: None,
+//!     response_format: None,
 //!     tools: None,
 //!     tool_choice: None,
+//!     parallel_tool_calls: None,
 //! };
 //!
 //! let response = ChatCompletionGenerator::generate_response(&request);
 //! ```
 
 use chrono;
+use serde_json::json;
+use std::borrow::Cow;
+use std::cmp::Reverse;
 use uuid::Uuid;
 
+use crate::fixtures::FixtureResponse;
 use crate::models::{
-    requests::{ChatCompletionMessage, ChatCompletionRole, CreateChatCompletionRequest},
+    requests::{
+        ChatCompletionContent, ChatCompletionMessage, ChatCompletionRole, ChatCompletionTool,
+        ChatCompletionToolChoice, CreateChatCompletionRequest, ResponseFormat, StreamOptions,
+    },
     responses::{
-        ChatCompletionChoice, ChatCompletionMessageToolCall, ChatCompletionResponseMessage,
-        CompletionUsage, CreateChatCompletionResponse,
+        ChatCompletionChoice, ChatCompletionChunkChoice, ChatCompletionMessageToolCall,
+        ChatCompletionMessageToolCallChunk, ChatCompletionResponseMessage,
+        ChatCompletionStreamDelta, CompletionUsage, CreateChatCompletionChunk,
+        CreateChatCompletionResponse,
     },
 };
+use crate::schema;
+use crate::streaming::split_into_deltas;
+use crate::tokenizer;
 
 /// Generator for fake chat completion responses
 ///
@@ -98,6 +113,141 @@ impl ChatCompletionGenerator {
         CreateChatCompletionResponse::new(id, request.model.clone(), created, choices, usage)
     }
 
+    /// Generate a chat completion response whose message comes from a fixture
+    ///
+    /// Every choice carries the fixture's content or tool calls, while the ID, timestamp,
+    /// finish reason and usage are generated as for any other response. Error fixtures never
+    /// reach this method; the handler turns them into error responses.
+    pub fn generate_fixture_response(
+        request: &CreateChatCompletionRequest,
+        fixture: &FixtureResponse,
+    ) -> CreateChatCompletionResponse {
+        let id = Self::generate_id();
+        let created = Self::generate_timestamp();
+        let num_choices = request.n.unwrap_or(1);
+
+        let mut choices = Vec::new();
+        for i in 0..num_choices {
+            let mut message = match &fixture.tool_calls {
+                Some(tool_calls) => ChatCompletionResponseMessage::assistant_message_with_tools(
+                    tool_calls
+                        .iter()
+                        .map(|call| {
+                            ChatCompletionMessageToolCall::new(
+                                Self::generate_call_id(),
+                                call.name.clone(),
+                                call.arguments_json(),
+                            )
+                        })
+                        .collect(),
+                ),
+                None => ChatCompletionResponseMessage::assistant_message(
+                    fixture.content.clone().unwrap_or_default(),
+                ),
+            };
+            let truncated = Self::truncate_to_max_tokens(request, &mut message);
+            let finish_reason = Self::determine_finish_reason(request, &message, truncated);
+            choices.push(ChatCompletionChoice::new(i, message, finish_reason));
+        }
+
+        let usage = Self::generate_usage(request, &choices);
+
+        CreateChatCompletionResponse::new(id, request.model.clone(), created, choices, usage)
+    }
+
+    /// Split a generated chat completion response into streamed chunks
+    ///
+    /// Each choice is streamed as an opening delta carrying the assistant role, followed by
+    /// content (or refusal) deltas, tool call fragments, and a final empty delta carrying the
+    /// `finish_reason`. All chunks share the response's ID, timestamp and model. When
+    /// `stream_options.include_usage` is set, a last chunk with no choices carries the
+    /// response's usage statistics.
+    pub fn generate_chunks(
+        request: &CreateChatCompletionRequest,
+        response: &CreateChatCompletionResponse,
+    ) -> Vec<CreateChatCompletionChunk> {
+        let chunk = |choice: ChatCompletionChunkChoice| {
+            let mut chunk = CreateChatCompletionChunk::new(
+                response.id.clone(),
+                response.model.clone(),
+                response.created,
+                vec![choice],
+            );
+            chunk.system_fingerprint = response.system_fingerprint.clone();
+            chunk
+        };
+
+        let mut chunks = Vec::new();
+        for choice in &response.choices {
+            let index = choice.index;
+            let message = &choice.message;
+
+            chunks.push(chunk(ChatCompletionChunkChoice::new(
+                index,
+                ChatCompletionStreamDelta::role(message.role.clone()),
+            )));
+
+            if let Some(content) = &message.content {
+                for piece in split_into_deltas(content) {
+                    chunks.push(chunk(ChatCompletionChunkChoice::new(
+                        index,
+                        ChatCompletionStreamDelta::content(piece),
+                    )));
+                }
+            }
+
+            if let Some(refusal) = &message.refusal {
+                for piece in split_into_deltas(refusal) {
+                    chunks.push(chunk(ChatCompletionChunkChoice::new(
+                        index,
+                        ChatCompletionStreamDelta::refusal(piece),
+                    )));
+                }
+            }
+
+            for (call_index, tool_call) in message.tool_calls.iter().flatten().enumerate() {
+                let call_index = call_index as u32;
+                chunks.push(chunk(ChatCompletionChunkChoice::new(
+                    index,
+                    ChatCompletionStreamDelta::tool_call(
+                        ChatCompletionMessageToolCallChunk::start(
+                            call_index,
+                            tool_call.id.clone(),
+                            tool_call.function.name.clone(),
+                        ),
+                    ),
+                )));
+                for piece in split_into_deltas(&tool_call.function.arguments) {
+                    chunks.push(chunk(ChatCompletionChunkChoice::new(
+                        index,
+                        ChatCompletionStreamDelta::tool_call(
+                            ChatCompletionMessageToolCallChunk::arguments(call_index, piece),
+                        ),
+                    )));
+                }
+            }
+
+            chunks.push(chunk(
+                ChatCompletionChunkChoice::new(index, ChatCompletionStreamDelta::default())
+                    .with_finish_reason(choice.finish_reason.clone()),
+            ));
+        }
+
+        if StreamOptions::includes_usage(request.stream_options.as_ref()) {
+            let mut usage_chunk = CreateChatCompletionChunk::new(
+                response.id.clone(),
+                response.model.clone(),
+                response.created,
+                vec![],
+            )
+            .with_usage(response.usage.clone());
+            usage_chunk.system_fingerprint = response.system_fingerprint.clone();
+            chunks.push(usage_chunk);
+        }
+
+        chunks
+    }
+
     /// Generate a unique chat completion ID
     fn generate_id() -> String {
         let uuid = Uuid::new_v4();
@@ -113,59 +263,184 @@ impl ChatCompletionGenerator {
     }
 
     /// Generate a single chat completion choice
+    ///
+    /// Tools are called as `tool_choice` dictates: never with `none`, always with `required` or
+    /// a named function, and with `auto` (the default) when the last user message asks for one.
+    /// Once the results of those calls are sent back, `auto` answers with them instead.
     fn generate_choice(request: &CreateChatCompletionRequest, index: u32) -> ChatCompletionChoice {
-        // Determine if we should generate tool calls based on the request
-        let should_generate_tools = request.tools.is_some()
-            && request.tool_choice.is_some()
-            && Self::should_call_function(&request.messages);
-
-        let message = if should_generate_tools {
-            Self::generate_tool_call_message(request)
+        let tools = request.callable_tools();
+        let should_generate_tools = !tools.is_empty()
+            && (request.requires_tool_call()
+                || (Self::tool_results(&request.messages).is_empty()
+                    && Self::should_call_function(&request.messages, &tools)));
+
+        let mut message = if should_generate_tools {
+            Self::generate_tool_call_message(request, &tools)
         } else {
             Self::generate_content_message(request)
         };
 
-        let finish_reason = Self::determine_finish_reason(request, &message);
+        let truncated = Self::truncate_to_max_tokens(request, &mut message);
+        let finish_reason = Self::determine_finish_reason(request, &message, truncated);
 
         ChatCompletionChoice::new(index, message, finish_reason)
     }
 
     /// Generate a content-based assistant message
+    ///
+    /// Answers with the tool results since the last user message, if there are any.
     fn generate_content_message(
         request: &CreateChatCompletionRequest,
     ) -> ChatCompletionResponseMessage {
-        let content = Self::generate_assistant_content(&request.messages, &request.model);
-        ChatCompletionResponseMessage::assistant_message(content)
+        let tool_results = Self::tool_results(&request.messages);
+        let content = if tool_results.is_empty() {
+            Self::generate_assistant_content(&request.messages, &request.model)
+        } else {
+            Self::generate_tool_results_content(&tool_results)
+        };
+        ChatCompletionResponseMessage::assistant_message(Self::format_content(request, content))
+    }
+
+    /// Shape generated content into the requested `response_format`
+    ///
+    /// `json_object` wraps the content in a `{"response": ...}` object, and `json_schema`
+    /// replaces it with a value generated from the schema, drawing on the last user message.
+    fn format_content(request: &CreateChatCompletionRequest, content: String) -> String {
+        match &request.response_format {
+            Some(ResponseFormat::JsonObject) => json!({ "response": content }).to_string(),
+            Some(ResponseFormat::JsonSchema { json_schema }) => {
+                let context = Self::last_user_content(&request.messages).unwrap_or_default();
+                match &json_schema.schema {
+                    Some(schema_value) => {
+                        schema::generate_value(schema_value, &context).to_string()
+                    }
+                    None => json!({ "response": content }).to_string(),
+                }
+            }
+            Some(ResponseFormat::Text) | None => content,
+        }
+    }
+
+    /// The function names and results of the tool messages since the last user message
+    fn tool_results(messages: &[ChatCompletionMessage]) -> Vec<(&str, String)> {
+        let turn_start = messages
+            .iter()
+            .rposition(|msg| matches!(msg.role, ChatCompletionRole::User))
+            .map_or(0, |index| index + 1);
+        let turn = &messages[turn_start..];
+
+        turn.iter()
+            .filter(|msg| matches!(msg.role, ChatCompletionRole::Tool))
+            .map(|msg| {
+                // Name the result after the call it answers
+                let name = turn
+                    .iter()
+                    .flat_map(|msg| msg.tool_calls.iter().flatten())
+                    .find(|call| Some(&call.id) == msg.tool_call_id.as_ref())
+                    .map_or("the tool", |call| call.function.name.as_str());
+                (name, msg.text().unwrap_or_default().trim().to_string())
+            })
+            .collect()
+    }
+
+    /// Generate a final answer that reports tool results
+    fn generate_tool_results_content(tool_results: &[(&str, String)]) -> String {
+        match tool_results {
+            [(name, result)] => format!(
+                "Here's what I found using {name}: {}",
+                Self::summarize_tool_result(result)
+            ),
+            _ => {
+                let lines: Vec<String> = tool_results
+                    .iter()
+                    .map(|(name, result)| {
+                        format!("- {name}: {}", Self::summarize_tool_result(result))
+                    })
+                    .collect();
+                format!("Here's what I found:\n\n{}", lines.join("\n"))
+            }
+        }
+    }
+
+    /// Shorten a tool result to at most 200 characters
+    fn summarize_tool_result(result: &str) -> String {
+        const MAX_CHARS: usize = 200;
+        if result.chars().count() <= MAX_CHARS {
+            result.to_string()
+        } else {
+            let summary: String = result.chars().take(MAX_CHARS).collect();
+            format!("{}...", summary.trim_end())
+        }
     }
 
-    /// Generate an assistant message with tool calls
+    /// Generate an assistant message calling the tools relevant to the last user message
+    ///
+    /// Every tool whose name the message mentions is called in parallel, unless
+    /// `parallel_tool_calls` is false. Otherwise the single most relevant tool is called.
     fn generate_tool_call_message(
         request: &CreateChatCompletionRequest,
+        tools: &[&ChatCompletionTool],
     ) -> ChatCompletionResponseMessage {
-        if let Some(tools) = &request.tools {
-            if !tools.is_empty() {
-                let tool_call = Self::generate_tool_call(&tools[0]);
-                return ChatCompletionResponseMessage::assistant_message_with_tools(vec![
-                    tool_call,
-                ]);
-            }
-        }
+        let context = Self::last_user_content(&request.messages).unwrap_or_default();
+        let context_lower = context.to_lowercase();
+        let relevant: Vec<&ChatCompletionTool> = tools
+            .iter()
+            .copied()
+            .filter(|tool| Self::tool_relevance(tool, &context_lower) > 0)
+            .collect();
 
-        // Fallback to content message if no tools available
-        Self::generate_content_message(request)
+        let selected = if request.allows_parallel_tool_calls() && relevant.len() > 1 {
+            relevant
+        } else {
+            vec![Self::select_tool(tools, &context)]
+        };
+
+        let tool_calls = selected
+            .into_iter()
+            .map(|tool| Self::generate_tool_call(tool, &context))
+            .collect();
+        ChatCompletionResponseMessage::assistant_message_with_tools(tool_calls)
     }
 
     /// Generate assistant content based on conversation context
+    ///
+    /// Images and audio attached to the last user message are acknowledged before the reply.
     fn generate_assistant_content(messages: &[ChatCompletionMessage], model: &str) -> String {
-        let last_user_message = messages
-            .iter()
-            .rev()
-            .find(|msg| matches!(msg.role, ChatCompletionRole::User))
-            .and_then(|msg| msg.content.as_ref());
+        let Some(content) = Self::last_user_message(messages).and_then(|msg| msg.content.as_ref())
+        else {
+            return Self::get_default_response();
+        };
 
-        match last_user_message {
-            Some(content) => Self::generate_contextual_response(content, model),
-            None => Self::get_default_response(),
+        let text = content.text();
+        let response = if text.trim().is_empty() {
+            "What would you like to know about it?".to_string()
+        } else {
+            Self::generate_contextual_response(&text, model)
+        };
+
+        match Self::describe_attachments(content) {
+            Some(acknowledgement) => format!("{acknowledgement} {response}"),
+            None => response,
+        }
+    }
+
+    /// Acknowledge the images and audio clips attached to a message
+    fn describe_attachments(content: &ChatCompletionContent) -> Option<String> {
+        let count = |n: usize, one: &str, many: &str| match n {
+            0 => None,
+            1 => Some(format!("the {one}")),
+            n => Some(format!("the {n} {many}")),
+        };
+        let images = count(content.images().count(), "image", "images");
+        let audio = count(content.audio().count(), "audio clip", "audio clips");
+
+        match (images, audio) {
+            (Some(images), Some(audio)) => Some(format!(
+                "I've looked at {images} and listened to {audio} you shared."
+            )),
+            (Some(images), None) => Some(format!("I can see {images} you shared.")),
+            (None, Some(audio)) => Some(format!("I've listened to {audio} you shared.")),
+            (None, None) => None,
         }
     }
 
@@ -223,22 +498,33 @@ impl ChatCompletionGenerator {
     }
 
     /// Generate a tool call for function calling
+    ///
+    /// The arguments are generated from the function's `parameters` schema, drawing on the
+    /// last user message. Functions without a schema get arguments guessed from their name.
     fn generate_tool_call(
-        tool: &crate::models::requests::ChatCompletionTool,
+        tool: &ChatCompletionTool,
+        context: &str,
     ) -> ChatCompletionMessageToolCall {
-        let call_id = format!(
-            "call_{}",
-            &Uuid::new_v4().to_string().replace("-", "")[..24]
-        );
+        let call_id = Self::generate_call_id();
         let function_name = tool.function.name.clone();
 
-        // Generate realistic function arguments based on the function name
-        let arguments = Self::generate_function_arguments(&function_name);
+        let arguments = match &tool.function.parameters {
+            Some(parameters) => schema::generate_value(parameters, context).to_string(),
+            None => Self::generate_function_arguments(&function_name),
+        };
 
         ChatCompletionMessageToolCall::new(call_id, function_name, arguments)
     }
 
-    /// Generate realistic function arguments
+    /// Generate a unique tool call ID
+    fn generate_call_id() -> String {
+        format!(
+            "call_{}",
+            &Uuid::new_v4().to_string().replace("-", "")[..24]
+        )
+    }
+
+    /// Generate realistic function arguments for a function without a schema
     fn generate_function_arguments(function_name: &str) -> String {
         match function_name.to_lowercase().as_str() {
             name if name.contains("weather") => r#"{"location": "San Francisco, CA"}"#.to_string(),
@@ -252,42 +538,98 @@ impl ChatCompletionGenerator {
     }
 
     /// Determine if we should call a function based on conversation context
-    fn should_call_function(messages: &[ChatCompletionMessage]) -> bool {
-        let last_user_message = messages
-            .iter()
-            .rev()
-            .find(|msg| matches!(msg.role, ChatCompletionRole::User))
-            .and_then(|msg| msg.content.as_ref());
-
-        if let Some(content) = last_user_message {
+    fn should_call_function(
+        messages: &[ChatCompletionMessage],
+        tools: &[&ChatCompletionTool],
+    ) -> bool {
+        if let Some(content) = Self::last_user_content(messages) {
             let content_lower = content.to_lowercase();
             return content_lower.contains("weather")
                 || content_lower.contains("search")
                 || content_lower.contains("calculate")
-                || content_lower.contains("time");
+                || content_lower.contains("time")
+                || tools
+                    .iter()
+                    .any(|tool| Self::tool_relevance(tool, &content_lower) > 0);
         }
 
         false
     }
 
+    /// Pick the tool whose name best matches the user message, or the first tool
+    fn select_tool<'a>(tools: &[&'a ChatCompletionTool], context: &str) -> &'a ChatCompletionTool {
+        let context = context.to_lowercase();
+        tools
+            .iter()
+            .enumerate()
+            .max_by_key(|(index, tool)| (Self::tool_relevance(tool, &context), Reverse(*index)))
+            .map(|(_, tool)| *tool)
+            .expect("at least one callable tool")
+    }
+
+    /// The number of words of a tool's name, such as `weather` in `get_weather`, that appear
+    /// in lowercase text
+    fn tool_relevance(tool: &ChatCompletionTool, text: &str) -> usize {
+        tool.function
+            .name
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| word.len() >= 4 && text.contains(word))
+            .count()
+    }
+
+    /// The text of the last user message
+    fn last_user_content(messages: &[ChatCompletionMessage]) -> Option<Cow<'_, str>> {
+        Self::last_user_message(messages).and_then(ChatCompletionMessage::text)
+    }
+
+    /// The last user message
+    fn last_user_message(messages: &[ChatCompletionMessage]) -> Option<&ChatCompletionMessage> {
+        messages
+            .iter()
+            .rev()
+            .find(|msg| matches!(msg.role, ChatCompletionRole::User))
+    }
+
+    /// Cut the message content down to `max_tokens` tokens
+    ///
+    /// Returns whether the content was cut.
+    fn truncate_to_max_tokens(
+        request: &CreateChatCompletionRequest,
+        message: &mut ChatCompletionResponseMessage,
+    ) -> bool {
+        let (Some(max_tokens), Some(content)) = (request.max_tokens, &mut message.content) else {
+            return false;
+        };
+        match tokenizer::truncate_tokens(&request.model, content, max_tokens) {
+            Some(truncated) => {
+                *content = truncated;
+                true
+            }
+            None => false,
+        }
+    }
+
     /// Determine the finish reason for the completion
+    ///
+    /// `truncated` tells whether the content was cut at `max_tokens`.
     fn determine_finish_reason(
         request: &CreateChatCompletionRequest,
         message: &ChatCompletionResponseMessage,
+        truncated: bool,
     ) -> String {
-        // If there are tool calls, finish reason is tool_calls
+        // If there are tool calls, finish reason is tool_calls, except for calls forced by
+        // naming the function in tool_choice, which OpenAI reports as stop
         if message.tool_calls.is_some() {
-            return "tool_calls".to_string();
+            return match request.tool_choice {
+                Some(ChatCompletionToolChoice::Function(_)) => "stop".to_string(),
+                _ => "tool_calls".to_string(),
+            };
         }
 
-        // Check if we hit max tokens (simplified logic)
-        if let Some(max_tokens) = request.max_tokens {
-            if let Some(content) = &message.content {
-                let estimated_tokens = Self::estimate_tokens(content);
-                if estimated_tokens >= max_tokens {
-                    return "length".to_string();
-                }
-            }
+        // Content cut at max tokens
+        if truncated {
+            return "length".to_string();
         }
 
         // Default to stop
@@ -299,34 +641,26 @@ impl ChatCompletionGenerator {
         request: &CreateChatCompletionRequest,
         choices: &[ChatCompletionChoice],
     ) -> CompletionUsage {
-        let prompt_tokens = Self::estimate_prompt_tokens(&request.messages);
-        let completion_tokens = Self::estimate_completion_tokens(choices);
+        let prompt_tokens = tokenizer::count_chat_tokens(
+            &request.model,
+            &request.messages,
+            request.tools.as_deref().unwrap_or_default(),
+        );
+        let completion_tokens = Self::count_completion_tokens(&request.model, choices);
 
         CompletionUsage::new(prompt_tokens, completion_tokens)
     }
 
-    /// Estimate tokens in the prompt messages
-    fn estimate_prompt_tokens(messages: &[ChatCompletionMessage]) -> u32 {
-        let total_chars: usize = messages
-            .iter()
-            .map(|msg| {
-                msg.content.as_ref().map(|c| c.len()).unwrap_or(0) + msg.role.to_string().len() + 20 // overhead for message structure
-            })
-            .sum();
-
-        Self::estimate_tokens_from_chars(total_chars)
-    }
-
-    /// Estimate tokens in completion choices
-    fn estimate_completion_tokens(choices: &[ChatCompletionChoice]) -> u32 {
-        let total_chars: usize = choices
+    /// Count the tokens in completion choices
+    fn count_completion_tokens(model: &str, choices: &[ChatCompletionChoice]) -> u32 {
+        choices
             .iter()
             .map(|choice| {
                 choice
                     .message
                     .content
                     .as_ref()
-                    .map(|c| c.len())
+                    .map(|c| tokenizer::count_tokens(model, c))
                     .unwrap_or(0)
                     + choice
                         .message
@@ -336,25 +670,14 @@ impl ChatCompletionGenerator {
                             calls
                                 .iter()
                                 .map(|call| {
-                                    call.function.arguments.len() + call.function.name.len()
+                                    tokenizer::count_tokens(model, &call.function.name)
+                                        + tokenizer::count_tokens(model, &call.function.arguments)
                                 })
-                                .sum::<usize>()
+                                .sum::<u32>()
                         })
                         .unwrap_or(0)
             })
-            .sum();
-
-        Self::estimate_tokens_from_chars(total_chars)
-    }
-
-    /// Simple token estimation (roughly 4 characters per token)
-    fn estimate_tokens_from_chars(chars: usize) -> u32 {
-        ((chars as f64) / 4.0).ceil() as u32
-    }
-
-    /// Estimate tokens in a string
-    fn estimate_tokens(text: &str) -> u32 {
-        Self::estimate_tokens_from_chars(text.len())
+            .sum()
     }
 
     // Response generators for different types of content
@@ -436,7 +759,7 @@ mod tests {
             model: "gpt-3.5-turbo".to_string(),
             messages: vec![ChatCompletionMessage {
                 role: ChatCompletionRole::User,
-                content: Some("Hello, how are you?".to_string()),
+                content: Some("Hello, how are you?".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
@@ -445,14 +768,17 @@ mod tests {
             top_p: None,
             n: None,
             stream: None,
+            stream_options: None,
             stop: None,
             max_tokens: Some(100),
             presence_penalty: None,
             frequency_penalty: None,
             logit_bias: None,
             user: None,
+            response_format: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
         }
     }
 
@@ -491,7 +817,7 @@ mod tests {
 
         for (input, expected_type) in test_cases {
             let mut request = create_test_request();
-            request.messages[0].content = Some(input.to_string());
+            request.messages[0].content = Some(input.into());
 
             let response = ChatCompletionGenerator::generate_response(&request);
             let content = response.choices[0].message.content.as_ref().unwrap();
@@ -535,6 +861,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_max_tokens_truncates_content() {
+        let mut request = create_test_request();
+        request.max_tokens = Some(3);
+        let response = ChatCompletionGenerator::generate_response(&request);
+
+        assert_eq!(response.choices[0].finish_reason, "length");
+        assert_eq!(response.usage.completion_tokens, 3);
+        let content = response.choices[0].message.content.as_deref().unwrap();
+        assert_eq!(tokenizer::count_tokens(&request.model, content), 3);
+
+        request.max_tokens = Some(1000);
+        let response = ChatCompletionGenerator::generate_response(&request);
+        assert_eq!(response.choices[0].finish_reason, "stop");
+        assert!(response.usage.completion_tokens < 1000);
+    }
+
     #[test]
     fn test_id_generation() {
         let request = create_test_request();
@@ -547,11 +890,392 @@ mod tests {
     }
 
     #[test]
-    fn test_token_estimation() {
-        let text = "Hello, world!";
-        let tokens = ChatCompletionGenerator::estimate_tokens(text);
-        assert!(tokens > 0);
-        assert!(tokens <= 10); // Should be reasonable for short text
+    fn test_usage_counts_tokens() {
+        let request = create_test_request();
+        let response = ChatCompletionGenerator::generate_response(&request);
+
+        assert_eq!(
+            response.usage.prompt_tokens,
+            tokenizer::count_chat_tokens(&request.model, &request.messages, &[])
+        );
+        assert_eq!(
+            response.usage.completion_tokens,
+            tokenizer::count_tokens(
+                &request.model,
+                response.choices[0].message.content.as_deref().unwrap()
+            )
+        );
+    }
+
+    #[test]
+    fn test_generate_fixture_response() {
+        let mut request = create_test_request();
+        request.n = Some(2);
+
+        let fixture = FixtureResponse {
+            content: Some("Scripted reply".to_string()),
+            ..Default::default()
+        };
+        let response = ChatCompletionGenerator::generate_fixture_response(&request, &fixture);
+        assert_eq!(response.choices.len(), 2);
+        assert_eq!(
+            response.choices[1].message.content.as_deref(),
+            Some("Scripted reply")
+        );
+        assert_eq!(response.choices[0].finish_reason, "stop");
+
+        let fixture: FixtureResponse = serde_json::from_value(serde_json::json!({
+            "tool_calls": [{"name": "lookup", "arguments": {"id": 7}}]
+        }))
+        .unwrap();
+        let response = ChatCompletionGenerator::generate_fixture_response(&request, &fixture);
+        let tool_calls = response.choices[0].message.tool_calls.as_ref().unwrap();
+        assert_eq!(tool_calls[0].function.name, "lookup");
+        assert_eq!(tool_calls[0].function.arguments, r#"{"id":7}"#);
+        assert!(tool_calls[0].id.starts_with("call_"));
+        assert_eq!(response.choices[0].finish_reason, "tool_calls");
+    }
+
+    #[test]
+    fn test_generate_chunks_content() {
+        let request = create_test_request();
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let chunks = ChatCompletionGenerator::generate_chunks(&request, &response);
+
+        assert!(chunks.len() > 2);
+        assert!(chunks.iter().all(|chunk| chunk.id == response.id));
+        assert!(
+            chunks
+                .iter()
+                .all(|chunk| chunk.object == "chat.completion.chunk")
+        );
+
+        let first = &chunks[0].choices[0];
+        assert_eq!(first.delta.role.as_deref(), Some("assistant"));
+        assert!(first.finish_reason.is_none());
+
+        let last = &chunks.last().unwrap().choices[0];
+        assert_eq!(
+            last.finish_reason,
+            Some(response.choices[0].finish_reason.clone())
+        );
+
+        let streamed: String = chunks
+            .iter()
+            .filter_map(|chunk| chunk.choices[0].delta.content.clone())
+            .collect();
+        assert_eq!(Some(streamed), response.choices[0].message.content);
+    }
+
+    #[test]
+    fn test_generate_chunks_tool_calls() {
+        let mut request = create_test_request();
+        request.messages[0].content = Some("What's the weather in Paris?".into());
+        request.tools = Some(vec![crate::models::requests::ChatCompletionTool {
+            tool_type: "function".to_string(),
+            function: crate::models::requests::ChatCompletionFunction {
+                name: "get_weather".to_string(),
+                description: None,
+                parameters: None,
+                strict: None,
+            },
+        }]);
+        request.tool_choice = Some(crate::models::requests::ChatCompletionToolChoice::Auto);
+
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let chunks = ChatCompletionGenerator::generate_chunks(&request, &response);
+
+        let fragments: Vec<_> = chunks
+            .iter()
+            .filter_map(|chunk| chunk.choices[0].delta.tool_calls.as_ref())
+            .flatten()
+            .collect();
+        assert_eq!(fragments[0].function.name.as_deref(), Some("get_weather"));
+        assert!(fragments[0].id.is_some());
+
+        let arguments: String = fragments
+            .iter()
+            .filter_map(|fragment| fragment.function.arguments.clone())
+            .collect();
+        let expected = &response.choices[0].message.tool_calls.as_ref().unwrap()[0];
+        assert_eq!(arguments, expected.function.arguments);
+
+        let last = &chunks.last().unwrap().choices[0];
+        assert_eq!(last.finish_reason.as_deref(), Some("tool_calls"));
+    }
+
+    #[test]
+    fn test_tool_call_arguments_follow_schema() {
+        let mut request = create_test_request();
+        request.messages[0].content = Some("What's the weather in Paris in fahrenheit?".into());
+        request.tools = Some(vec![crate::models::requests::ChatCompletionTool {
+            tool_type: "function".to_string(),
+            function: crate::models::requests::ChatCompletionFunction {
+                name: "get_weather".to_string(),
+                description: None,
+                parameters: Some(serde_json::json!({
+                    "type": "object",
+                    "properties": {
+                        "location": {"type": "string"},
+                        "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                        "days": {"type": "integer", "minimum": 1, "maximum": 7}
+                    },
+                    "required": ["location", "unit", "days"]
+                })),
+                strict: None,
+            },
+        }]);
+        request.tool_choice = Some(crate::models::requests::ChatCompletionToolChoice::Auto);
+
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let tool_calls = response.choices[0].message.tool_calls.as_ref().unwrap();
+        let arguments: serde_json::Value =
+            serde_json::from_str(&tool_calls[0].function.arguments).unwrap();
+        assert_eq!(arguments["location"], "Paris");
+        assert_eq!(arguments["unit"], "fahrenheit");
+        assert!((1..=7).contains(&arguments["days"].as_i64().unwrap()));
+    }
+
+    #[test]
+    fn test_tool_choice_semantics() {
+        use crate::models::requests::ChatCompletionFunction;
+
+        let tool = |name: &str| ChatCompletionTool {
+            tool_type: "function".to_string(),
+            function: ChatCompletionFunction {
+                name: name.to_string(),
+                description: None,
+                parameters: None,
+                strict: None,
+            },
+        };
+        let called = |request: &CreateChatCompletionRequest| {
+            let response = ChatCompletionGenerator::generate_response(request);
+            response.choices[0]
+                .message
+                .tool_calls
+                .as_ref()
+                .map(|calls| calls[0].function.name.clone())
+        };
+
+        let mut request = create_test_request();
+        request.messages[0].content = Some("What's the stock price of ACME?".into());
+        request.tools = Some(vec![tool("get_weather"), tool("get_stock_price")]);
+
+        // auto, the default, calls the tool the message asks for
+        assert_eq!(called(&request).as_deref(), Some("get_stock_price"));
+        request.tool_choice = Some(ChatCompletionToolChoice::Auto);
+        assert_eq!(called(&request).as_deref(), Some("get_stock_price"));
+
+        request.tool_choice = Some(ChatCompletionToolChoice::None);
+        assert_eq!(called(&request), None);
+
+        request.tool_choice = Some(ChatCompletionToolChoice::function("get_weather"));
+        assert_eq!(called(&request).as_deref(), Some("get_weather"));
+        let response = ChatCompletionGenerator::generate_response(&request);
+        assert_eq!(response.choices[0].finish_reason, "stop");
+
+        // Messages that ask for no tool only call one when it is required
+        request.messages[0].content = Some("Hello there".into());
+        request.tool_choice = Some(ChatCompletionToolChoice::Auto);
+        assert_eq!(called(&request), None);
+        request.tool_choice = Some(ChatCompletionToolChoice::Required);
+        assert_eq!(called(&request).as_deref(), Some("get_weather"));
+        let response = ChatCompletionGenerator::generate_response(&request);
+        assert_eq!(response.choices[0].finish_reason, "tool_calls");
+    }
+
+    #[test]
+    fn test_parallel_tool_calls() {
+        use crate::models::requests::ChatCompletionFunction;
+
+        let tool = |name: &str| ChatCompletionTool {
+            tool_type: "function".to_string(),
+            function: ChatCompletionFunction {
+                name: name.to_string(),
+                description: None,
+                parameters: None,
+                strict: None,
+            },
+        };
+        let called = |request: &CreateChatCompletionRequest| -> Vec<String> {
+            let response = ChatCompletionGenerator::generate_response(request);
+            response.choices[0]
+                .message
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|call| call.function.name.clone())
+                .collect()
+        };
+
+        let mut request = create_test_request();
+        request.messages[0].content =
+            Some("What's the weather and the local time in Paris?".into());
+        request.tools = Some(vec![
+            tool("get_weather"),
+            tool("get_stock_price"),
+            tool("get_local_time"),
+        ]);
+        assert_eq!(called(&request), vec!["get_weather", "get_local_time"]);
+
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let tool_calls = response.choices[0].message.tool_calls.as_ref().unwrap();
+        assert_ne!(tool_calls[0].id, tool_calls[1].id);
+
+        // Every call is streamed under its own index
+        let chunks = ChatCompletionGenerator::generate_chunks(&request, &response);
+        let indexes: std::collections::BTreeSet<u32> = chunks
+            .iter()
+            .filter_map(|chunk| chunk.choices.first()?.delta.tool_calls.as_ref())
+            .flatten()
+            .map(|fragment| fragment.index)
+            .collect();
+        assert_eq!(indexes.into_iter().collect::<Vec<_>>(), vec![0, 1]);
+
+        // Without parallel calls, the tool matching most words of the message is called
+        request.parallel_tool_calls = Some(false);
+        assert_eq!(called(&request), vec!["get_local_time"]);
+
+        request.parallel_tool_calls = None;
+        request.tool_choice = Some(ChatCompletionToolChoice::function("get_weather"));
+        assert_eq!(called(&request), vec!["get_weather"]);
+    }
+
+    #[test]
+    fn test_tool_results_end_the_tool_loop() {
+        let request: CreateChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "user", "content": "What's the weather and the local time in Paris?"},
+                {"role": "assistant", "content": null, "tool_calls": [
+                    {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{}"}},
+                    {"id": "call_2", "type": "function", "function": {"name": "get_local_time", "arguments": "{}"}}
+                ]},
+                {"role": "tool", "tool_call_id": "call_1", "content": "18°C and sunny"},
+                {"role": "tool", "tool_call_id": "call_2", "content": "14:05"}
+            ],
+            "tools": [
+                {"type": "function", "function": {"name": "get_weather"}},
+                {"type": "function", "function": {"name": "get_local_time"}}
+            ]
+        }))
+        .unwrap();
+        assert!(request.validate().is_ok());
+
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let choice = &response.choices[0];
+        assert!(choice.message.tool_calls.is_none());
+        assert_eq!(choice.finish_reason, "stop");
+        let content = choice.message.content.as_deref().unwrap();
+        assert!(content.contains("get_weather: 18°C and sunny"));
+        assert!(content.contains("get_local_time: 14:05"));
+
+        // A new user message starts a new turn that may call tools again
+        let mut next = request.clone();
+        next.messages.push(ChatCompletionMessage {
+            role: ChatCompletionRole::User,
+            content: Some("And the weather in Rome?".into()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        });
+        let response = ChatCompletionGenerator::generate_response(&next);
+        assert!(response.choices[0].message.tool_calls.is_some());
+    }
+
+    #[test]
+    fn test_acknowledges_attachments() {
+        let mut request = create_test_request();
+        request.messages[0] = serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "content": [
+                {"type": "text", "text": "Describe this picture"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+            ]
+        }))
+        .unwrap();
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let content = response.choices[0].message.content.as_deref().unwrap();
+        assert!(content.starts_with("I can see the image you shared."));
+
+        request.messages[0] = serde_json::from_value(serde_json::json!({
+            "role": "user",
+            "content": [
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}},
+                {"type": "image_url", "image_url": {"url": "https://example.com/dog.png", "detail": "low"}}
+            ]
+        }))
+        .unwrap();
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let content = response.choices[0].message.content.as_deref().unwrap();
+        assert!(content.starts_with("I can see the 2 images you shared."));
+
+        // Images count towards the prompt tokens: 765 for the remote image, 85 at low detail
+        request.model = "gpt-4o".to_string();
+        let response = ChatCompletionGenerator::generate_response(&request);
+        assert!(response.usage.prompt_tokens > 765 + 85);
+    }
+
+    #[test]
+    fn test_response_format() {
+        use crate::models::requests::{ResponseFormat, ResponseFormatJsonSchema};
+
+        let mut request = create_test_request();
+        request.messages[0].content = Some("Reply in JSON please".into());
+        request.response_format = Some(ResponseFormat::JsonObject);
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let content = response.choices[0].message.content.as_deref().unwrap();
+        let value: serde_json::Value = serde_json::from_str(content).unwrap();
+        assert!(value["response"].is_string());
+
+        request.messages[0].content = Some("Extract: Ada Lovelace, 36, lives in London".into());
+        request.response_format = Some(ResponseFormat::JsonSchema {
+            json_schema: ResponseFormatJsonSchema {
+                name: "person".to_string(),
+                description: None,
+                schema: Some(json!({
+                    "type": "object",
+                    "properties": {
+                        "name": {"type": "string"},
+                        "age": {"type": "integer", "minimum": 0, "maximum": 150},
+                        "city": {"type": "string"}
+                    },
+                    "required": ["name", "age", "city"],
+                    "additionalProperties": false
+                })),
+                strict: Some(true),
+            },
+        });
+        let response = ChatCompletionGenerator::generate_response(&request);
+        let content = response.choices[0].message.content.as_deref().unwrap();
+        let value: serde_json::Value = serde_json::from_str(content).unwrap();
+        assert!(value["name"].is_string());
+        assert_eq!(value["age"], 36);
+        assert_eq!(value["city"], "London");
+    }
+
+    #[test]
+    fn test_generate_chunks_include_usage() {
+        let mut request = create_test_request();
+        request.stream = Some(true);
+        let response = ChatCompletionGenerator::generate_response(&request);
+
+        // Without stream_options no chunk carries usage
+        let chunks = ChatCompletionGenerator::generate_chunks(&request, &response);
+        assert!(chunks.iter().all(|chunk| chunk.usage.is_none()));
+
+        request.stream_options = Some(StreamOptions {
+            include_usage: Some(true),
+        });
+        let chunks = ChatCompletionGenerator::generate_chunks(&request, &response);
+        let last = chunks.last().unwrap();
+        assert!(last.choices.is_empty());
+
+        let usage = last.usage.as_ref().expect("Final chunk should carry usage");
+        assert_eq!(usage.prompt_tokens, response.usage.prompt_tokens);
+        assert_eq!(usage.completion_tokens, response.usage.completion_tokens);
+        assert_eq!(usage.total_tokens, response.usage.total_tokens);
     }
 
     #[test]
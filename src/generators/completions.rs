@@ -29,6 +29,7 @@
 //!     suffix: None,
 //!     top_p: None,
 //!     stream: None,
+//!     stream_options: None,
 //!     logprobs: None,
 //!     echo: None,
 //!     stop: None,
@@ -58,9 +59,14 @@
 //! during testing while still providing variety.
 
 use crate::models::{
-    requests::CreateCompletionRequest,
-    responses::{CompletionChoice, CompletionLogprobs, CompletionUsage, CreateCompletionResponse},
+    requests::{CreateCompletionRequest, StreamOptions},
+    responses::{
+        CompletionChoice, CompletionChunkChoice, CompletionLogprobs, CompletionUsage,
+        CreateCompletionChunk, CreateCompletionResponse,
+    },
 };
+use crate::streaming::split_into_deltas;
+use crate::tokenizer;
 use chrono::{Datelike, Timelike};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -86,6 +92,59 @@ impl CompletionGenerator {
         CreateCompletionResponse::new(id, request.model.clone(), created, choices, usage)
     }
 
+    /// Split a generated completion response into streamed chunks
+    ///
+    /// Each choice is streamed token by token, with one chunk per token carrying the choice
+    /// `index` and, when `logprobs` was requested, the log probabilities of that token. A final
+    /// empty chunk per choice carries the `finish_reason`. When `stream_options.include_usage`
+    /// is set, a last chunk with no choices carries the response's usage statistics.
+    pub fn generate_chunks(
+        request: &CreateCompletionRequest,
+        response: &CreateCompletionResponse,
+    ) -> Vec<CreateCompletionChunk> {
+        let chunk = |choice: CompletionChunkChoice| {
+            let mut chunk = CreateCompletionChunk::new(
+                response.id.clone(),
+                response.model.clone(),
+                response.created,
+                vec![choice],
+            );
+            chunk.system_fingerprint = response.system_fingerprint.clone();
+            chunk
+        };
+
+        let mut chunks = Vec::new();
+        for choice in &response.choices {
+            for (token_index, piece) in split_into_deltas(&choice.text).into_iter().enumerate() {
+                let mut chunk_choice = CompletionChunkChoice::new(piece, choice.index);
+                if let Some(logprobs) = &choice.logprobs {
+                    chunk_choice =
+                        chunk_choice.with_logprobs(Self::token_logprobs(logprobs, token_index));
+                }
+                chunks.push(chunk(chunk_choice));
+            }
+
+            chunks.push(chunk(
+                CompletionChunkChoice::new(String::new(), choice.index)
+                    .with_finish_reason(choice.finish_reason.clone()),
+            ));
+        }
+
+        if StreamOptions::includes_usage(request.stream_options.as_ref()) {
+            let mut usage_chunk = CreateCompletionChunk::new(
+                response.id.clone(),
+                response.model.clone(),
+                response.created,
+                vec![],
+            )
+            .with_usage(response.usage.clone());
+            usage_chunk.system_fingerprint = response.system_fingerprint.clone();
+            chunks.push(usage_chunk);
+        }
+
+        chunks
+    }
+
     /// Generate a unique completion ID
     fn generate_id() -> String {
         let uuid = Uuid::new_v4();
@@ -108,11 +167,11 @@ impl CompletionGenerator {
         let mut choice = CompletionChoice::new(text, index, finish_reason);
 
         // Add logprobs if requested
-        if let Some(logprobs_count) = request.logprobs {
-            if logprobs_count > 0 {
-                let logprobs = Self::generate_logprobs(&choice.text, logprobs_count);
-                choice = choice.with_logprobs(logprobs);
-            }
+        if let Some(logprobs_count) = request.logprobs
+            && logprobs_count > 0
+        {
+            let logprobs = Self::generate_logprobs(&choice.text, logprobs_count);
+            choice = choice.with_logprobs(logprobs);
         }
 
         choice
@@ -132,23 +191,24 @@ impl CompletionGenerator {
         let max_tokens = request.max_tokens.unwrap_or(16);
 
         // Generate contextually appropriate response based on prompt patterns
-        let completion = if prompt.to_lowercase().contains("hello") {
-            Self::get_greeting_completion(&prompt, max_tokens)
+        let response = if prompt.to_lowercase().contains("hello") {
+            Self::get_greeting_completion(&prompt)
         } else if prompt.to_lowercase().contains("write")
             || prompt.to_lowercase().contains("create")
         {
-            Self::get_creative_completion(&prompt, max_tokens)
+            Self::get_creative_completion(&prompt)
         } else if prompt.to_lowercase().contains("explain")
             || prompt.to_lowercase().contains("what")
         {
-            Self::get_explanatory_completion(&prompt, max_tokens)
+            Self::get_explanatory_completion(&prompt)
         } else if prompt.to_lowercase().contains("code")
             || prompt.to_lowercase().contains("function")
         {
-            Self::get_code_completion(&prompt, max_tokens)
+            Self::get_code_completion(&prompt)
         } else {
-            Self::get_general_completion(&prompt, max_tokens)
+            Self::get_general_completion(&prompt)
         };
+        let completion = Self::truncate_to_tokens(&request.model, response, max_tokens);
 
         // Add echo if requested
         if request.echo.unwrap_or(false) {
@@ -159,52 +219,48 @@ impl CompletionGenerator {
     }
 
     /// Generate greeting-style completions
-    fn get_greeting_completion(prompt: &str, max_tokens: u32) -> String {
+    fn get_greeting_completion(prompt: &str) -> &'static str {
         let responses = [" Hello! How can I help you today?",
             " Hi there! Nice to meet you.",
             " Hello! I'm here to assist you.",
             " Hi! What would you like to know?",
             " Hello! Feel free to ask me anything."];
 
-        let response = responses[prompt.len() % responses.len()];
-        Self::truncate_to_tokens(response, max_tokens)
+        responses[prompt.len() % responses.len()]
     }
 
     /// Generate creative writing completions
-    fn get_creative_completion(_prompt: &str, max_tokens: u32) -> String {
+    fn get_creative_completion(_prompt: &str) -> &'static str {
         let responses = [" Once upon a time, in a land far away, there lived a curious explorer who discovered amazing secrets hidden in ancient ruins.",
             " The story begins on a rainy Tuesday morning when everything seemed ordinary, but little did anyone know that extraordinary events were about to unfold.",
             " In the bustling city, among the towering skyscrapers and busy streets, a small coffee shop held the key to an incredible adventure.",
             " The old library contained more than just books - it held mysteries that had been waiting centuries to be uncovered by the right person."];
 
-        let response = responses[chrono::Utc::now().timestamp() as usize % responses.len()];
-        Self::truncate_to_tokens(response, max_tokens)
+        responses[chrono::Utc::now().timestamp() as usize % responses.len()]
     }
 
     /// Generate explanatory completions
-    fn get_explanatory_completion(_prompt: &str, max_tokens: u32) -> String {
+    fn get_explanatory_completion(_prompt: &str) -> &'static str {
         let responses = [" This is a complex topic that involves multiple interconnected concepts. Let me break it down into simpler parts for better understanding.",
             " The fundamental principle behind this is based on well-established scientific theories that have been validated through extensive research.",
             " To understand this properly, we need to consider the historical context and how various factors have influenced its development over time.",
             " This concept can be explained through a practical example that demonstrates its real-world applications and benefits."];
 
-        let response = responses[chrono::Utc::now().day() as usize % responses.len()];
-        Self::truncate_to_tokens(response, max_tokens)
+        responses[chrono::Utc::now().day() as usize % responses.len()]
     }
 
     /// Generate code-related completions
-    fn get_code_completion(_prompt: &str, max_tokens: u32) -> String {
+    fn get_code_completion(_prompt: &str) -> &'static str {
         let responses = ["\n```rust\nfn example() {\n    println!(\"Hello, world!\");\n}\n```",
             "\n```python\ndef example():\n    print(\"Hello, world!\")\n    return True\n```",
             "\n```javascript\nfunction example() {\n    console.log(\"Hello, world!\");\n    return true;\n}\n```",
             "\n```java\npublic void example() {\n    System.out.println(\"Hello, world!\");\n}\n```"];
 
-        let response = responses[chrono::Utc::now().hour() as usize % responses.len()];
-        Self::truncate_to_tokens(response, max_tokens)
+        responses[chrono::Utc::now().hour() as usize % responses.len()]
     }
 
     /// Generate general purpose completions
-    fn get_general_completion(_prompt: &str, max_tokens: u32) -> String {
+    fn get_general_completion(_prompt: &str) -> &'static str {
         let responses = [" This is an interesting topic that deserves careful consideration and thoughtful analysis.",
             " There are several important factors to consider when approaching this subject matter.",
             " The key to understanding this lies in examining the underlying principles and their practical applications.",
@@ -212,8 +268,7 @@ impl CompletionGenerator {
             " The complexity of this subject requires a multifaceted approach to fully appreciate its nuances."];
 
         let hash = Self::simple_hash(_prompt);
-        let response = responses[hash % responses.len()];
-        Self::truncate_to_tokens(response, max_tokens)
+        responses[hash % responses.len()]
     }
 
     /// Simple hash function for consistent but varied responses
@@ -221,22 +276,9 @@ impl CompletionGenerator {
         input.chars().map(|c| c as usize).sum()
     }
 
-    /// Truncate response to approximate token count
-    fn truncate_to_tokens(text: &str, max_tokens: u32) -> String {
-        // Rough approximation: 1 token ≈ 4 characters for English text
-        let max_chars = (max_tokens * 4) as usize;
-
-        if text.len() <= max_chars {
-            text.to_string()
-        } else {
-            // Try to break at word boundaries
-            let truncated = &text[..max_chars];
-            if let Some(last_space) = truncated.rfind(' ') {
-                truncated[..last_space].to_string()
-            } else {
-                truncated.to_string()
-            }
-        }
+    /// Truncate response to the first `max_tokens` tokens of the model's encoding
+    fn truncate_to_tokens(model: &str, text: &str, max_tokens: u32) -> String {
+        tokenizer::truncate_tokens(model, text, max_tokens).unwrap_or_else(|| text.to_string())
     }
 
     /// Determine the finish reason for the completion
@@ -255,11 +297,11 @@ impl CompletionGenerator {
             }
         }
 
-        // Check if we likely hit max_tokens (rough approximation)
-        let estimated_tokens = text.len() / 4;
-        let max_tokens = request.max_tokens.unwrap_or(16) as usize;
+        // Check if we hit max_tokens
+        let tokens = tokenizer::count_tokens(&request.model, text);
+        let max_tokens = request.max_tokens.unwrap_or(16);
 
-        if estimated_tokens >= max_tokens {
+        if tokens >= max_tokens {
             "length".to_string()
         } else {
             "stop".to_string()
@@ -268,7 +310,6 @@ impl CompletionGenerator {
 
     /// Generate fake log probabilities
     fn generate_logprobs(text: &str, logprobs_count: u32) -> CompletionLogprobs {
-        let words: Vec<&str> = text.split_whitespace().collect();
         let mut tokens = Vec::new();
         let mut token_logprobs = Vec::new();
         let mut text_offset = Vec::new();
@@ -276,27 +317,37 @@ impl CompletionGenerator {
 
         let mut current_offset = 0u32;
 
-        for word in words.iter().take(logprobs_count as usize) {
-            tokens.push(word.to_string());
+        // Tokens are the pieces the response is streamed in, so streamed chunks carry the
+        // same log probabilities as the whole response
+        for token in split_into_deltas(text) {
+            let word = token.trim();
 
             // Generate fake but realistic log probabilities (typically negative)
-            let logprob = -0.1 - (word.len() as f64 * 0.05);
+            let logprob = -0.1 - (word.chars().count() as f64 * 0.05);
             token_logprobs.push(Some(logprob));
 
             text_offset.push(current_offset);
-
-            // Generate top logprobs alternatives
+            current_offset += token.chars().count() as u32;
+
+            // The chosen token plus up to `logprobs_count - 1` fake alternatives
+            let alternatives = [
+                format!("{token}s"),
+                format!(" un{word}"),
+                format!("{token}."),
+                format!(" {word}ing"),
+            ];
             let mut top_map = HashMap::new();
-            top_map.insert(word.to_string(), logprob);
-
-            // Add some fake alternatives
-            if word.len() > 3 {
-                top_map.insert(format!("{word}s"), logprob - 0.5);
-                top_map.insert(format!("un{word}"), logprob - 1.0);
+            top_map.insert(token.clone(), logprob);
+            for (i, alternative) in alternatives
+                .into_iter()
+                .take(logprobs_count.saturating_sub(1) as usize)
+                .enumerate()
+            {
+                top_map.insert(alternative, logprob - 0.5 * (i as f64 + 1.0));
             }
 
+            tokens.push(token);
             top_logprobs.push(Some(top_map));
-            current_offset += word.len() as u32 + 1; // +1 for space
         }
 
         CompletionLogprobs {
@@ -307,36 +358,51 @@ impl CompletionGenerator {
         }
     }
 
+    /// The log probabilities of a single token of a response, for its streamed chunk
+    fn token_logprobs(logprobs: &CompletionLogprobs, index: usize) -> CompletionLogprobs {
+        CompletionLogprobs {
+            tokens: logprobs.tokens.get(index).cloned().into_iter().collect(),
+            token_logprobs: logprobs
+                .token_logprobs
+                .get(index)
+                .cloned()
+                .into_iter()
+                .collect(),
+            top_logprobs: logprobs
+                .top_logprobs
+                .as_ref()
+                .map(|top_logprobs| top_logprobs.get(index).cloned().into_iter().collect()),
+            text_offset: logprobs
+                .text_offset
+                .get(index)
+                .cloned()
+                .into_iter()
+                .collect(),
+        }
+    }
+
     /// Generate usage statistics based on the request and response
     fn generate_usage(
         request: &CreateCompletionRequest,
         choices: &[CompletionChoice],
     ) -> CompletionUsage {
-        // Estimate prompt tokens based on prompt length
-        let prompt_text = match &request.prompt {
-            crate::models::requests::PromptInput::String(s) => s.clone(),
-            crate::models::requests::PromptInput::Array(arr) => arr.join(" "),
+        let prompt_tokens = match &request.prompt {
+            crate::models::requests::PromptInput::String(s) => {
+                tokenizer::count_tokens(&request.model, s)
+            }
+            crate::models::requests::PromptInput::Array(arr) => arr
+                .iter()
+                .map(|prompt| tokenizer::count_tokens(&request.model, prompt))
+                .sum(),
         };
 
-        let prompt_tokens = Self::estimate_tokens(&prompt_text);
-
-        // Estimate completion tokens based on generated text
         let completion_tokens = choices
             .iter()
-            .map(|choice| Self::estimate_tokens(&choice.text))
+            .map(|choice| tokenizer::count_tokens(&request.model, &choice.text))
             .sum();
 
         CompletionUsage::new(prompt_tokens, completion_tokens)
     }
-
-    /// Estimate token count for a given text
-    fn estimate_tokens(text: &str) -> u32 {
-        // Rough approximation: 1 token ≈ 4 characters for English text
-        // Add some randomness to make it more realistic
-        let base_estimate = (text.len() / 4).max(1) as u32;
-        let variation = (text.len() % 3) as u32;
-        base_estimate + variation
-    }
 }
 
 #[cfg(test)]
@@ -354,6 +420,7 @@ mod tests {
             top_p: None,
             n: Some(1),
             stream: None,
+            stream_options: None,
             logprobs: None,
             echo: None,
             stop: None,
@@ -473,20 +540,128 @@ mod tests {
     }
 
     #[test]
-    fn test_token_estimation() {
-        assert!(CompletionGenerator::estimate_tokens("hello") > 0);
-        assert!(
-            CompletionGenerator::estimate_tokens("hello world test")
-                > CompletionGenerator::estimate_tokens("hello")
+    fn test_usage_counts_tokens() {
+        let request = create_test_request();
+        let response = CompletionGenerator::generate_response(&request);
+
+        assert_eq!(response.usage.prompt_tokens, 2); // "Hello world" in cl100k_base
+        assert_eq!(
+            response.usage.completion_tokens,
+            tokenizer::count_tokens(&request.model, &response.choices[0].text)
         );
     }
 
+    #[test]
+    fn test_generate_chunks() {
+        let mut request = create_test_request();
+        request.n = Some(2);
+        let response = CompletionGenerator::generate_response(&request);
+        let chunks = CompletionGenerator::generate_chunks(&request, &response);
+
+        assert!(chunks.iter().all(|chunk| chunk.id == response.id));
+        assert!(chunks.iter().all(|chunk| chunk.object == "text_completion"));
+
+        for choice in &response.choices {
+            let choice_chunks: Vec<_> = chunks
+                .iter()
+                .map(|chunk| &chunk.choices[0])
+                .filter(|chunk_choice| chunk_choice.index == choice.index)
+                .collect();
+
+            let streamed: String = choice_chunks.iter().map(|c| c.text.as_str()).collect();
+            assert_eq!(streamed, choice.text);
+
+            let last = choice_chunks.last().unwrap();
+            assert_eq!(last.finish_reason.as_ref(), Some(&choice.finish_reason));
+            assert!(
+                choice_chunks[..choice_chunks.len() - 1]
+                    .iter()
+                    .all(|c| c.finish_reason.is_none() && c.logprobs.is_none())
+            );
+        }
+    }
+
+    #[test]
+    fn test_generate_chunks_with_logprobs() {
+        let mut request = create_test_request();
+        request.logprobs = Some(3);
+        let response = CompletionGenerator::generate_response(&request);
+        let chunks = CompletionGenerator::generate_chunks(&request, &response);
+
+        let mut expected_offset = 0;
+        for chunk in &chunks[..chunks.len() - 1] {
+            let choice = &chunk.choices[0];
+            let logprobs = choice
+                .logprobs
+                .as_ref()
+                .expect("Chunk should carry logprobs");
+            assert_eq!(logprobs.tokens, vec![choice.text.clone()]);
+            assert_eq!(logprobs.text_offset, vec![expected_offset]);
+
+            let top = logprobs.top_logprobs.as_ref().unwrap()[0].as_ref().unwrap();
+            assert!(top.len() <= 3);
+            assert!(top.contains_key(&choice.text));
+            expected_offset += choice.text.chars().count() as u32;
+        }
+
+        // The streamed log probabilities are those of the whole response, token by token
+        let full = response.choices[0].logprobs.as_ref().unwrap();
+        let streamed: Vec<_> = chunks[..chunks.len() - 1]
+            .iter()
+            .map(|chunk| chunk.choices[0].logprobs.as_ref().unwrap())
+            .collect();
+        assert_eq!(streamed.len(), full.tokens.len());
+        for (index, logprobs) in streamed.iter().enumerate() {
+            assert_eq!(logprobs.tokens[0], full.tokens[index]);
+            assert_eq!(logprobs.token_logprobs[0], full.token_logprobs[index]);
+            assert_eq!(logprobs.text_offset[0], full.text_offset[index]);
+        }
+    }
+
+    #[test]
+    fn test_logprobs_offsets_count_chars() {
+        let logprobs = CompletionGenerator::generate_logprobs("Café au lait", 1);
+        assert_eq!(logprobs.tokens, vec!["Café", " au", " lait"]);
+        assert_eq!(logprobs.text_offset, vec![0, 4, 7]);
+    }
+
+    #[test]
+    fn test_generate_chunks_include_usage() {
+        let mut request = create_test_request();
+        request.stream = Some(true);
+        request.stream_options = Some(StreamOptions {
+            include_usage: Some(true),
+        });
+        let response = CompletionGenerator::generate_response(&request);
+        let chunks = CompletionGenerator::generate_chunks(&request, &response);
+
+        let last = chunks.last().unwrap();
+        assert!(last.choices.is_empty());
+        let usage = last.usage.as_ref().expect("Final chunk should carry usage");
+        assert_eq!(usage.total_tokens, response.usage.total_tokens);
+        assert!(chunks[..chunks.len() - 1].iter().all(|c| c.usage.is_none()));
+    }
+
     #[test]
     fn test_text_truncation() {
         let long_text = "This is a very long text that should be truncated when max tokens is low";
-        let truncated = CompletionGenerator::truncate_to_tokens(long_text, 5);
+        let truncated =
+            CompletionGenerator::truncate_to_tokens("gpt-3.5-turbo-instruct", long_text, 5);
+
+        assert_eq!(truncated, "This is a very long");
+        assert_eq!(
+            tokenizer::count_tokens("gpt-3.5-turbo-instruct", &truncated),
+            5
+        );
+
+        // Code counts more tokens per character than prose
+        let code = "\n```rust\nfn example() {\n    println!(\"Hello, world!\");\n}\n```";
+        let truncated = CompletionGenerator::truncate_to_tokens("davinci-002", code, 8);
+        assert_eq!(tokenizer::count_tokens("davinci-002", &truncated), 8);
 
-        assert!(truncated.len() < long_text.len());
-        assert!(truncated.len() <= 20); // 5 tokens * 4 chars approximation
+        // Cuts never split a multi-byte character
+        let truncated =
+            CompletionGenerator::truncate_to_tokens("davinci-002", "héllo wörld ☃☃☃", 6);
+        assert!("héllo wörld ☃☃☃".starts_with(&truncated));
     }
 }
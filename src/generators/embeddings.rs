@@ -10,6 +10,7 @@
 //! - **Multiple Input Types**: Supports string, string array, integer array, and nested integer array inputs
 //! - **Proper Dimensionality**: Generates 1536-dimensional vectors for text-embedding-ada-002 (default)
 //! - **Configurable Dimensions**: Supports custom dimensions when specified in request
+//! - **Encoding Formats**: Returns float lists, or base64 float32 arrays for `encoding_format: "base64"`
 //! - **Usage Statistics**: Generates realistic token usage counts
 //! - **Proper Formatting**: All responses conform to OpenAI's embedding response format
 //!
@@ -42,51 +43,83 @@
 //!
 //! This approach ensures that the same input always produces the same embedding vector,
 //! which is important for consistent testing behavior.
+//!
+//! ## Semantic Mode
+//!
+//! Hash-based vectors of related texts are unrelated: "dog" and "dogs" are as far apart as
+//! "dog" and "tax return". [`EmbeddingMode::Semantic`] instead builds each vector from the
+//! text's features, its lowercased words and their character trigrams. Every feature is
+//! hashed to a pseudo-random direction, and the vector is the normalized sum of those
+//! directions, counting each feature as often as it occurs (feature hashing with a random
+//! projection). Texts sharing words, or word stems, get a high cosine similarity, so
+//! retrieval tests can assert that relevant documents rank first. The vectors stay deterministic and unit-length.
+
+use std::collections::BTreeMap;
 
 use crate::models::{
-    requests::{CreateEmbeddingRequest, EmbeddingInput},
-    responses::{CreateEmbeddingResponse, EmbeddingData, EmbeddingUsage},
+    requests::CreateEmbeddingRequest,
+    responses::{CreateEmbeddingResponse, EmbeddingData, EmbeddingUsage, EmbeddingVector},
 };
 
+/// How embedding vectors are derived from the input text
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum EmbeddingMode {
+    /// A pseudo-random vector seeded by a hash of the whole text
+    #[default]
+    Hash,
+    /// A random projection of the text's word and character trigram features
+    Semantic,
+}
+
 /// Generator for creating fake embedding responses that conform to OpenAI API specifications
 pub struct EmbeddingGenerator;
 
 impl EmbeddingGenerator {
     /// Generate a fake embedding response based on the request
     pub fn generate_response(request: &CreateEmbeddingRequest) -> CreateEmbeddingResponse {
-        let input_strings = Self::extract_input_strings(&request.input);
-        let dimensions = Self::determine_dimensions(request);
+        Self::generate_response_with_mode(request, EmbeddingMode::Hash)
+    }
+
+    /// Generate a fake embedding response with vectors derived in the given mode
+    pub fn generate_response_with_mode(
+        request: &CreateEmbeddingRequest,
+        mode: EmbeddingMode,
+    ) -> CreateEmbeddingResponse {
+        Self::generate_response_with_dimensions(request, Self::determine_dimensions(request), mode)
+    }
+
+    /// Generate a fake embedding response with vectors of the given length
+    ///
+    /// The server uses this to apply the default dimensions of the models in its registry.
+    pub fn generate_response_with_dimensions(
+        request: &CreateEmbeddingRequest,
+        dimensions: usize,
+        mode: EmbeddingMode,
+    ) -> CreateEmbeddingResponse {
+        let input_strings = request.get_input_strings();
 
         let mut data = Vec::new();
+        let base64 = request.encoding_format.as_deref() == Some("base64");
+
         for (index, input_text) in input_strings.iter().enumerate() {
-            let embedding_vector = Self::generate_embedding_vector(input_text, dimensions);
+            let values = match mode {
+                EmbeddingMode::Hash => Self::generate_embedding_vector(input_text, dimensions),
+                EmbeddingMode::Semantic => Self::generate_semantic_vector(input_text, dimensions),
+            };
+            let embedding_vector = if base64 {
+                EmbeddingVector::base64(&values)
+            } else {
+                EmbeddingVector::Float(values)
+            };
             let embedding_data = EmbeddingData::new(embedding_vector, index as u32);
             data.push(embedding_data);
         }
 
-        let usage = Self::generate_usage(&input_strings);
+        let usage = Self::generate_usage(request);
 
         CreateEmbeddingResponse::new(data, request.model.clone(), usage)
     }
 
-    /// Extract input strings from the various input types
-    fn extract_input_strings(input: &EmbeddingInput) -> Vec<String> {
-        match input {
-            EmbeddingInput::String(s) => vec![s.clone()],
-            EmbeddingInput::StringArray(arr) => arr.clone(),
-            EmbeddingInput::IntegerArray(arr) => {
-                // Convert integer array to a string representation
-                vec![format!("{:?}", arr)]
-            }
-            EmbeddingInput::IntegerArrayArray(arr) => {
-                // Convert each integer array to a string representation
-                arr.iter()
-                    .map(|inner_arr| format!("{inner_arr:?}"))
-                    .collect()
-            }
-        }
-    }
-
     /// Determine the number of dimensions for the embedding vectors
     fn determine_dimensions(request: &CreateEmbeddingRequest) -> usize {
         // If dimensions are explicitly specified, use them
@@ -139,6 +172,58 @@ impl EmbeddingGenerator {
         vector
     }
 
+    /// Generate a vector from the word and character trigram features of the input text
+    ///
+    /// Every feature adds a pseudo-random direction seeded by its hash, so texts sharing
+    /// words, or word stems through their trigrams, get vectors with a high cosine similarity.
+    /// Texts without any words fall back to the hash-based vector.
+    fn generate_semantic_vector(input_text: &str, dimensions: usize) -> Vec<f64> {
+        // Features are counted in a sorted map so the result does not depend on iteration order
+        let mut features: BTreeMap<String, f64> = BTreeMap::new();
+        for word in input_text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !word.is_empty())
+        {
+            let word = word.to_lowercase();
+            let padded: Vec<char> = format!("<{word}>").chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                *features.entry(format!("#{trigram}")).or_default() += 1.0;
+            }
+            *features.entry(word).or_default() += 1.0;
+        }
+
+        if features.is_empty() {
+            return Self::generate_embedding_vector(input_text, dimensions);
+        }
+
+        let mut vector = vec![0.0; dimensions];
+        for (feature, weight) in &features {
+            Self::add_feature_direction(&mut vector, feature, *weight);
+        }
+
+        Self::normalize_vector(&mut vector);
+
+        vector
+    }
+
+    /// Add the pseudo-random direction of a feature to a vector
+    fn add_feature_direction(vector: &mut [f64], feature: &str, weight: f64) {
+        // SplitMix64 spreads even similar seeds over unrelated sequences
+        let mut state = Self::hash_string(feature);
+        for value in vector.iter_mut() {
+            state = state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+            let mut z = state;
+            z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+            z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+            z ^= z >> 31;
+
+            // Uniform in [-1, 1)
+            let unit = (z >> 11) as f64 / (1u64 << 53) as f64;
+            *value += weight * (unit * 2.0 - 1.0);
+        }
+    }
+
     /// Normalize a vector to unit length
     fn normalize_vector(vector: &mut [f64]) {
         let magnitude: f64 = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
@@ -160,28 +245,17 @@ impl EmbeddingGenerator {
     }
 
     /// Generate usage statistics based on the input
-    fn generate_usage(input_strings: &[String]) -> EmbeddingUsage {
-        let total_text = input_strings.join(" ");
-        let prompt_tokens = Self::estimate_tokens(&total_text);
-        EmbeddingUsage::new(prompt_tokens)
-    }
-
-    /// Estimate token count for a given text
-    fn estimate_tokens(text: &str) -> u32 {
-        // Rough approximation: 1 token ≈ 4 characters for English text
-        // Add some variation based on text characteristics
-        let base_estimate = (text.len() / 4).max(1) as u32;
-        let word_count = text.split_whitespace().count() as u32;
-
-        // Embedding tokens are typically more efficient than completion tokens
-        // Use a slightly lower estimate
-        ((base_estimate + word_count) as f64 * 0.8) as u32
+    ///
+    /// Text inputs are tokenized for the model; token ID inputs count one token per ID.
+    fn generate_usage(request: &CreateEmbeddingRequest) -> EmbeddingUsage {
+        EmbeddingUsage::new(request.input_token_counts().iter().sum())
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::requests::EmbeddingInput;
 
     fn create_test_request() -> CreateEmbeddingRequest {
         CreateEmbeddingRequest {
@@ -237,6 +311,30 @@ mod tests {
         assert_eq!(response.data[0].embedding.len(), 512);
     }
 
+    #[test]
+    fn test_base64_encoding_format() {
+        let request = create_test_request();
+        let floats = EmbeddingGenerator::generate_response(&request);
+
+        let mut request = create_test_request();
+        request.encoding_format = Some("base64".to_string());
+        let response = EmbeddingGenerator::generate_response(&request);
+
+        assert!(matches!(
+            response.data[0].embedding,
+            EmbeddingVector::Base64(_)
+        ));
+        assert_eq!(response.data[0].embedding.len(), 1536);
+        for (decoded, value) in response.data[0]
+            .embedding
+            .to_floats()
+            .iter()
+            .zip(floats.data[0].embedding.to_floats())
+        {
+            assert_eq!(*decoded, f64::from(value as f32));
+        }
+    }
+
     #[test]
     fn test_different_models() {
         let models_and_dimensions = vec![
@@ -274,7 +372,7 @@ mod tests {
         let request = create_test_request();
         let response = EmbeddingGenerator::generate_response(&request);
 
-        let vector = &response.data[0].embedding;
+        let vector = response.data[0].embedding.to_floats();
         let magnitude: f64 = vector.iter().map(|x| x * x).sum::<f64>().sqrt();
 
         // Vector should be approximately unit length (allowing for floating point precision)
@@ -343,36 +441,49 @@ mod tests {
     }
 
     #[test]
-    fn test_token_estimation() {
-        assert!(EmbeddingGenerator::estimate_tokens("hello") > 0);
-        assert!(
-            EmbeddingGenerator::estimate_tokens("hello world test")
-                > EmbeddingGenerator::estimate_tokens("hello")
+    fn test_usage_counts_tokens() {
+        let mut request = create_test_request();
+        request.model = "text-embedding-3-small".to_string();
+        request.input =
+            EmbeddingInput::StringArray(vec!["Hello, world!".to_string(), "hello".to_string()]);
+        assert_eq!(
+            EmbeddingGenerator::generate_usage(&request).prompt_tokens,
+            5
+        );
+
+        request.input = EmbeddingInput::IntegerArrayArray(vec![vec![9906, 11, 1917], vec![15339]]);
+        assert_eq!(
+            EmbeddingGenerator::generate_usage(&request).prompt_tokens,
+            4
         );
     }
 
     #[test]
-    fn test_extract_input_strings() {
-        // Test string input
-        let input1 = EmbeddingInput::String("test".to_string());
-        let result1 = EmbeddingGenerator::extract_input_strings(&input1);
-        assert_eq!(result1, vec!["test"]);
-
-        // Test string array input
-        let input2 = EmbeddingInput::StringArray(vec!["test1".to_string(), "test2".to_string()]);
-        let result2 = EmbeddingGenerator::extract_input_strings(&input2);
-        assert_eq!(result2, vec!["test1", "test2"]);
-
-        // Test integer array input
-        let input3 = EmbeddingInput::IntegerArray(vec![1, 2, 3]);
-        let result3 = EmbeddingGenerator::extract_input_strings(&input3);
-        assert_eq!(result3.len(), 1);
-        assert!(result3[0].contains("1"));
-
-        // Test nested integer array input
-        let input4 = EmbeddingInput::IntegerArrayArray(vec![vec![1, 2], vec![3, 4]]);
-        let result4 = EmbeddingGenerator::extract_input_strings(&input4);
-        assert_eq!(result4.len(), 2);
+    fn test_token_id_input() {
+        let text = create_test_request();
+        let text_response = EmbeddingGenerator::generate_response(&text);
+
+        // "Hello world" in cl100k_base
+        let mut tokens = create_test_request();
+        tokens.input = EmbeddingInput::IntegerArray(vec![9906, 1917]);
+        let tokens_response = EmbeddingGenerator::generate_response(&tokens);
+
+        assert_eq!(
+            tokens_response.data[0].embedding,
+            text_response.data[0].embedding
+        );
+        assert_eq!(
+            tokens_response.usage.prompt_tokens,
+            text_response.usage.prompt_tokens
+        );
+
+        tokens.input = EmbeddingInput::IntegerArrayArray(vec![vec![9906, 1917], vec![9906]]);
+        let tokens_response = EmbeddingGenerator::generate_response(&tokens);
+        assert_eq!(tokens_response.data.len(), 2);
+        assert_eq!(
+            tokens_response.data[0].embedding,
+            text_response.data[0].embedding
+        );
     }
 
     #[test]
@@ -380,14 +491,66 @@ mod tests {
         let request = create_test_request();
         let response = EmbeddingGenerator::generate_response(&request);
 
-        let vector = &response.data[0].embedding;
+        let vector = response.data[0].embedding.to_floats();
 
         // All values should be in a reasonable range (since we normalize, they should be between -1 and 1)
-        for &value in vector {
+        for value in vector {
             assert!(
                 (-1.0..=1.0).contains(&value),
                 "Vector value {value} is out of range [-1, 1]"
             );
         }
     }
+
+    fn semantic_embedding(text: &str) -> Vec<f64> {
+        let mut request = create_test_request();
+        request.input = EmbeddingInput::String(text.to_string());
+        EmbeddingGenerator::generate_response_with_mode(&request, EmbeddingMode::Semantic).data[0]
+            .embedding
+            .to_floats()
+    }
+
+    fn cosine_similarity(a: &[f64], b: &[f64]) -> f64 {
+        a.iter().zip(b).map(|(x, y)| x * y).sum()
+    }
+
+    #[test]
+    fn test_semantic_embeddings_share_words() {
+        let dog = semantic_embedding("dog");
+        let dogs = semantic_embedding("Dogs");
+        let unrelated = semantic_embedding("quarterly tax return");
+
+        assert!(cosine_similarity(&dog, &dogs) > 0.3);
+        assert!(cosine_similarity(&dog, &unrelated).abs() < 0.1);
+
+        // Deterministic and unit-normalized
+        assert_eq!(dog, semantic_embedding("dog"));
+        let magnitude = cosine_similarity(&dog, &dog).sqrt();
+        assert!((magnitude - 1.0).abs() < 1e-10);
+    }
+
+    #[test]
+    fn test_semantic_embeddings_rank_relevant_documents() {
+        let query = semantic_embedding("How do I reset my password?");
+        let documents = [
+            "Shipping usually takes three to five business days.",
+            "To reset your password, open the login page and click 'Forgot password'.",
+            "Our offices are closed on public holidays.",
+        ];
+
+        let best = documents
+            .iter()
+            .map(|document| cosine_similarity(&query, &semantic_embedding(document)))
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(index, _)| index);
+        assert_eq!(best, Some(1));
+    }
+
+    #[test]
+    fn test_semantic_embeddings_without_words() {
+        let vector = semantic_embedding("?!");
+        assert_eq!(vector.len(), 1536);
+        assert!(vector.iter().any(|value| *value != 0.0));
+    }
 }
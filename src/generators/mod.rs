@@ -4,4 +4,4 @@ pub mod embeddings;
 
 pub use chat_completions::ChatCompletionGenerator;
 pub use completions::CompletionGenerator;
-pub use embeddings::EmbeddingGenerator;
+pub use embeddings::{EmbeddingGenerator, EmbeddingMode};
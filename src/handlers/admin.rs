@@ -0,0 +1,267 @@
+//! # Admin API Handlers
+//!
+//! This module implements the runtime admin API mounted under `/admin`. It lets tests register,
+//! list and remove response expectations without restarting the server, and inspect the
+//! requests recorded in the request journal. The routes are guarded by the admin key
+//! (`--admin-key`) rather than the API key.
+//!
+//! ## Endpoints
+//!
+//! - `POST /admin/expectations`: Register an expectation
+//! - `GET /admin/expectations`: List registered expectations
+//! - `DELETE /admin/expectations`: Remove all expectations
+//! - `DELETE /admin/expectations/{id}`: Remove a single expectation
+//! - `GET /admin/requests`: List recorded requests, filtered by `endpoint` and `model`
+//! - `DELETE /admin/requests`: Clear the request journal
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use poem::{Route, get, delete, EndpointExt};
+//! use openai_mock::auth::AdminAuthMiddleware;
+//! use openai_mock::handlers::admin::{
+//!     clear_expectations, create_expectation, delete_expectation, list_expectations,
+//! };
+//! use openai_mock::state::AppState;
+//!
+//! let app = Route::new()
+//!     .at(
+//!         "/admin/expectations",
+//!         get(list_expectations)
+//!             .post(create_expectation)
+//!             .delete(clear_expectations),
+//!     )
+//!     .at("/admin/expectations/:id", delete(delete_expectation))
+//!     .with(AdminAuthMiddleware::new("sk-mock-admin-key-12345"))
+//!     .data(AppState::default());
+//! ```
+
+use poem::{
+    IntoResponse, Response, Result, handler,
+    http::StatusCode,
+    web::{Data, Json, Path, Query},
+};
+use serde::Serialize;
+use serde_json;
+
+use crate::expectations::CreateExpectationRequest;
+use crate::journal::JournalFilter;
+use crate::models::responses::ErrorResponse;
+use crate::state::AppState;
+
+/// Handler for POST /admin/expectations
+///
+/// Registers an expectation that answers matching chat completion requests. The body is a
+/// fixture (`name`, `match`, `response`) with an optional `times` limit:
+///
+/// ```json
+/// {
+///   "name": "greeting",
+///   "match": {"user_message": "(?i)hello"},
+///   "response": {"content": "Scripted hello"},
+///   "times": 1
+/// }
+/// ```
+///
+/// Returns `201 Created` with the registered expectation, including its `id`, or
+/// `400 Bad Request` if the fixture is invalid.
+#[handler]
+pub async fn create_expectation(
+    body: Json<CreateExpectationRequest>,
+    state: Data<&AppState>,
+) -> Result<impl IntoResponse> {
+    match state.expectations.add(body.0) {
+        Ok(expectation) => Ok(json_response(StatusCode::CREATED, &expectation)),
+        Err(err) => Ok(json_response(
+            StatusCode::BAD_REQUEST,
+            &ErrorResponse::invalid_request_error(err.to_string()),
+        )),
+    }
+}
+
+/// Handler for GET /admin/expectations
+///
+/// Lists the registered expectations in matching order, with how many more requests each
+/// answers (`remaining`) and how many it has answered (`match_count`).
+#[handler]
+pub async fn list_expectations(state: Data<&AppState>) -> Result<impl IntoResponse> {
+    let expectations = serde_json::json!({
+        "object": "list",
+        "data": state.expectations.list(),
+    });
+
+    Ok(json_response(StatusCode::OK, &expectations))
+}
+
+/// Handler for DELETE /admin/expectations
+///
+/// Removes all registered expectations and reports how many were removed.
+#[handler]
+pub async fn clear_expectations(state: Data<&AppState>) -> Result<impl IntoResponse> {
+    let deleted = state.expectations.clear();
+
+    Ok(json_response(
+        StatusCode::OK,
+        &serde_json::json!({ "deleted": deleted }),
+    ))
+}
+
+/// Handler for DELETE /admin/expectations/{id}
+///
+/// Removes a single expectation, returning `404 Not Found` if no expectation has the ID.
+#[handler]
+pub async fn delete_expectation(
+    Path(id): Path<String>,
+    state: Data<&AppState>,
+) -> Result<impl IntoResponse> {
+    if !state.expectations.remove(&id) {
+        let error_response =
+            ErrorResponse::invalid_request_error(format!("No expectation with ID '{id}'"))
+                .with_param("id".to_string());
+        return Ok(json_response(StatusCode::NOT_FOUND, &error_response));
+    }
+
+    Ok(json_response(
+        StatusCode::OK,
+        &serde_json::json!({ "id": id, "deleted": true }),
+    ))
+}
+
+/// Handler for GET /admin/requests
+///
+/// Lists the recorded requests, oldest first. The optional `endpoint` (e.g. `chat/completions`
+/// or `/v1/chat/completions`) and `model` query parameters narrow the list:
+///
+/// ```text
+/// GET /admin/requests?endpoint=chat/completions&model=gpt-4o
+/// ```
+///
+/// Each entry holds the request `headers` and parsed `body`, and the `response` status and body
+/// (omitted for streamed responses).
+#[handler]
+pub async fn list_requests(
+    Query(filter): Query<JournalFilter>,
+    state: Data<&AppState>,
+) -> Result<impl IntoResponse> {
+    let requests = serde_json::json!({
+        "object": "list",
+        "data": state.journal.list(&filter),
+    });
+
+    Ok(json_response(StatusCode::OK, &requests))
+}
+
+/// Handler for DELETE /admin/requests
+///
+/// Clears the request journal and reports how many entries were removed.
+#[handler]
+pub async fn clear_requests(state: Data<&AppState>) -> Result<impl IntoResponse> {
+    let deleted = state.journal.clear();
+
+    Ok(json_response(
+        StatusCode::OK,
+        &serde_json::json!({ "deleted": deleted }),
+    ))
+}
+
+/// Serialize a value into a JSON response with the given status
+fn json_response<T: Serialize>(status: StatusCode, value: &T) -> Response {
+    match serde_json::to_string(value) {
+        Ok(json_body) => Response::builder()
+            .status(status)
+            .header("content-type", "application/json")
+            .body(json_body),
+        Err(_) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("content-type", "application/json")
+            .body(r#"{"error":{"message":"Internal server error","type":"server_error"}}"#),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{EndpointExt, Route, delete, get, test::TestClient};
+
+    fn create_test_app() -> impl poem::Endpoint {
+        Route::new()
+            .at(
+                "/admin/expectations",
+                get(list_expectations)
+                    .post(create_expectation)
+                    .delete(clear_expectations),
+            )
+            .at("/admin/expectations/:id", delete(delete_expectation))
+            .at("/admin/requests", get(list_requests).delete(clear_requests))
+            .data(AppState::default())
+    }
+
+    #[tokio::test]
+    async fn test_expectation_lifecycle() {
+        let client = TestClient::new(create_test_app());
+
+        let resp = client
+            .post("/admin/expectations")
+            .body_json(&serde_json::json!({
+                "name": "greeting",
+                "response": {"content": "Scripted"},
+                "times": 2
+            }))
+            .send()
+            .await;
+        resp.assert_status(StatusCode::CREATED);
+        let created: serde_json::Value = resp.json().await.value().deserialize();
+        let id = created["id"].as_str().unwrap().to_string();
+        assert_eq!(created["remaining"], 2);
+
+        let resp = client.get("/admin/expectations").send().await;
+        resp.assert_status_is_ok();
+        let listed: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(listed["data"][0]["id"], id.as_str());
+        assert_eq!(listed["data"][0]["name"], "greeting");
+
+        let resp = client
+            .delete(format!("/admin/expectations/{id}"))
+            .send()
+            .await;
+        resp.assert_status_is_ok();
+
+        let resp = client
+            .delete(format!("/admin/expectations/{id}"))
+            .send()
+            .await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_create_invalid_expectation() {
+        let client = TestClient::new(create_test_app());
+
+        let resp = client
+            .post("/admin/expectations")
+            .body_json(&serde_json::json!({"response": {}}))
+            .send()
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["error"]["type"], "invalid_request_error");
+    }
+
+    #[tokio::test]
+    async fn test_clear_expectations() {
+        let client = TestClient::new(create_test_app());
+
+        for _ in 0..2 {
+            client
+                .post("/admin/expectations")
+                .body_json(&serde_json::json!({"response": {"content": "x"}}))
+                .send()
+                .await
+                .assert_status(StatusCode::CREATED);
+        }
+
+        let resp = client.delete("/admin/expectations").send().await;
+        resp.assert_status_is_ok();
+        resp.assert_json(serde_json::json!({"deleted": 2})).await;
+    }
+}
@@ -11,6 +11,8 @@
 //! - **Response Generation**: Uses the ChatCompletionGenerator to create realistic fake responses
 //! - **Content Type Handling**: Properly handles JSON request/response content types
 //! - **Tool Support**: Handles function/tool calling in chat completions
+//! - **Streaming**: Emits `chat.completion.chunk` server-sent events when `stream` is true
+//! - **Fixtures**: Answers from the first matching scripted fixture before falling back to the generator
 //!
 //! ## Usage
 //!
@@ -26,11 +28,21 @@
 //!     .with(AuthMiddleware::new("sk-mock-openai-api-key-12345"));
 //! ```
 
-use poem::{IntoResponse, Response, Result, handler, http::StatusCode, web::Json};
+use poem::{
+    IntoResponse, Response, Result, handler,
+    http::StatusCode,
+    web::{Data, Json},
+};
 use serde_json;
 
+use crate::fixtures::FixtureErrorReply;
 use crate::generators::ChatCompletionGenerator;
 use crate::models::{requests::CreateChatCompletionRequest, responses::ErrorResponse};
+use crate::rate_limit::TokenUsage;
+use crate::registry::Capability;
+use crate::state::AppState;
+use crate::streaming;
+use crate::tokenizer;
 
 /// Handler for POST /v1/chat/completions
 ///
@@ -54,6 +66,16 @@ use crate::models::{requests::CreateChatCompletionRequest, responses::ErrorRespo
 /// - `choices`: Array of completion choices with messages
 /// - `usage`: Token usage statistics
 ///
+/// When `stream` is true, the response is instead a `text/event-stream` of
+/// `chat.completion.chunk` objects terminated by `data: [DONE]`. With
+/// `stream_options.include_usage`, a final chunk with empty `choices` carries the `usage`.
+///
+/// ## Fixtures
+///
+/// When the application state holds runtime expectations or response fixtures, the first one
+/// matching the request supplies the message content or tool calls, or an error response with the fixture's status.
+/// Requests matching no fixture are answered by the `ChatCompletionGenerator`.
+///
 /// ## Error Handling
 ///
 /// Returns appropriate HTTP status codes and OpenAI-formatted error responses for:
@@ -61,6 +83,12 @@ use crate::models::{requests::CreateChatCompletionRequest, responses::ErrorRespo
 /// - Missing required fields (400 Bad Request)
 /// - Invalid parameter values (400 Bad Request)
 /// - Empty messages array (400 Bad Request)
+/// - `tool_choice` naming a function that is not in `tools` (400 Bad Request)
+/// - Tool messages whose `tool_call_id` matches no preceding tool call (400 Bad Request)
+/// - `json_schema` response formats or `strict` functions breaking the rules of strict mode,
+///   with the offending `param` (400 Bad Request)
+/// - Messages and `max_tokens` exceeding the model's context window (400 Bad Request)
+/// - Unknown models, or models that do not support this endpoint (404 Not Found)
 ///
 /// # Examples
 ///
@@ -180,13 +208,14 @@ use crate::models::{requests::CreateChatCompletionRequest, responses::ErrorRespo
 #[handler]
 pub async fn create_chat_completion(
     body: Json<CreateChatCompletionRequest>,
+    state: Option<Data<&AppState>>,
 ) -> Result<impl IntoResponse> {
     // Extract the request from the JSON wrapper
     let request = body.0;
 
     // Validate the request
     if let Err(validation_error) = request.validate() {
-        let error_response = ErrorResponse::invalid_request_error(validation_error);
+        let error_response = validation_error.to_error_response();
         let json_body = match serde_json::to_string(&error_response) {
             Ok(json) => json,
             Err(_) => {
@@ -200,8 +229,65 @@ pub async fn create_chat_completion(
             .body(json_body));
     }
 
+    // Reject models the registry does not serve from this endpoint
+    let models = state
+        .as_ref()
+        .map(|state| state.models.clone())
+        .unwrap_or_default();
+    let model = match models.check(&request.model, Capability::Chat) {
+        Ok(model) => model,
+        Err(error) => return Ok(error.to_response()),
+    };
+
+    // Reject conversations that do not fit in the model's context window
+    let prompt_tokens = tokenizer::count_chat_tokens(
+        &request.model,
+        &request.messages,
+        request.tools.as_deref().unwrap_or_default(),
+    );
+    if let Err(error) =
+        model.check_context_length(Capability::Chat, prompt_tokens, request.max_tokens)
+    {
+        return Ok(error.to_response());
+    }
+
+    // Answer from the first matching fixture, falling back to the generator
+    let fixture = state
+        .as_ref()
+        .and_then(|state| state.find_fixture(&request));
+    if let Some(fixture) = &fixture {
+        tracing::debug!("Chat completion answered by fixture {:?}", fixture.name());
+        if let Some(error) = &fixture.response().error {
+            return Ok(create_fixture_error_response(error));
+        }
+    }
+
+    // Without application state, responses are not delayed
+    let latency = state.map(|state| state.latency.clone()).unwrap_or_default();
+
     // Generate the chat completion response
-    let chat_completion_response = ChatCompletionGenerator::generate_response(&request);
+    let chat_completion_response = match &fixture {
+        Some(fixture) => {
+            ChatCompletionGenerator::generate_fixture_response(&request, fixture.response())
+        }
+        None => ChatCompletionGenerator::generate_response(&request),
+    };
+
+    // Stream the generated response as chunks if requested
+    if request.stream.unwrap_or(false) {
+        let chunks = ChatCompletionGenerator::generate_chunks(&request, &chat_completion_response);
+        return match streaming::to_events(&chunks) {
+            Ok(events) => {
+                // Pace the chunks like a model generating tokens
+                let carries_tokens: Vec<bool> =
+                    chunks.iter().map(|chunk| chunk.carries_tokens()).collect();
+                let delays = latency.stream_delays(&request.model, &carries_tokens);
+                let response = streaming::into_paced_sse(events, delays).into_response();
+                Ok(TokenUsage(chat_completion_response.usage.total_tokens).attach(response))
+            }
+            Err(_) => Ok(create_internal_error_response()),
+        };
+    }
 
     // Serialize the response to JSON
     let json_body = match serde_json::to_string(&chat_completion_response) {
@@ -211,11 +297,34 @@ pub async fn create_chat_completion(
         }
     };
 
-    // Return the successful response
-    Ok(Response::builder()
+    // Take as long as the model would to produce the response
+    let delay = latency.response_delay(
+        &request.model,
+        chat_completion_response.usage.completion_tokens,
+    );
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+
+    // Return the successful response, noting the tokens used for rate limiting
+    let response = Response::builder()
         .status(StatusCode::OK)
         .header("content-type", "application/json")
-        .body(json_body))
+        .body(json_body);
+    Ok(TokenUsage(chat_completion_response.usage.total_tokens).attach(response))
+}
+
+/// Create the error response scripted by a fixture
+fn create_fixture_error_response(error: &FixtureErrorReply) -> Response {
+    let json_body = match serde_json::to_string(&error.to_error_response()) {
+        Ok(json) => json,
+        Err(_) => return create_internal_error_response(),
+    };
+
+    Response::builder()
+        .status(StatusCode::from_u16(error.status).unwrap_or(StatusCode::BAD_REQUEST))
+        .header("content-type", "application/json")
+        .body(json_body)
 }
 
 /// Create a generic internal server error response
@@ -250,7 +359,7 @@ mod tests {
             model: "gpt-3.5-turbo".to_string(),
             messages: vec![ChatCompletionMessage {
                 role: ChatCompletionRole::User,
-                content: Some("Hello, world!".to_string()),
+                content: Some("Hello, world!".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
@@ -259,14 +368,17 @@ mod tests {
             top_p: None,
             n: None,
             stream: None,
+            stream_options: None,
             stop: None,
             max_tokens: Some(100),
             presence_penalty: None,
             frequency_penalty: None,
             logit_bias: None,
             user: None,
+            response_format: None,
             tools: None,
             tool_choice: None,
+            parallel_tool_calls: None,
         }
     }
 
@@ -309,7 +421,7 @@ mod tests {
             0,
             ChatCompletionMessage {
                 role: ChatCompletionRole::System,
-                content: Some("You are a helpful assistant.".to_string()),
+                content: Some("You are a helpful assistant.".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
@@ -327,28 +439,28 @@ mod tests {
         request.messages = vec![
             ChatCompletionMessage {
                 role: ChatCompletionRole::System,
-                content: Some("You are a helpful assistant.".to_string()),
+                content: Some("You are a helpful assistant.".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
             },
             ChatCompletionMessage {
                 role: ChatCompletionRole::User,
-                content: Some("Hello!".to_string()),
+                content: Some("Hello!".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
             },
             ChatCompletionMessage {
                 role: ChatCompletionRole::Assistant,
-                content: Some("Hi there! How can I help you?".to_string()),
+                content: Some("Hi there! How can I help you?".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
             },
             ChatCompletionMessage {
                 role: ChatCompletionRole::User,
-                content: Some("What's the weather like?".to_string()),
+                content: Some("What's the weather like?".into()),
                 name: None,
                 tool_calls: None,
                 tool_call_id: None,
@@ -391,7 +503,7 @@ mod tests {
     fn test_message_validation_edge_cases() {
         // Test empty content for user message
         let mut request = create_test_request();
-        request.messages[0].content = Some("".to_string());
+        request.messages[0].content = Some("".into());
         assert!(request.validate().is_err());
 
         // Test missing content for user message
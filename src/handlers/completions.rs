@@ -10,6 +10,7 @@
 //! - **Error Handling**: Returns proper OpenAI-formatted error responses for invalid requests
 //! - **Response Generation**: Uses the CompletionGenerator to create realistic fake responses
 //! - **Content Type Handling**: Properly handles JSON request/response content types
+//! - **Streaming**: Emits `text_completion` server-sent events when `stream` is true
 //!
 //! ## Usage
 //!
@@ -25,11 +26,23 @@
 //!     .with(AuthMiddleware::new("sk-mock-openai-api-key-12345"));
 //! ```
 
-use poem::{IntoResponse, Response, Result, handler, http::StatusCode, web::Json};
+use poem::{
+    IntoResponse, Response, Result, handler,
+    http::StatusCode,
+    web::{Data, Json},
+};
 use serde_json;
 
 use crate::generators::CompletionGenerator;
-use crate::models::{requests::CreateCompletionRequest, responses::ErrorResponse};
+use crate::models::{
+    requests::{CreateCompletionRequest, PromptInput},
+    responses::ErrorResponse,
+};
+use crate::rate_limit::TokenUsage;
+use crate::registry::Capability;
+use crate::state::AppState;
+use crate::streaming;
+use crate::tokenizer;
 
 /// Handler for POST /v1/completions
 ///
@@ -53,12 +66,18 @@ use crate::models::{requests::CreateCompletionRequest, responses::ErrorResponse}
 /// - `choices`: Array of completion choices
 /// - `usage`: Token usage statistics
 ///
+/// When `stream` is true, the response is instead a `text/event-stream` of
+/// `text_completion` chunks, one per token, terminated by `data: [DONE]`. With
+/// `stream_options.include_usage`, a final chunk with empty `choices` carries the `usage`.
+///
 /// ## Error Handling
 ///
 /// Returns appropriate HTTP status codes and OpenAI-formatted error responses for:
 /// - Invalid JSON (400 Bad Request)
 /// - Missing required fields (400 Bad Request)
 /// - Invalid parameter values (400 Bad Request)
+/// - A prompt and `max_tokens` exceeding the model's context window (400 Bad Request)
+/// - Unknown models, or models that do not support this endpoint (404 Not Found)
 ///
 /// # Examples
 ///
@@ -110,7 +129,10 @@ use crate::models::{requests::CreateCompletionRequest, responses::ErrorResponse}
 /// }
 /// ```
 #[handler]
-pub async fn create_completion(body: Json<CreateCompletionRequest>) -> Result<impl IntoResponse> {
+pub async fn create_completion(
+    body: Json<CreateCompletionRequest>,
+    state: Option<Data<&AppState>>,
+) -> Result<impl IntoResponse> {
     // Extract the request from the JSON wrapper
     let request = body.0;
 
@@ -130,9 +152,54 @@ pub async fn create_completion(body: Json<CreateCompletionRequest>) -> Result<im
             .body(json_body));
     }
 
+    // Reject models the registry does not serve from this endpoint
+    let models = state
+        .as_ref()
+        .map(|state| state.models.clone())
+        .unwrap_or_default();
+    let model = match models.check(&request.model, Capability::Completion) {
+        Ok(model) => model,
+        Err(error) => return Ok(error.to_response()),
+    };
+
+    // Reject prompts that do not fit in the model's context window
+    let prompt_tokens = match &request.prompt {
+        PromptInput::String(prompt) => tokenizer::count_tokens(&request.model, prompt),
+        PromptInput::Array(prompts) => prompts
+            .iter()
+            .map(|prompt| tokenizer::count_tokens(&request.model, prompt))
+            .max()
+            .unwrap_or(0),
+    };
+    if let Err(error) = model.check_context_length(
+        Capability::Completion,
+        prompt_tokens,
+        Some(request.max_tokens.unwrap_or(16)),
+    ) {
+        return Ok(error.to_response());
+    }
+
+    let latency = state.map(|state| state.latency.clone()).unwrap_or_default();
+
     // Generate the completion response
     let completion_response = CompletionGenerator::generate_response(&request);
 
+    // Stream the generated response as chunks if requested
+    if request.stream.unwrap_or(false) {
+        let chunks = CompletionGenerator::generate_chunks(&request, &completion_response);
+        return match streaming::to_events(&chunks) {
+            Ok(events) => {
+                // Pace the chunks like a model generating tokens
+                let carries_tokens: Vec<bool> =
+                    chunks.iter().map(|chunk| chunk.carries_tokens()).collect();
+                let delays = latency.stream_delays(&request.model, &carries_tokens);
+                let response = streaming::into_paced_sse(events, delays).into_response();
+                Ok(TokenUsage(completion_response.usage.total_tokens).attach(response))
+            }
+            Err(_) => Ok(create_internal_error_response()),
+        };
+    }
+
     // Serialize the response to JSON
     let json_body = match serde_json::to_string(&completion_response) {
         Ok(json) => json,
@@ -141,11 +208,18 @@ pub async fn create_completion(body: Json<CreateCompletionRequest>) -> Result<im
         }
     };
 
-    // Return the successful response
-    Ok(Response::builder()
+    // Take as long as the model would to produce the response
+    let delay = latency.response_delay(&request.model, completion_response.usage.completion_tokens);
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+
+    // Return the successful response, noting the tokens used for rate limiting
+    let response = Response::builder()
         .status(StatusCode::OK)
         .header("content-type", "application/json")
-        .body(json_body))
+        .body(json_body);
+    Ok(TokenUsage(completion_response.usage.total_tokens).attach(response))
 }
 
 /// Create a generic internal server error response
@@ -183,6 +257,7 @@ mod tests {
             top_p: None,
             n: None,
             stream: None,
+            stream_options: None,
             logprobs: None,
             echo: None,
             stop: None,
@@ -26,11 +26,18 @@
 //!     .with(AuthMiddleware::new("sk-mock-openai-api-key-12345"));
 //! ```
 
-use poem::{IntoResponse, Response, Result, handler, http::StatusCode, web::Json};
+use poem::{
+    IntoResponse, Response, Result, handler,
+    http::StatusCode,
+    web::{Data, Json},
+};
 use serde_json;
 
 use crate::generators::EmbeddingGenerator;
 use crate::models::{requests::CreateEmbeddingRequest, responses::ErrorResponse};
+use crate::rate_limit::TokenUsage;
+use crate::registry::Capability;
+use crate::state::AppState;
 
 /// Handler for POST /v1/embeddings
 ///
@@ -63,6 +70,9 @@ use crate::models::{requests::CreateEmbeddingRequest, responses::ErrorResponse};
 /// - Invalid JSON (400 Bad Request)
 /// - Missing required fields (400 Bad Request)
 /// - Invalid parameter values (400 Bad Request)
+/// - More than 2048 inputs, or `dimensions` the model does not support (400 Bad Request)
+/// - Inputs longer than the model's context window (400 Bad Request)
+/// - Unknown models, or models that do not support this endpoint (404 Not Found)
 ///
 /// # Examples
 ///
@@ -110,28 +120,55 @@ use crate::models::{requests::CreateEmbeddingRequest, responses::ErrorResponse};
 /// }
 /// ```
 #[handler]
-pub async fn create_embedding(body: Json<CreateEmbeddingRequest>) -> Result<impl IntoResponse> {
+pub async fn create_embedding(
+    body: Json<CreateEmbeddingRequest>,
+    state: Option<Data<&AppState>>,
+) -> Result<impl IntoResponse> {
     // Extract the request from the JSON wrapper
     let request = body.0;
 
     // Validate the request
     if let Err(validation_error) = request.validate() {
         let error_response = ErrorResponse::invalid_request_error(validation_error);
-        let json_body = match serde_json::to_string(&error_response) {
-            Ok(json) => json,
-            Err(_) => {
-                return Ok(create_internal_error_response());
-            }
-        };
-
-        return Ok(Response::builder()
-            .status(StatusCode::BAD_REQUEST)
-            .header("content-type", "application/json")
-            .body(json_body));
+        return Ok(create_bad_request_response(&error_response));
     }
 
+    // Reject models the registry does not serve from this endpoint
+    let models = state
+        .as_ref()
+        .map(|state| state.models.clone())
+        .unwrap_or_default();
+    let model = match models.check(&request.model, Capability::Embedding) {
+        Ok(model) => model,
+        Err(error) => return Ok(error.to_response()),
+    };
+
+    // Apply the model's limits on `dimensions` and on the length of each input
+    if let Err(error_response) = model.check_dimensions(request.dimensions) {
+        return Ok(create_bad_request_response(&error_response));
+    }
+    for tokens in request.input_token_counts() {
+        if let Err(error) = model.check_context_length(Capability::Embedding, tokens, None) {
+            return Ok(error.to_response());
+        }
+    }
+
+    let mode = state
+        .as_ref()
+        .map(|state| state.embedding_mode)
+        .unwrap_or_default();
+    let latency = state.map(|state| state.latency.clone()).unwrap_or_default();
+
     // Generate the embedding response
-    let embedding_response = EmbeddingGenerator::generate_response(&request);
+    // Vectors have the model's default length unless the request sets `dimensions`
+    let embedding_response = match request.dimensions.or(model.embedding_dimensions) {
+        Some(dimensions) => EmbeddingGenerator::generate_response_with_dimensions(
+            &request,
+            dimensions as usize,
+            mode,
+        ),
+        None => EmbeddingGenerator::generate_response_with_mode(&request, mode),
+    };
 
     // Serialize the response to JSON
     let json_body = match serde_json::to_string(&embedding_response) {
@@ -141,11 +178,31 @@ pub async fn create_embedding(body: Json<CreateEmbeddingRequest>) -> Result<impl
         }
     };
 
-    // Return the successful response
-    Ok(Response::builder()
+    // Take as long as the model would to produce the response
+    let delay = latency.response_delay(&request.model, 0);
+    if !delay.is_zero() {
+        tokio::time::sleep(delay).await;
+    }
+
+    // Return the successful response, noting the tokens used for rate limiting
+    let response = Response::builder()
         .status(StatusCode::OK)
         .header("content-type", "application/json")
-        .body(json_body))
+        .body(json_body);
+    Ok(TokenUsage(embedding_response.usage.total_tokens).attach(response))
+}
+
+/// Create a `400 Bad Request` response from an OpenAI-formatted error
+fn create_bad_request_response(error_response: &ErrorResponse) -> Response {
+    let json_body = match serde_json::to_string(error_response) {
+        Ok(json) => json,
+        Err(_) => return create_internal_error_response(),
+    };
+
+    Response::builder()
+        .status(StatusCode::BAD_REQUEST)
+        .header("content-type", "application/json")
+        .body(json_body)
 }
 
 /// Create a generic internal server error response
@@ -1,6 +1,8 @@
+pub mod admin;
 pub mod chat_completions;
 pub mod completions;
 pub mod embeddings;
+pub mod models;
 
 // Re-export handlers for easier access
 pub use chat_completions::create_chat_completion;
@@ -0,0 +1,153 @@
+//! # Models Handlers
+//!
+//! This module implements the HTTP handlers for the OpenAI models endpoints. They serve the
+//! model registry: the built-in catalog, or the models file given by `--models`.
+//!
+//! ## Endpoints
+//!
+//! - `GET /v1/models`: List the available models
+//! - `GET /v1/models/{id}`: Retrieve a single model
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use poem::{Route, get};
+//! use openai_mock::handlers::models::{list_models, retrieve_model};
+//!
+//! let app = Route::new()
+//!     .at("/v1/models", get(list_models))
+//!     .at("/v1/models/:id", get(retrieve_model));
+//! ```
+
+use poem::{
+    IntoResponse, Response, Result, handler,
+    http::StatusCode,
+    web::{Data, Path},
+};
+use serde::Serialize;
+use serde_json;
+
+use crate::models::responses::ListModelsResponse;
+use crate::registry::ModelError;
+use crate::state::AppState;
+
+/// Handler for GET /v1/models
+///
+/// Lists the models in the registry:
+///
+/// ```json
+/// {
+///   "object": "list",
+///   "data": [
+///     {"id": "gpt-4o", "object": "model", "created": 1715367049, "owned_by": "system"}
+///   ]
+/// }
+/// ```
+#[handler]
+pub async fn list_models(state: Option<Data<&AppState>>) -> Result<impl IntoResponse> {
+    let models = state.map(|state| state.models.clone()).unwrap_or_default();
+    let response = ListModelsResponse::new(
+        models
+            .models()
+            .iter()
+            .map(|model| model.to_model())
+            .collect(),
+    );
+
+    Ok(json_response(&response))
+}
+
+/// Handler for GET /v1/models/{id}
+///
+/// Returns a single model object, or a `404 Not Found` error with the `model_not_found` code
+/// when the registry has no model with the ID.
+#[handler]
+pub async fn retrieve_model(
+    Path(id): Path<String>,
+    state: Option<Data<&AppState>>,
+) -> Result<impl IntoResponse> {
+    let models = state.map(|state| state.models.clone()).unwrap_or_default();
+    match models.get(&id) {
+        Some(model) => Ok(json_response(&model.to_model())),
+        None => Ok(ModelError::NotFound(id).to_response()),
+    }
+}
+
+/// Serialize a value into a `200 OK` JSON response
+fn json_response<T: Serialize>(value: &T) -> Response {
+    match serde_json::to_string(value) {
+        Ok(json_body) => Response::builder()
+            .status(StatusCode::OK)
+            .header("content-type", "application/json")
+            .body(json_body),
+        Err(_) => Response::builder()
+            .status(StatusCode::INTERNAL_SERVER_ERROR)
+            .header("content-type", "application/json")
+            .body(r#"{"error":{"message":"Internal server error","type":"server_error"}}"#),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::registry::{Capability, ModelDefinition, ModelRegistry};
+    use poem::{EndpointExt, Route, get, test::TestClient};
+
+    #[tokio::test]
+    async fn test_list_and_retrieve_builtin_models() {
+        let client = TestClient::new(
+            Route::new()
+                .at("/v1/models", get(list_models))
+                .at("/v1/models/:id", get(retrieve_model)),
+        );
+
+        let resp = client.get("/v1/models").send().await;
+        resp.assert_status_is_ok();
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["object"], "list");
+        assert!(
+            body["data"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .any(|model| model["id"] == "gpt-4o")
+        );
+
+        let resp = client.get("/v1/models/gpt-4o").send().await;
+        resp.assert_status_is_ok();
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["object"], "model");
+        assert_eq!(body["owned_by"], "system");
+
+        let resp = client.get("/v1/models/not-a-model").send().await;
+        resp.assert_status(StatusCode::NOT_FOUND);
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["error"]["code"], "model_not_found");
+    }
+
+    #[tokio::test]
+    async fn test_models_from_state() {
+        let models = ModelRegistry::new(vec![ModelDefinition {
+            id: "local-llm".to_string(),
+            owned_by: "me".to_string(),
+            created: 0,
+            capabilities: vec![Capability::Chat],
+            context_window: Some(4096),
+            embedding_dimensions: None,
+            supports_dimensions: true,
+        }])
+        .unwrap();
+        let client = TestClient::new(
+            Route::new()
+                .at("/v1/models", get(list_models))
+                .data(AppState::default().with_models(models)),
+        );
+
+        let resp = client.get("/v1/models").send().await;
+        resp.assert_status_is_ok();
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["data"].as_array().unwrap().len(), 1);
+        assert_eq!(body["data"][0]["id"], "local-llm");
+        assert_eq!(body["data"][0]["owned_by"], "me");
+    }
+}
@@ -0,0 +1,258 @@
+//! # Image Inputs
+//!
+//! This module checks the images attached to chat messages as `image_url` content parts and
+//! counts the prompt tokens they cost, the way the OpenAI API does.
+//!
+//! ## URLs
+//!
+//! An image is either an `http(s)` URL or a base64 data URL of a PNG, JPEG, GIF or WebP
+//! image, such as `data:image/png;base64,iVBORw0KGgo...`. Remote images are never fetched.
+//!
+//! ## Token Counting
+//!
+//! - **low** detail: a flat 85 tokens
+//! - **high** and **auto** detail: the image is scaled to fit in 2048x2048, then so that its
+//!   shortest side is at most 768px. It costs 85 tokens plus 170 per 512px tile it covers.
+//!
+//! The size of a data URL image is read from its header. Remote images, whose size is unknown,
+//! are counted as 1024x1024: 765 tokens. `gpt-4o-mini` models charge 2833 tokens per image
+//! and 5667 per tile instead.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use openai_mock::images::tile_tokens;
+//!
+//! assert_eq!(tile_tokens("gpt-4o", 1024, 1024), 765);
+//! assert_eq!(tile_tokens("gpt-4o", 2048, 4096), 1105);
+//! ```
+
+use base64::prelude::*;
+
+use crate::models::requests::{ImageDetail, ImageUrl};
+
+/// MIME types accepted in image data URLs
+const IMAGE_MIME_TYPES: &[&str] = &["image/png", "image/jpeg", "image/gif", "image/webp"];
+
+/// The error for data URLs that do not hold a supported image
+const UNSUPPORTED_IMAGE: &str = "You uploaded an unsupported image. Please make sure your image has one of the following formats: ['png', 'jpeg', 'gif', 'webp'].";
+
+/// Dimensions assumed for images whose size is unknown
+const DEFAULT_DIMENSIONS: (u32, u32) = (1024, 1024);
+
+/// The side of the square a high detail image is first scaled into
+const MAX_SIDE: f64 = 2048.0;
+
+/// The longest the shortest side of a high detail image may be
+const MAX_SHORT_SIDE: f64 = 768.0;
+
+/// The side of a tile
+const TILE_SIDE: f64 = 512.0;
+
+/// Check an image URL: an `http(s)` URL, or a base64 data URL of a supported image type
+pub fn validate_image_url(url: &str) -> Result<(), String> {
+    if url.starts_with("data:") {
+        return match decode_data_url(url) {
+            Some((_, bytes)) if dimensions(&bytes).is_some() => Ok(()),
+            _ => Err(UNSUPPORTED_IMAGE.to_string()),
+        };
+    }
+
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => Ok(()),
+        _ => Err("Expected a valid URL, but got a value with an invalid format.".to_string()),
+    }
+}
+
+/// Decode a base64 image data URL into its MIME type and bytes
+///
+/// Returns `None` for other URLs, MIME types other than PNG, JPEG, GIF and WebP, and data
+/// that is not valid base64.
+pub fn decode_data_url(url: &str) -> Option<(&str, Vec<u8>)> {
+    let (header, data) = url.strip_prefix("data:")?.split_once(',')?;
+    let mime_type = header.strip_suffix(";base64")?;
+    if !IMAGE_MIME_TYPES.contains(&mime_type) {
+        return None;
+    }
+    let bytes = BASE64_STANDARD.decode(data.trim()).ok()?;
+    Some((mime_type, bytes))
+}
+
+/// The width and height of a PNG, JPEG, GIF or WebP image, read from its header
+pub fn dimensions(bytes: &[u8]) -> Option<(u32, u32)> {
+    let be16 = |at: usize| {
+        let b = bytes.get(at..at + 2)?;
+        Some((b[0] as u32) << 8 | b[1] as u32)
+    };
+    let le16 = |at: usize| {
+        let b = bytes.get(at..at + 2)?;
+        Some(b[0] as u32 | (b[1] as u32) << 8)
+    };
+    let le24 = |at: usize| {
+        let b = bytes.get(at..at + 3)?;
+        Some(b[0] as u32 | (b[1] as u32) << 8 | (b[2] as u32) << 16)
+    };
+
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        let width = u32::from_be_bytes(bytes.get(16..20)?.try_into().ok()?);
+        let height = u32::from_be_bytes(bytes.get(20..24)?.try_into().ok()?);
+        return Some((width, height));
+    }
+
+    if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        return Some((le16(6)?, le16(8)?));
+    }
+
+    if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        return match bytes.get(12..16)? {
+            b"VP8 " => Some((le16(26)? & 0x3fff, le16(28)? & 0x3fff)),
+            b"VP8L" => {
+                let bits = u32::from_le_bytes(bytes.get(21..25)?.try_into().ok()?);
+                Some(((bits & 0x3fff) + 1, ((bits >> 14) & 0x3fff) + 1))
+            }
+            b"VP8X" => Some((le24(24)? + 1, le24(27)? + 1)),
+            _ => None,
+        };
+    }
+
+    if bytes.starts_with(&[0xff, 0xd8]) {
+        // Walk the JPEG segments up to the start of frame, which holds the size
+        let mut at = 2;
+        while at + 4 <= bytes.len() {
+            if bytes[at] != 0xff {
+                return None;
+            }
+            let marker = bytes[at + 1];
+            if marker == 0xff {
+                at += 1;
+                continue;
+            }
+            if matches!(marker, 0xc0..=0xcf) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+                return Some((be16(at + 7)?, be16(at + 5)?));
+            }
+            at += 2 + be16(at + 2)? as usize;
+        }
+    }
+
+    None
+}
+
+/// The prompt tokens of an image attached to a message for a model
+pub fn image_tokens(model: &str, image: &ImageUrl) -> u32 {
+    let (base_tokens, _) = token_rates(model);
+    match image.detail.unwrap_or_default() {
+        ImageDetail::Low => base_tokens,
+        ImageDetail::Auto | ImageDetail::High => {
+            let (width, height) = decode_data_url(&image.url)
+                .and_then(|(_, bytes)| dimensions(&bytes))
+                .unwrap_or(DEFAULT_DIMENSIONS);
+            tile_tokens(model, width, height)
+        }
+    }
+}
+
+/// The prompt tokens of a high detail image of a size for a model
+pub fn tile_tokens(model: &str, width: u32, height: u32) -> u32 {
+    let (base_tokens, tokens_per_tile) = token_rates(model);
+    let (mut width, mut height) = (width.max(1) as f64, height.max(1) as f64);
+
+    let fit = (MAX_SIDE / width.max(height)).min(1.0);
+    width *= fit;
+    height *= fit;
+
+    let shorten = (MAX_SHORT_SIDE / width.min(height)).min(1.0);
+    width *= shorten;
+    height *= shorten;
+
+    let tiles = (width / TILE_SIDE).ceil() * (height / TILE_SIDE).ceil();
+    base_tokens + tokens_per_tile * tiles as u32
+}
+
+/// The tokens an image costs and the tokens per tile for a model
+fn token_rates(model: &str) -> (u32, u32) {
+    if model.starts_with("gpt-4o-mini") {
+        (2833, 5667)
+    } else {
+        (85, 170)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A PNG header of the given size, enough for `dimensions`
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR".to_vec();
+        bytes.extend(width.to_be_bytes());
+        bytes.extend(height.to_be_bytes());
+        bytes.extend([8, 6, 0, 0, 0]);
+        bytes
+    }
+
+    fn image(url: String, detail: Option<ImageDetail>) -> ImageUrl {
+        ImageUrl { url, detail }
+    }
+
+    #[test]
+    fn test_dimensions() {
+        assert_eq!(dimensions(&png(640, 480)), Some((640, 480)));
+        assert_eq!(dimensions(b"GIF89a\x20\x03\x58\x02\0\0"), Some((800, 600)));
+
+        let jpeg = [
+            0xff, 0xd8, 0xff, 0xe0, 0x00, 0x04, 0x00, 0x00, // SOI, APP0
+            0xff, 0xc0, 0x00, 0x11, 0x08, 0x01, 0xe0, 0x02, 0x80, // SOF0, 480x640
+        ];
+        assert_eq!(dimensions(&jpeg), Some((640, 480)));
+
+        let mut webp = b"RIFF\0\0\0\0WEBPVP8X\x0a\0\0\0\0\0\0\0".to_vec();
+        webp.extend([0x7f, 0x02, 0x00, 0xdf, 0x01, 0x00]);
+        assert_eq!(dimensions(&webp), Some((640, 480)));
+
+        assert_eq!(dimensions(b"not an image"), None);
+    }
+
+    #[test]
+    fn test_validate_image_url() {
+        let data_url = format!(
+            "data:image/png;base64,{}",
+            BASE64_STANDARD.encode(png(2, 2))
+        );
+        assert!(validate_image_url(&data_url).is_ok());
+        assert!(validate_image_url("https://example.com/cat.png").is_ok());
+
+        assert!(validate_image_url("ftp://example.com/cat.png").is_err());
+        assert!(validate_image_url("cat.png").is_err());
+        assert!(validate_image_url("data:image/bmp;base64,Qk0=").is_err());
+        assert!(validate_image_url("data:image/png;base64,not base64!").is_err());
+        let mislabeled = format!("data:image/png;base64,{}", BASE64_STANDARD.encode("text"));
+        assert!(validate_image_url(&mislabeled).is_err());
+    }
+
+    #[test]
+    fn test_image_tokens() {
+        let url = format!(
+            "data:image/png;base64,{}",
+            BASE64_STANDARD.encode(png(2048, 4096))
+        );
+        assert_eq!(image_tokens("gpt-4o", &image(url.clone(), None)), 1105);
+        assert_eq!(
+            image_tokens("gpt-4o", &image(url.clone(), Some(ImageDetail::High))),
+            1105
+        );
+        assert_eq!(
+            image_tokens("gpt-4o", &image(url, Some(ImageDetail::Low))),
+            85
+        );
+
+        let remote = "https://example.com/cat.png".to_string();
+        assert_eq!(image_tokens("gpt-4o", &image(remote.clone(), None)), 765);
+        assert_eq!(
+            image_tokens("gpt-4o-mini", &image(remote, Some(ImageDetail::Low))),
+            2833
+        );
+
+        // Small images are a single tile
+        assert_eq!(tile_tokens("gpt-4o", 300, 200), 255);
+    }
+}
@@ -0,0 +1,478 @@
+//! # Request Journal
+//!
+//! This module records the requests that reach the completions, chat completions and embeddings
+//! handlers so tests can assert on what their application actually sent. Each entry holds the
+//! request headers, the parsed JSON body and the response or error that was returned.
+//! Credentials in the `authorization`, `x-api-key`, `api-key` and `cookie` headers are
+//! redacted before they are recorded (`Bearer sk-…2345`).
+//!
+//! The journal is bounded: once it holds `--journal-capacity` entries, the oldest entry is
+//! dropped for each new one. It is exposed through the admin API as `GET /admin/requests`
+//! (filterable by `endpoint` and `model`) and reset with `DELETE /admin/requests`.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use poem::{Route, post, EndpointExt};
+//! use std::sync::Arc;
+//! use openai_mock::handlers::chat_completions::create_chat_completion;
+//! use openai_mock::journal::{JournalMiddleware, RequestJournal};
+//!
+//! let journal = Arc::new(RequestJournal::new(100));
+//! let app = Route::new()
+//!     .at("/v1/chat/completions", post(create_chat_completion))
+//!     .with(JournalMiddleware::new(journal.clone()));
+//! ```
+
+use poem::{Body, Endpoint, IntoResponse, Middleware, Request, Response, Result, http::header};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::{Arc, RwLock};
+
+/// Default number of entries kept by the journal
+pub const DEFAULT_JOURNAL_CAPACITY: usize = 1000;
+
+/// Headers holding API keys, recorded with only the ends of the key visible
+const KEY_HEADERS: &[&str] = &[
+    "authorization",
+    "proxy-authorization",
+    "x-api-key",
+    "api-key",
+];
+
+/// Headers holding session secrets, recorded as `[redacted]`
+const SECRET_HEADERS: &[&str] = &["cookie"];
+
+/// A recorded request and the response it received
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Sequence number of the request, starting at 1
+    pub id: u64,
+
+    /// Unix timestamp (in seconds) of when the request was received
+    pub timestamp: u64,
+
+    /// The HTTP method
+    pub method: String,
+
+    /// The endpoint, relative to `/v1` (e.g. `chat/completions`)
+    pub endpoint: String,
+
+    /// The model named in the request body, if any
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+
+    /// The request headers
+    pub headers: BTreeMap<String, String>,
+
+    /// The request body, parsed as JSON when possible and kept as a string otherwise
+    pub body: serde_json::Value,
+
+    /// The response returned to the client
+    pub response: JournalResponse,
+}
+
+/// The response recorded for a journal entry
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JournalResponse {
+    /// The HTTP status code
+    pub status: u16,
+
+    /// Whether the response was a server-sent event stream
+    pub stream: bool,
+
+    /// The response body, parsed as JSON when possible (absent for streams)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub body: Option<serde_json::Value>,
+}
+
+/// Filter for listing journal entries
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct JournalFilter {
+    /// Only entries for this endpoint, with or without the `/v1/` prefix
+    pub endpoint: Option<String>,
+
+    /// Only entries for this model
+    pub model: Option<String>,
+}
+
+impl JournalFilter {
+    /// Check whether an entry passes the filter
+    pub fn matches(&self, entry: &JournalEntry) -> bool {
+        if let Some(endpoint) = &self.endpoint
+            && normalize_endpoint(endpoint) != entry.endpoint
+        {
+            return false;
+        }
+
+        if let Some(model) = &self.model
+            && entry.model.as_ref() != Some(model)
+        {
+            return false;
+        }
+
+        true
+    }
+}
+
+/// Thread-safe, bounded store of journal entries
+#[derive(Debug)]
+pub struct RequestJournal {
+    capacity: usize,
+    inner: RwLock<JournalInner>,
+}
+
+#[derive(Debug, Default)]
+struct JournalInner {
+    entries: VecDeque<JournalEntry>,
+    next_id: u64,
+}
+
+impl Default for RequestJournal {
+    fn default() -> Self {
+        Self::new(DEFAULT_JOURNAL_CAPACITY)
+    }
+}
+
+impl RequestJournal {
+    /// Create a journal keeping at most `capacity` entries (0 disables recording)
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            inner: RwLock::new(JournalInner::default()),
+        }
+    }
+
+    /// The maximum number of entries kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Record an entry, assigning its sequence number and evicting the oldest entry if full
+    pub fn record(&self, mut entry: JournalEntry) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let mut inner = self.write();
+        inner.next_id += 1;
+        entry.id = inner.next_id;
+
+        while inner.entries.len() >= self.capacity {
+            inner.entries.pop_front();
+        }
+        inner.entries.push_back(entry);
+    }
+
+    /// List the entries passing a filter, oldest first
+    pub fn list(&self, filter: &JournalFilter) -> Vec<JournalEntry> {
+        self.read()
+            .entries
+            .iter()
+            .filter(|entry| filter.matches(entry))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove all entries, returning how many were removed
+    pub fn clear(&self) -> usize {
+        let mut inner = self.write();
+        let count = inner.entries.len();
+        inner.entries.clear();
+        count
+    }
+
+    fn read(&self) -> std::sync::RwLockReadGuard<'_, JournalInner> {
+        self.inner
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    fn write(&self) -> std::sync::RwLockWriteGuard<'_, JournalInner> {
+        self.inner
+            .write()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+/// Middleware that records every request and its response in a journal
+#[derive(Clone)]
+pub struct JournalMiddleware {
+    journal: Arc<RequestJournal>,
+}
+
+impl JournalMiddleware {
+    /// Create a new journal middleware recording into the given journal
+    pub fn new(journal: Arc<RequestJournal>) -> Self {
+        Self { journal }
+    }
+}
+
+impl<E> Middleware<E> for JournalMiddleware
+where
+    E: Endpoint,
+{
+    type Output = JournalEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        JournalEndpoint {
+            inner: ep,
+            journal: self.journal.clone(),
+        }
+    }
+}
+
+/// Wrapper endpoint that records requests and responses
+pub struct JournalEndpoint<E> {
+    inner: E,
+    journal: Arc<RequestJournal>,
+}
+
+impl<E> Endpoint for JournalEndpoint<E>
+where
+    E: Endpoint,
+{
+    type Output = Response;
+
+    async fn call(&self, mut req: Request) -> Result<Self::Output> {
+        if self.journal.capacity() == 0 {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        }
+
+        let timestamp = chrono::Utc::now().timestamp() as u64;
+        let method = req.method().to_string();
+        let endpoint = normalize_endpoint(req.uri().path());
+        let headers = req
+            .headers()
+            .iter()
+            .map(|(name, value)| {
+                let value = String::from_utf8_lossy(value.as_bytes());
+                (name.to_string(), redact_header(name.as_str(), &value))
+            })
+            .collect();
+
+        // Buffer the body so it can be both recorded and passed on
+        let request_bytes = req.take_body().into_bytes().await.unwrap_or_default();
+        let body = parse_body(&request_bytes);
+        req.set_body(request_bytes);
+
+        let model = body
+            .get("model")
+            .and_then(|model| model.as_str())
+            .map(str::to_string);
+
+        let mut resp = match self.inner.call(req).await {
+            Ok(resp) => resp.into_response(),
+            Err(err) => err.into_response(),
+        };
+
+        let stream = resp
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|value| value.starts_with("text/event-stream"));
+
+        let response_body = if stream {
+            None
+        } else {
+            let response_bytes = resp.take_body().into_bytes().await.unwrap_or_default();
+            let parsed = parse_body(&response_bytes);
+            resp.set_body(Body::from(response_bytes));
+            Some(parsed)
+        };
+
+        self.journal.record(JournalEntry {
+            id: 0,
+            timestamp,
+            method,
+            endpoint,
+            model,
+            headers,
+            body,
+            response: JournalResponse {
+                status: resp.status().as_u16(),
+                stream,
+                body: response_body,
+            },
+        });
+
+        Ok(resp)
+    }
+}
+
+/// Redact the credentials in a header value so the journal never exposes them
+fn redact_header(name: &str, value: &str) -> String {
+    if SECRET_HEADERS.contains(&name) {
+        return "[redacted]".to_string();
+    }
+    if !KEY_HEADERS.contains(&name) {
+        return value.to_string();
+    }
+
+    match value.split_once(' ') {
+        Some((scheme, key)) => format!("{scheme} {}", redact_key(key.trim())),
+        None => redact_key(value),
+    }
+}
+
+/// Keep the first three and last four characters of a key (`sk-…2345`)
+fn redact_key(key: &str) -> String {
+    let chars: Vec<char> = key.chars().collect();
+    if chars.len() < 12 {
+        return "[redacted]".to_string();
+    }
+    let prefix: String = chars[..3].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{prefix}…{suffix}")
+}
+
+/// Strip the leading slash and `v1/` prefix from an endpoint path
+pub(crate) fn normalize_endpoint(path: &str) -> String {
+    let path = path.trim_start_matches('/');
+    path.strip_prefix("v1/").unwrap_or(path).to_string()
+}
+
+/// Parse a body as JSON, falling back to a string (or null when empty)
+fn parse_body(bytes: &[u8]) -> serde_json::Value {
+    if bytes.is_empty() {
+        return serde_json::Value::Null;
+    }
+
+    serde_json::from_slice(bytes)
+        .unwrap_or_else(|_| serde_json::Value::String(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{EndpointExt, Route, handler, http::StatusCode, post, test::TestClient, web::Json};
+
+    #[handler]
+    async fn echo_handler(body: Json<serde_json::Value>) -> Json<serde_json::Value> {
+        Json(serde_json::json!({ "echo": body.0 }))
+    }
+
+    fn create_entry(endpoint: &str, model: Option<&str>) -> JournalEntry {
+        JournalEntry {
+            id: 0,
+            timestamp: 0,
+            method: "POST".to_string(),
+            endpoint: endpoint.to_string(),
+            model: model.map(str::to_string),
+            headers: BTreeMap::new(),
+            body: serde_json::Value::Null,
+            response: JournalResponse {
+                status: 200,
+                stream: false,
+                body: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_journal_is_bounded() {
+        let journal = RequestJournal::new(2);
+        for _ in 0..3 {
+            journal.record(create_entry("completions", None));
+        }
+
+        let entries = journal.list(&JournalFilter::default());
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].id, 2);
+        assert_eq!(entries[1].id, 3);
+
+        assert_eq!(journal.clear(), 2);
+        assert!(journal.list(&JournalFilter::default()).is_empty());
+    }
+
+    #[test]
+    fn test_journal_filter() {
+        let journal = RequestJournal::default();
+        journal.record(create_entry("chat/completions", Some("gpt-4")));
+        journal.record(create_entry("chat/completions", Some("gpt-4o")));
+        journal.record(create_entry("embeddings", Some("gpt-4")));
+
+        let filter = JournalFilter {
+            endpoint: Some("/v1/chat/completions".to_string()),
+            model: None,
+        };
+        assert_eq!(journal.list(&filter).len(), 2);
+
+        let filter = JournalFilter {
+            endpoint: Some("chat/completions".to_string()),
+            model: Some("gpt-4".to_string()),
+        };
+        let entries = journal.list(&filter);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, 1);
+    }
+
+    #[test]
+    fn test_disabled_journal() {
+        let journal = RequestJournal::new(0);
+        journal.record(create_entry("completions", None));
+        assert!(journal.list(&JournalFilter::default()).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_journal_middleware_records_request_and_response() {
+        let journal = Arc::new(RequestJournal::default());
+        let app = Route::new()
+            .at("/v1/echo", post(echo_handler))
+            .with(JournalMiddleware::new(journal.clone()));
+        let client = TestClient::new(app);
+
+        let resp = client
+            .post("/v1/echo")
+            .header("x-test", "yes")
+            .header("authorization", "Bearer sk-secret-key-12345")
+            .header("cookie", "session=abc")
+            .body_json(&serde_json::json!({"model": "gpt-4", "temperature": 0.5}))
+            .send()
+            .await;
+        resp.assert_status_is_ok();
+        resp.assert_json(serde_json::json!({"echo": {"model": "gpt-4", "temperature": 0.5}}))
+            .await;
+
+        let entries = journal.list(&JournalFilter::default());
+        assert_eq!(entries.len(), 1);
+        let entry = &entries[0];
+        assert_eq!(entry.endpoint, "echo");
+        assert_eq!(entry.model.as_deref(), Some("gpt-4"));
+        assert_eq!(entry.headers.get("x-test").map(String::as_str), Some("yes"));
+        assert_eq!(entry.headers["authorization"], "Bearer sk-…2345");
+        assert_eq!(entry.headers["cookie"], "[redacted]");
+        assert!(
+            !serde_json::to_string(&entries)
+                .unwrap()
+                .contains("sk-secret-key-12345")
+        );
+        assert_eq!(entry.body["temperature"], 0.5);
+        assert_eq!(entry.response.status, 200);
+        assert_eq!(
+            entry.response.body.as_ref().unwrap()["echo"]["model"],
+            "gpt-4"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_journal_middleware_records_errors() {
+        let journal = Arc::new(RequestJournal::default());
+        let app = Route::new()
+            .at("/v1/echo", post(echo_handler))
+            .with(JournalMiddleware::new(journal.clone()));
+        let client = TestClient::new(app);
+
+        let resp = client
+            .post("/v1/echo")
+            .content_type("application/json")
+            .body("not json")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::BAD_REQUEST);
+
+        let entries = journal.list(&JournalFilter::default());
+        assert_eq!(entries[0].body, "not json");
+        assert_eq!(entries[0].response.status, 400);
+    }
+}
@@ -0,0 +1,264 @@
+//! # Latency Simulation
+//!
+//! This module slows responses down the way a real model would, so timeouts, spinners and
+//! streaming UIs can be tested against the mock. Latency profiles are loaded from a YAML or
+//! JSON file given by the `--latency` configuration option; without one, responses are
+//! returned immediately.
+//!
+//! ## File Format
+//!
+//! ```yaml
+//! default:
+//!   base_ms: 100                  # request overhead
+//!   time_to_first_token_ms: 250   # time until the first token
+//!   inter_token_ms: 20            # time per further token
+//!   jitter_ms: 30                 # random extra time added to every delay
+//! models:
+//!   gpt-4o-mini:
+//!     time_to_first_token_ms: 150
+//!     inter_token_ms: 8
+//! ```
+//!
+//! Models without a profile of their own use `default`. Omitted fields are zero.
+//!
+//! ## Timing
+//!
+//! A non-streamed response is returned after the base latency, the time to first token and
+//! the inter-token delay for every further completion token. A streamed response sends its
+//! first token-carrying chunk after the base latency and time to first token, then paces the
+//! remaining token-carrying chunks by the inter-token delay. Chunks without tokens, such as
+//! the opening role delta, the `finish_reason` chunk and the usage chunk, follow immediately.
+//!
+//! Jitter adds a random delay between zero and `jitter_ms` to each wait. The
+//! `--deterministic-latency` option disables jitter so tests see stable timings.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+use rand::Rng;
+
+/// The latency of a single model
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LatencyProfile {
+    /// Fixed overhead before any work starts, in milliseconds
+    pub base_ms: u64,
+
+    /// Time until the first token is produced, in milliseconds
+    pub time_to_first_token_ms: u64,
+
+    /// Time per token after the first, in milliseconds
+    pub inter_token_ms: u64,
+
+    /// Maximum random delay added to every wait, in milliseconds
+    pub jitter_ms: u64,
+}
+
+/// Latency profiles for all models
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyProfiles {
+    /// The profile of models without one of their own
+    #[serde(default)]
+    pub default: LatencyProfile,
+
+    /// Profiles by model name
+    #[serde(default)]
+    pub models: HashMap<String, LatencyProfile>,
+
+    /// Whether jitter is disabled
+    #[serde(skip)]
+    pub deterministic: bool,
+}
+
+impl LatencyProfiles {
+    /// Load latency profiles from a YAML (`.yaml`/`.yml`) or JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, LatencyError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| LatencyError::Io(format!("{}: {e}", path.display())))?;
+
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+
+        if is_yaml {
+            serde_yaml::from_str(&contents).map_err(|e| LatencyError::Parse(e.to_string()))
+        } else {
+            serde_json::from_str(&contents).map_err(|e| LatencyError::Parse(e.to_string()))
+        }
+    }
+
+    /// Enable or disable deterministic mode, in which jitter is ignored
+    pub fn with_deterministic(mut self, deterministic: bool) -> Self {
+        self.deterministic = deterministic;
+        self
+    }
+
+    /// The profile used for a model
+    pub fn profile(&self, model: &str) -> &LatencyProfile {
+        self.models.get(model).unwrap_or(&self.default)
+    }
+
+    /// How long a non-streamed response producing `completion_tokens` tokens takes
+    pub fn response_delay(&self, model: &str, completion_tokens: u32) -> Duration {
+        let profile = self.profile(model);
+        let tokens = u64::from(completion_tokens.saturating_sub(1));
+        let millis = profile
+            .base_ms
+            .saturating_add(profile.time_to_first_token_ms)
+            .saturating_add(profile.inter_token_ms.saturating_mul(tokens));
+        Duration::from_millis(millis) + self.jitter(profile)
+    }
+
+    /// The wait before each streamed chunk, given whether each chunk carries tokens
+    ///
+    /// The first chunk carrying tokens waits for the base latency and the time to first
+    /// token, every further one for the inter-token delay. Chunks without tokens are sent
+    /// right away, unless no chunk carries tokens, in which case the first chunk takes the
+    /// first-token wait.
+    pub fn stream_delays(&self, model: &str, carries_tokens: &[bool]) -> Vec<Duration> {
+        let profile = self.profile(model);
+        let first = carries_tokens
+            .iter()
+            .position(|&tokens| tokens)
+            .unwrap_or(0);
+        carries_tokens
+            .iter()
+            .enumerate()
+            .map(|(index, &tokens)| {
+                let millis = if index == first {
+                    profile
+                        .base_ms
+                        .saturating_add(profile.time_to_first_token_ms)
+                } else if tokens {
+                    profile.inter_token_ms
+                } else {
+                    return Duration::ZERO;
+                };
+                Duration::from_millis(millis) + self.jitter(profile)
+            })
+            .collect()
+    }
+
+    fn jitter(&self, profile: &LatencyProfile) -> Duration {
+        if self.deterministic || profile.jitter_ms == 0 {
+            return Duration::ZERO;
+        }
+        Duration::from_millis(rand::thread_rng().gen_range(0..=profile.jitter_ms))
+    }
+}
+
+/// Latency configuration error types
+#[derive(Debug, Clone)]
+pub enum LatencyError {
+    Io(String),
+    Parse(String),
+}
+
+impl fmt::Display for LatencyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LatencyError::Io(msg) => write!(f, "Failed to read latency profiles: {msg}"),
+            LatencyError::Parse(msg) => write!(f, "Failed to parse latency profiles: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for LatencyError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_profiles() -> LatencyProfiles {
+        serde_yaml::from_str(
+            r#"
+default:
+  base_ms: 100
+  time_to_first_token_ms: 200
+  inter_token_ms: 10
+  jitter_ms: 50
+models:
+  fast-model:
+    time_to_first_token_ms: 20
+    inter_token_ms: 1
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_profile_lookup() {
+        let profiles = create_test_profiles();
+        assert_eq!(profiles.profile("fast-model").time_to_first_token_ms, 20);
+        assert_eq!(profiles.profile("fast-model").base_ms, 0);
+        assert_eq!(profiles.profile("gpt-4o").base_ms, 100);
+    }
+
+    #[test]
+    fn test_deterministic_delays() {
+        let profiles = create_test_profiles().with_deterministic(true);
+
+        assert_eq!(
+            profiles.response_delay("gpt-4o", 11),
+            Duration::from_millis(400)
+        );
+        assert_eq!(
+            profiles.stream_delays("gpt-4o", &[false, true, true, true, false, false]),
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(300),
+                Duration::from_millis(10),
+                Duration::from_millis(10),
+                Duration::ZERO,
+                Duration::ZERO,
+            ]
+        );
+        assert_eq!(
+            profiles.stream_delays("gpt-4o", &[false, false]),
+            vec![Duration::from_millis(300), Duration::ZERO]
+        );
+    }
+
+    #[test]
+    fn test_jitter_is_bounded() {
+        let profiles = create_test_profiles();
+        for _ in 0..20 {
+            let delay = profiles.response_delay("gpt-4o", 1);
+            assert!(delay >= Duration::from_millis(300));
+            assert!(delay <= Duration::from_millis(350));
+        }
+    }
+
+    #[test]
+    fn test_delays_saturate() {
+        let profiles: LatencyProfiles = serde_yaml::from_str(&format!(
+            "default:\n  base_ms: {max}\n  time_to_first_token_ms: {max}\n  inter_token_ms: {max}\n",
+            max = u64::MAX
+        ))
+        .unwrap();
+        let longest = Duration::from_millis(u64::MAX);
+
+        assert_eq!(profiles.response_delay("gpt-4o", 10), longest);
+        assert_eq!(
+            profiles.stream_delays("gpt-4o", &[true, true]),
+            vec![longest, longest]
+        );
+    }
+
+    #[test]
+    fn test_no_profiles_means_no_delay() {
+        let profiles = LatencyProfiles::default();
+        assert_eq!(profiles.response_delay("gpt-4o", 100), Duration::ZERO);
+        assert!(
+            profiles
+                .stream_delays("gpt-4o", &[true; 5])
+                .iter()
+                .all(Duration::is_zero)
+        );
+    }
+}
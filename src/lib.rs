@@ -8,6 +8,18 @@
 //!
 //! - **Completion API**: `/v1/completions` endpoint with realistic fake responses
 //! - **Embedding API**: `/v1/embeddings` endpoint with consistent fake vectors
+//! - **Streaming**: Server-sent event streaming for chat and legacy completions
+//! - **Fixtures**: Scripted chat completion responses loaded from a YAML or JSON file
+//! - **Request Journal**: Inspect the requests your application sent via the admin API
+//! - **Model Registry**: Configurable model catalog served by `/v1/models`, checked by every endpoint
+//! - **Latency Simulation**: Per-model time to first token and token pacing for streams
+//! - **Token Counting**: `usage` counted with the cl100k_base and o200k_base BPE tokenizers
+//! - **Function Calling**: Tool call arguments generated to match each function's JSON Schema
+//! - **Structured Outputs**: `response_format` JSON content, checked against strict mode rules
+//! - **Vision and Audio Inputs**: Image and audio content parts, with image tokens counted in usage
+//! - **Rate Limiting**: Per-key requests and tokens per minute with `x-ratelimit-*` headers
+//! - **Fault Injection**: Rate limit, server error, overload, timeout and dropped-connection faults
+//! - **Record and Replay**: Capture real upstream traffic in a cassette and replay it offline
 //! - **Authentication**: API key validation using Bearer token authentication
 //! - **CORS Support**: Cross-origin requests for browser-based applications
 //! - **Health Checks**: Built-in health monitoring endpoint
@@ -39,8 +51,22 @@
 //! - `RUST_LOG`: Logging level (default: info)
 
 pub mod auth;
+pub mod cassette;
 pub mod config;
+pub mod expectations;
+pub mod faults;
+pub mod fixtures;
 pub mod generators;
 pub mod handlers;
+pub mod images;
+pub mod journal;
+pub mod latency;
 pub mod models;
+pub mod rate_limit;
+pub mod registry;
+pub mod schema;
 pub mod server;
+pub mod state;
+pub mod streaming;
+pub mod timeout;
+pub mod tokenizer;
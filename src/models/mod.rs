@@ -3,16 +3,20 @@ pub mod responses;
 
 // Re-export commonly used types
 pub use requests::{
-    ChatCompletionFunction, ChatCompletionFunctionCall, ChatCompletionMessage,
-    ChatCompletionMessageToolCall, ChatCompletionRole, ChatCompletionTool,
-    ChatCompletionToolChoice, CreateChatCompletionRequest, CreateCompletionRequest,
-    CreateEmbeddingRequest, EmbeddingInput, PromptInput, StopSequences,
+    AudioFormat, ChatCompletionContent, ChatCompletionContentPart, ChatCompletionFunction,
+    ChatCompletionFunctionCall, ChatCompletionMessage, ChatCompletionMessageToolCall,
+    ChatCompletionRole, ChatCompletionTool, ChatCompletionToolChoice, CreateChatCompletionRequest,
+    CreateCompletionRequest, CreateEmbeddingRequest, EmbeddingInput, ImageDetail, ImageUrl,
+    InputAudio, PromptInput, ResponseFormat, ResponseFormatJsonSchema, StopSequences,
+    StreamOptions, ValidationError,
 };
 pub use responses::{
-    ApiError, ChatCompletionChoice, ChatCompletionFunctionCall as ResponseFunctionCall,
-    ChatCompletionLogprobs, ChatCompletionMessageToolCall as ResponseMessageToolCall,
-    ChatCompletionResponseMessage, ChatCompletionTokenLogprob, ChatCompletionTopLogprob,
-    CompletionChoice, CompletionLogprobs, CompletionUsage, CreateChatCompletionResponse,
+    ApiError, ChatCompletionChoice, ChatCompletionChunkChoice,
+    ChatCompletionFunctionCall as ResponseFunctionCall, ChatCompletionLogprobs,
+    ChatCompletionMessageToolCall as ResponseMessageToolCall, ChatCompletionResponseMessage,
+    ChatCompletionStreamDelta, ChatCompletionTokenLogprob, ChatCompletionTopLogprob,
+    CompletionChoice, CompletionChunkChoice, CompletionLogprobs, CompletionUsage,
+    CreateChatCompletionChunk, CreateChatCompletionResponse, CreateCompletionChunk,
     CreateCompletionResponse, CreateEmbeddingResponse, EmbeddingData, EmbeddingUsage,
-    ErrorResponse,
+    EmbeddingVector, ErrorResponse,
 };
@@ -1,5 +1,16 @@
+use base64::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::images;
+use crate::models::responses::ErrorResponse;
+use crate::schema;
+use crate::tokenizer::Encoding;
+
+/// The maximum number of inputs in one embedding request
+pub const MAX_EMBEDDING_INPUTS: usize = 2048;
 
 /// Request model for the Create Completion endpoint
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -34,6 +45,10 @@ pub struct CreateCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
 
+    /// Options for streaming responses, only allowed when `stream` is true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+
     /// Include the log probabilities on the most likely tokens
     #[serde(skip_serializing_if = "Option::is_none")]
     pub logprobs: Option<u32>,
@@ -75,6 +90,15 @@ pub enum PromptInput {
     Array(Vec<String>),
 }
 
+/// Options for streaming responses
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct StreamOptions {
+    /// If set, an additional chunk carrying the usage statistics for the entire request is
+    /// streamed before the `data: [DONE]` message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub include_usage: Option<bool>,
+}
+
 /// Stop sequences can be a string or array of strings
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(untagged)]
@@ -140,6 +164,10 @@ pub struct CreateChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stream: Option<bool>,
 
+    /// Options for streaming responses, only allowed when `stream` is true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<StreamOptions>,
+
     /// Up to 4 sequences where the API will stop generating further tokens
     #[serde(skip_serializing_if = "Option::is_none")]
     pub stop: Option<StopSequences>,
@@ -164,6 +192,10 @@ pub struct CreateChatCompletionRequest {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub user: Option<String>,
 
+    /// The format the model must output: plain text, any JSON object, or JSON matching a schema
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub response_format: Option<ResponseFormat>,
+
     /// A list of tools the model may call
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tools: Option<Vec<ChatCompletionTool>>,
@@ -171,6 +203,43 @@ pub struct CreateChatCompletionRequest {
     /// Controls which (if any) tool is called by the model
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tool_choice: Option<ChatCompletionToolChoice>,
+
+    /// Whether the model may call several tools in one turn. Defaults to true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parallel_tool_calls: Option<bool>,
+}
+
+/// The format of a chat completion's content
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ResponseFormat {
+    /// Plain text, the default
+    Text,
+    /// Any valid JSON object
+    JsonObject,
+    /// JSON matching a schema
+    JsonSchema {
+        json_schema: ResponseFormatJsonSchema,
+    },
+}
+
+/// The schema of a `json_schema` response format
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ResponseFormatJsonSchema {
+    /// The name of the response format, up to 64 letters, digits, underscores and dashes
+    pub name: String,
+
+    /// A description of what the response format is for
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+
+    /// The JSON Schema the content must match
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub schema: Option<serde_json::Value>,
+
+    /// Whether the schema follows the rules of strict mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 /// A message in a chat completion conversation
@@ -179,9 +248,9 @@ pub struct ChatCompletionMessage {
     /// The role of the messages author
     pub role: ChatCompletionRole,
 
-    /// The contents of the message
+    /// The contents of the message: a string, or an array of text, image and audio parts
     #[serde(skip_serializing_if = "Option::is_none")]
-    pub content: Option<String>,
+    pub content: Option<ChatCompletionContent>,
 
     /// The name of the author of this message
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -196,6 +265,144 @@ pub struct ChatCompletionMessage {
     pub tool_call_id: Option<String>,
 }
 
+/// The contents of a chat message
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(untagged)]
+pub enum ChatCompletionContent {
+    /// Plain text
+    Text(String),
+    /// Text, image and audio parts
+    Parts(Vec<ChatCompletionContentPart>),
+}
+
+/// A part of a chat message's contents
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ChatCompletionContentPart {
+    /// A text part
+    Text { text: String },
+    /// An image, by URL or as a base64 data URL
+    ImageUrl { image_url: ImageUrl },
+    /// A base64-encoded audio clip
+    InputAudio { input_audio: InputAudio },
+}
+
+/// An image attached to a chat message
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct ImageUrl {
+    /// An `http(s)` URL, or a `data:image/...;base64,` data URL
+    pub url: String,
+
+    /// The resolution the model looks at the image in. Defaults to `auto`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+/// The resolution of an image input
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageDetail {
+    #[default]
+    Auto,
+    Low,
+    High,
+}
+
+/// An audio clip attached to a chat message
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+pub struct InputAudio {
+    /// The base64-encoded audio data
+    pub data: String,
+
+    /// The format of the audio data
+    pub format: AudioFormat,
+}
+
+/// The format of an audio input
+#[derive(Debug, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+}
+
+impl ChatCompletionContent {
+    /// The text of the contents, with the text parts joined by newlines
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            ChatCompletionContent::Text(text) => Cow::Borrowed(text),
+            ChatCompletionContent::Parts(parts) => {
+                let texts: Vec<&str> = parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ChatCompletionContentPart::Text { text } => Some(text.as_str()),
+                        _ => None,
+                    })
+                    .collect();
+                Cow::Owned(texts.join("\n"))
+            }
+        }
+    }
+
+    /// Whether the contents have no text and no attachments
+    pub fn is_empty(&self) -> bool {
+        match self {
+            ChatCompletionContent::Text(text) => text.trim().is_empty(),
+            ChatCompletionContent::Parts(parts) => parts.iter().all(|part| match part {
+                ChatCompletionContentPart::Text { text } => text.trim().is_empty(),
+                _ => false,
+            }),
+        }
+    }
+
+    /// The parts of the contents; plain text has none
+    pub fn parts(&self) -> &[ChatCompletionContentPart] {
+        match self {
+            ChatCompletionContent::Text(_) => &[],
+            ChatCompletionContent::Parts(parts) => parts,
+        }
+    }
+
+    /// The images attached to the contents
+    pub fn images(&self) -> impl Iterator<Item = &ImageUrl> {
+        self.parts().iter().filter_map(|part| match part {
+            ChatCompletionContentPart::ImageUrl { image_url } => Some(image_url),
+            _ => None,
+        })
+    }
+
+    /// The audio clips attached to the contents
+    pub fn audio(&self) -> impl Iterator<Item = &InputAudio> {
+        self.parts().iter().filter_map(|part| match part {
+            ChatCompletionContentPart::InputAudio { input_audio } => Some(input_audio),
+            _ => None,
+        })
+    }
+}
+
+impl From<String> for ChatCompletionContent {
+    fn from(text: String) -> Self {
+        ChatCompletionContent::Text(text)
+    }
+}
+
+impl From<&str> for ChatCompletionContent {
+    fn from(text: &str) -> Self {
+        ChatCompletionContent::Text(text.to_string())
+    }
+}
+
+impl ChatCompletionContentPart {
+    /// The name of the part's type, as in its `type` field
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            ChatCompletionContentPart::Text { .. } => "text",
+            ChatCompletionContentPart::ImageUrl { .. } => "image_url",
+            ChatCompletionContentPart::InputAudio { .. } => "input_audio",
+        }
+    }
+}
+
 /// The role of the message author
 #[derive(Debug, Deserialize, Serialize, Clone)]
 #[serde(rename_all = "lowercase")]
@@ -230,20 +437,77 @@ pub struct ChatCompletionFunction {
     /// The parameters the functions accepts, described as a JSON Schema object
     #[serde(skip_serializing_if = "Option::is_none")]
     pub parameters: Option<serde_json::Value>,
+
+    /// Whether the parameters schema follows the rules of strict mode
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 /// Tool choice for chat completion
-#[derive(Debug, Deserialize, Serialize, Clone)]
-#[serde(untagged)]
+///
+/// Serialized as `"none"`, `"auto"`, `"required"` or a named tool choice object.
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
+#[serde(try_from = "ToolChoiceRepr", into = "ToolChoiceRepr")]
 pub enum ChatCompletionToolChoice {
-    None(String),     // "none"
-    Auto(String),     // "auto"
-    Required(String), // "required"
+    /// Never call a tool
+    None,
+    /// Call a tool if the conversation calls for one
+    Auto,
+    /// Always call at least one tool
+    Required,
+    /// Call the named function
     Function(ChatCompletionNamedToolChoice),
 }
 
+impl ChatCompletionToolChoice {
+    /// Force a call to the named function
+    pub fn function(name: impl Into<String>) -> Self {
+        ChatCompletionToolChoice::Function(ChatCompletionNamedToolChoice {
+            tool_type: "function".to_string(),
+            function: ChatCompletionFunctionChoice { name: name.into() },
+        })
+    }
+}
+
+/// The JSON representation of a tool choice
+#[derive(Deserialize, Serialize)]
+#[serde(untagged)]
+enum ToolChoiceRepr {
+    Mode(String),
+    Function(ChatCompletionNamedToolChoice),
+}
+
+impl TryFrom<ToolChoiceRepr> for ChatCompletionToolChoice {
+    type Error = String;
+
+    fn try_from(repr: ToolChoiceRepr) -> Result<Self, Self::Error> {
+        match repr {
+            ToolChoiceRepr::Mode(mode) => match mode.as_str() {
+                "none" => Ok(ChatCompletionToolChoice::None),
+                "auto" => Ok(ChatCompletionToolChoice::Auto),
+                "required" => Ok(ChatCompletionToolChoice::Required),
+                _ => Err(format!(
+                    "Invalid value for 'tool_choice': '{mode}'. Supported values are: 'none', 'auto' and 'required'."
+                )),
+            },
+            ToolChoiceRepr::Function(choice) => Ok(ChatCompletionToolChoice::Function(choice)),
+        }
+    }
+}
+
+impl From<ChatCompletionToolChoice> for ToolChoiceRepr {
+    fn from(choice: ChatCompletionToolChoice) -> Self {
+        match choice {
+            ChatCompletionToolChoice::None => ToolChoiceRepr::Mode("none".to_string()),
+            ChatCompletionToolChoice::Auto => ToolChoiceRepr::Mode("auto".to_string()),
+            ChatCompletionToolChoice::Required => ToolChoiceRepr::Mode("required".to_string()),
+            ChatCompletionToolChoice::Function(choice) => ToolChoiceRepr::Function(choice),
+        }
+    }
+}
+
 /// Named tool choice for forcing a specific function call
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ChatCompletionNamedToolChoice {
     /// The type of the tool. Currently, only `function` is supported
     #[serde(rename = "type")]
@@ -254,7 +518,7 @@ pub struct ChatCompletionNamedToolChoice {
 }
 
 /// Function choice for named tool selection
-#[derive(Debug, Deserialize, Serialize, Clone)]
+#[derive(Debug, Deserialize, Serialize, Clone, PartialEq)]
 pub struct ChatCompletionFunctionChoice {
     /// The name of the function to call
     pub name: String,
@@ -284,6 +548,15 @@ pub struct ChatCompletionFunctionCall {
     pub arguments: String,
 }
 
+impl StreamOptions {
+    /// Whether the final usage chunk was requested for a stream
+    pub fn includes_usage(stream_options: Option<&StreamOptions>) -> bool {
+        stream_options
+            .and_then(|options| options.include_usage)
+            .unwrap_or(false)
+    }
+}
+
 impl CreateCompletionRequest {
     /// Validate the completion request parameters
     pub fn validate(&self) -> Result<(), String> {
@@ -306,56 +579,61 @@ impl CreateCompletionRequest {
             _ => {}
         }
 
-        // Validate max_tokens
-        if let Some(max_tokens) = self.max_tokens {
-            if max_tokens == 0 {
-                return Err("max_tokens must be greater than 0".to_string());
-            }
-            if max_tokens > 4096 {
-                return Err("max_tokens cannot exceed 4096".to_string());
-            }
+        // Validate max_tokens is positive; the handler checks it against the model's context window
+        if let Some(max_tokens) = self.max_tokens
+            && max_tokens == 0
+        {
+            return Err("max_tokens must be greater than 0".to_string());
         }
 
         // Validate temperature
-        if let Some(temperature) = self.temperature {
-            if !(0.0..=2.0).contains(&temperature) {
-                return Err("temperature must be between 0.0 and 2.0".to_string());
-            }
+        if let Some(temperature) = self.temperature
+            && !(0.0..=2.0).contains(&temperature)
+        {
+            return Err("temperature must be between 0.0 and 2.0".to_string());
         }
 
         // Validate top_p
-        if let Some(top_p) = self.top_p {
-            if !(0.0..=1.0).contains(&top_p) {
-                return Err("top_p must be between 0.0 and 1.0".to_string());
-            }
+        if let Some(top_p) = self.top_p
+            && !(0.0..=1.0).contains(&top_p)
+        {
+            return Err("top_p must be between 0.0 and 1.0".to_string());
         }
 
         // Validate n
-        if let Some(n) = self.n {
-            if n == 0 || n > 20 {
-                return Err("n must be between 1 and 20".to_string());
-            }
+        if let Some(n) = self.n
+            && (n == 0 || n > 20)
+        {
+            return Err("n must be between 1 and 20".to_string());
         }
 
         // Validate logprobs
-        if let Some(logprobs) = self.logprobs {
-            if logprobs > 5 {
-                return Err("logprobs cannot exceed 5".to_string());
-            }
+        if let Some(logprobs) = self.logprobs
+            && logprobs > 5
+        {
+            return Err("logprobs cannot exceed 5".to_string());
         }
 
         // Validate presence_penalty
-        if let Some(penalty) = self.presence_penalty {
-            if !(-2.0..=2.0).contains(&penalty) {
-                return Err("presence_penalty must be between -2.0 and 2.0".to_string());
-            }
+        if let Some(penalty) = self.presence_penalty
+            && !(-2.0..=2.0).contains(&penalty)
+        {
+            return Err("presence_penalty must be between -2.0 and 2.0".to_string());
         }
 
         // Validate frequency_penalty
-        if let Some(penalty) = self.frequency_penalty {
-            if !(-2.0..=2.0).contains(&penalty) {
-                return Err("frequency_penalty must be between -2.0 and 2.0".to_string());
-            }
+        if let Some(penalty) = self.frequency_penalty
+            && !(-2.0..=2.0).contains(&penalty)
+        {
+            return Err("frequency_penalty must be between -2.0 and 2.0".to_string());
+        }
+
+        // Validate stream_options
+        if self.stream_options.is_some() && !self.stream.unwrap_or(false) {
+            return Err(
+                "The 'stream_options' parameter is only allowed when 'stream' is enabled."
+                    .to_string(),
+            );
         }
 
         // Validate best_of
@@ -373,79 +651,382 @@ impl CreateCompletionRequest {
     }
 }
 
+/// A request that failed validation
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ValidationError {
+    /// What is wrong with the request
+    pub message: String,
+    /// The path of the offending parameter, such as `tools[0].function.parameters`
+    pub param: Option<String>,
+}
+
+impl ValidationError {
+    /// Create a validation error without a parameter
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            param: None,
+        }
+    }
+
+    /// Set the path of the offending parameter
+    pub fn with_param(mut self, param: impl Into<String>) -> Self {
+        self.param = Some(param.into());
+        self
+    }
+
+    /// Convert into an `invalid_request_error` response body
+    pub fn to_error_response(&self) -> ErrorResponse {
+        let error = ErrorResponse::invalid_request_error(self.message.clone());
+        match &self.param {
+            Some(param) => error.with_param(param.clone()),
+            None => error,
+        }
+    }
+}
+
+impl From<String> for ValidationError {
+    fn from(message: String) -> Self {
+        Self::new(message)
+    }
+}
+
+impl From<&str> for ValidationError {
+    fn from(message: &str) -> Self {
+        Self::new(message)
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ValidationError {}
+
 impl CreateChatCompletionRequest {
     /// Validate the chat completion request parameters
-    pub fn validate(&self) -> Result<(), String> {
+    pub fn validate(&self) -> Result<(), ValidationError> {
         // Validate model is not empty
         if self.model.trim().is_empty() {
-            return Err("Model cannot be empty".to_string());
+            return Err("Model cannot be empty".into());
         }
 
         // Validate messages array is not empty
         if self.messages.is_empty() {
-            return Err("Messages array cannot be empty".to_string());
+            return Err("Messages array cannot be empty".into());
         }
 
         // Validate temperature is within bounds
-        if let Some(temp) = self.temperature {
-            if !(0.0..=2.0).contains(&temp) {
-                return Err("Temperature must be between 0 and 2".to_string());
-            }
+        if let Some(temp) = self.temperature
+            && !(0.0..=2.0).contains(&temp)
+        {
+            return Err("Temperature must be between 0 and 2".into());
         }
 
         // Validate top_p is within bounds
-        if let Some(top_p) = self.top_p {
-            if !(0.0..=1.0).contains(&top_p) {
-                return Err("Top_p must be between 0 and 1".to_string());
-            }
+        if let Some(top_p) = self.top_p
+            && !(0.0..=1.0).contains(&top_p)
+        {
+            return Err("Top_p must be between 0 and 1".into());
         }
 
         // Validate n is positive
-        if let Some(n) = self.n {
-            if n == 0 {
-                return Err("Number of completions (n) must be greater than 0".to_string());
-            }
+        if let Some(n) = self.n
+            && n == 0
+        {
+            return Err("Number of completions (n) must be greater than 0".into());
         }
 
         // Validate max_tokens is positive
-        if let Some(max_tokens) = self.max_tokens {
-            if max_tokens == 0 {
-                return Err("Max tokens must be greater than 0".to_string());
-            }
+        if let Some(max_tokens) = self.max_tokens
+            && max_tokens == 0
+        {
+            return Err("Max tokens must be greater than 0".into());
         }
 
         // Validate presence_penalty is within bounds
-        if let Some(penalty) = self.presence_penalty {
-            if !(-2.0..=2.0).contains(&penalty) {
-                return Err("Presence penalty must be between -2.0 and 2.0".to_string());
-            }
+        if let Some(penalty) = self.presence_penalty
+            && !(-2.0..=2.0).contains(&penalty)
+        {
+            return Err("Presence penalty must be between -2.0 and 2.0".into());
         }
 
         // Validate frequency_penalty is within bounds
-        if let Some(penalty) = self.frequency_penalty {
-            if !(-2.0..=2.0).contains(&penalty) {
-                return Err("Frequency penalty must be between -2.0 and 2.0".to_string());
-            }
+        if let Some(penalty) = self.frequency_penalty
+            && !(-2.0..=2.0).contains(&penalty)
+        {
+            return Err("Frequency penalty must be between -2.0 and 2.0".into());
+        }
+
+        // Validate stream_options
+        if self.stream_options.is_some() && !self.stream.unwrap_or(false) {
+            return Err(
+                "The 'stream_options' parameter is only allowed when 'stream' is enabled.".into(),
+            );
         }
 
+        // Validate tool_choice against the declared tools
+        self.validate_tool_choice()?;
+
+        // Validate response_format
+        self.validate_response_format()?;
+
+        // Validate the schemas of strict functions
+        self.validate_strict_tools()?;
+
         // Validate messages
         for (i, message) in self.messages.iter().enumerate() {
             if let Err(err) = message.validate() {
-                return Err(format!("Invalid message at index {i}: {err}"));
+                return Err(format!("Invalid message at index {i}: {err}").into());
+            }
+        }
+
+        // Validate the images and audio attached to messages
+        for (i, message) in self.messages.iter().enumerate() {
+            let parts = message.content.iter().flat_map(|content| content.parts());
+            for (j, part) in parts.enumerate() {
+                match part {
+                    ChatCompletionContentPart::ImageUrl { image_url } => {
+                        let param = format!("messages.[{i}].content.[{j}].image_url.url");
+                        images::validate_image_url(&image_url.url).map_err(|error| {
+                            ValidationError::new(format!("Invalid '{param}'. {error}"))
+                                .with_param(param.clone())
+                        })?;
+                    }
+                    ChatCompletionContentPart::InputAudio { input_audio } => {
+                        if BASE64_STANDARD.decode(input_audio.data.trim()).is_err() {
+                            let param = format!("messages.[{i}].content.[{j}].input_audio.data");
+                            return Err(ValidationError::new(format!(
+                                "Invalid '{param}'. Expected base64-encoded audio data."
+                            ))
+                            .with_param(param));
+                        }
+                    }
+                    ChatCompletionContentPart::Text { .. } => {}
+                }
+            }
+        }
+
+        // Validate that tool messages answer a tool call of a preceding assistant message
+        let mut tool_call_ids = HashSet::new();
+        for (i, message) in self.messages.iter().enumerate() {
+            if matches!(message.role, ChatCompletionRole::Assistant) {
+                tool_call_ids.extend(message.tool_calls.iter().flatten().map(|call| &call.id));
+            }
+            if let Some(tool_call_id) = &message.tool_call_id
+                && matches!(message.role, ChatCompletionRole::Tool)
+                && !tool_call_ids.contains(tool_call_id)
+            {
+                return Err(ValidationError::new(format!(
+                    "Invalid message at index {i}: tool_call_id '{tool_call_id}' does not match any tool call of a preceding assistant message"
+                ))
+                .with_param(format!("messages.[{i}].tool_call_id")));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the response format the way the API does
+    ///
+    /// `json_object` requires the messages to mention JSON. `json_schema` requires a valid
+    /// name and an object schema that, with `strict`, follows the rules of strict mode.
+    fn validate_response_format(&self) -> Result<(), ValidationError> {
+        match &self.response_format {
+            Some(ResponseFormat::JsonObject) => {
+                let mentions_json = self.messages.iter().any(|message| {
+                    message
+                        .text()
+                        .is_some_and(|content| content.to_lowercase().contains("json"))
+                });
+                if !mentions_json {
+                    return Err(ValidationError::new(
+                        "'messages' must contain the word 'json' in some form, to use 'response_format' of type 'json_object'.",
+                    )
+                    .with_param("messages"));
+                }
             }
+            Some(ResponseFormat::JsonSchema { json_schema }) => {
+                let name = &json_schema.name;
+                if name.is_empty()
+                    || name.len() > 64
+                    || !name
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+                {
+                    return Err(ValidationError::new(
+                        "Invalid 'response_format.json_schema.name': string does not match pattern. Expected a string that matches the pattern '^[a-zA-Z0-9_-]{1,64}$'.",
+                    )
+                    .with_param("response_format.json_schema.name"));
+                }
+
+                match &json_schema.schema {
+                    Some(schema) if !schema.is_object() => {
+                        return Err(ValidationError::new(
+                            "Invalid type for 'response_format.json_schema.schema': expected an object.",
+                        )
+                        .with_param("response_format.json_schema.schema"));
+                    }
+                    Some(schema) if json_schema.strict.unwrap_or(false) => {
+                        schema::check_strict(schema).map_err(|error| {
+                            ValidationError::new(format!(
+                                "Invalid schema for response_format '{name}': {error}"
+                            ))
+                            .with_param("response_format")
+                        })?;
+                    }
+                    _ => {}
+                }
+            }
+            Some(ResponseFormat::Text) | None => {}
         }
 
         Ok(())
     }
+
+    /// Validate that `tool_choice` only names tools the request declares
+    fn validate_tool_choice(&self) -> Result<(), ValidationError> {
+        if self.parallel_tool_calls.is_some()
+            && self.tools.as_deref().unwrap_or_default().is_empty()
+        {
+            return Err(ValidationError::new(
+                "Invalid value for 'parallel_tool_calls': 'parallel_tool_calls' is only allowed when 'tools' are specified.",
+            )
+            .with_param("parallel_tool_calls"));
+        }
+
+        let Some(tool_choice) = &self.tool_choice else {
+            return Ok(());
+        };
+
+        let tools = self.tools.as_deref().unwrap_or_default();
+        if tools.is_empty() {
+            return Err(ValidationError::new(
+                "Invalid value for 'tool_choice': 'tool_choice' is only allowed when 'tools' are specified.",
+            )
+            .with_param("tool_choice"));
+        }
+
+        if let ChatCompletionToolChoice::Function(choice) = tool_choice {
+            if choice.tool_type != "function" {
+                return Err(ValidationError::new(format!(
+                    "Invalid value for 'tool_choice.type': '{}'. Supported values are: 'function'.",
+                    choice.tool_type
+                ))
+                .with_param("tool_choice.type"));
+            }
+            let name = &choice.function.name;
+            if !tools.iter().any(|tool| &tool.function.name == name) {
+                return Err(ValidationError::new(format!(
+                    "Invalid value for 'tool_choice': no function named '{name}' was specified in the 'tools' parameter."
+                ))
+                .with_param("tool_choice"));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the `parameters` of functions marked `strict` against the rules of strict mode
+    fn validate_strict_tools(&self) -> Result<(), ValidationError> {
+        for (i, tool) in self.tools.iter().flatten().enumerate() {
+            let function = &tool.function;
+            if !function.strict.unwrap_or(false) {
+                continue;
+            }
+
+            let param = format!("tools[{i}].function.parameters");
+            match &function.parameters {
+                Some(parameters) if parameters.is_object() => {
+                    schema::check_strict(parameters).map_err(|error| {
+                        ValidationError::new(format!(
+                            "Invalid schema for function '{}': {error}",
+                            function.name
+                        ))
+                        .with_param(param.clone())
+                    })?;
+                }
+                Some(_) => {
+                    return Err(ValidationError::new(format!(
+                        "Invalid type for '{param}': expected an object."
+                    ))
+                    .with_param(param));
+                }
+                None => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The tools the model may call, honoring `tool_choice`
+    ///
+    /// `none` leaves no tools and a named function only that function. Without a
+    /// `tool_choice`, all declared tools may be called, as with `auto`.
+    pub fn callable_tools(&self) -> Vec<&ChatCompletionTool> {
+        let tools = self.tools.as_deref().unwrap_or_default();
+        match &self.tool_choice {
+            Some(ChatCompletionToolChoice::None) => Vec::new(),
+            Some(ChatCompletionToolChoice::Function(choice)) => tools
+                .iter()
+                .filter(|tool| tool.function.name == choice.function.name)
+                .collect(),
+            _ => tools.iter().collect(),
+        }
+    }
+
+    /// Whether the model may call several tools in one turn
+    ///
+    /// Forcing a named function always makes a single call.
+    pub fn allows_parallel_tool_calls(&self) -> bool {
+        self.parallel_tool_calls.unwrap_or(true)
+            && !matches!(
+                self.tool_choice,
+                Some(ChatCompletionToolChoice::Function(_))
+            )
+    }
+
+    /// Whether `tool_choice` obliges the model to call a tool
+    pub fn requires_tool_call(&self) -> bool {
+        matches!(
+            self.tool_choice,
+            Some(ChatCompletionToolChoice::Required | ChatCompletionToolChoice::Function(_))
+        )
+    }
 }
 
 impl ChatCompletionMessage {
+    /// The text of the message's content, without its attachments
+    pub fn text(&self) -> Option<Cow<'_, str>> {
+        self.content.as_ref().map(ChatCompletionContent::text)
+    }
+
     /// Validate the chat completion message
     pub fn validate(&self) -> Result<(), String> {
+        // Only user messages may attach images and audio
+        if !matches!(self.role, ChatCompletionRole::User)
+            && let Some(part) = self
+                .content
+                .iter()
+                .flat_map(|content| content.parts())
+                .find(|part| !matches!(part, ChatCompletionContentPart::Text { .. }))
+        {
+            return Err(format!(
+                "content parts of type '{}' are only supported for messages with role 'user'",
+                part.type_name()
+            ));
+        }
+
         // For most roles, content should be present
         match self.role {
             ChatCompletionRole::System | ChatCompletionRole::User => {
-                if self.content.is_none() || self.content.as_ref().unwrap().trim().is_empty() {
+                if self
+                    .content
+                    .as_ref()
+                    .is_none_or(ChatCompletionContent::is_empty)
+                {
                     return Err("Content cannot be empty for system and user messages".to_string());
                 }
             }
@@ -459,7 +1040,11 @@ impl ChatCompletionMessage {
             }
             ChatCompletionRole::Tool => {
                 // Tool messages must have content and tool_call_id
-                if self.content.is_none() || self.content.as_ref().unwrap().trim().is_empty() {
+                if self
+                    .content
+                    .as_ref()
+                    .is_none_or(ChatCompletionContent::is_empty)
+                {
                     return Err("Tool messages must have content".to_string());
                 }
                 if self.tool_call_id.is_none() {
@@ -503,6 +1088,31 @@ impl CreateEmbeddingRequest {
             _ => {}
         }
 
+        // Validate the number of inputs
+        let input_count = match &self.input {
+            EmbeddingInput::String(_) | EmbeddingInput::IntegerArray(_) => 1,
+            EmbeddingInput::StringArray(arr) => arr.len(),
+            EmbeddingInput::IntegerArrayArray(arr) => arr.len(),
+        };
+        if input_count > MAX_EMBEDDING_INPUTS {
+            return Err(format!(
+                "Too many inputs. The max number of inputs is {MAX_EMBEDDING_INPUTS}. Please reduce the number of inputs and try again."
+            ));
+        }
+
+        // Validate token IDs are in the model's vocabulary
+        match &self.input {
+            EmbeddingInput::IntegerArray(tokens) => {
+                self.decode_tokens(tokens)?;
+            }
+            EmbeddingInput::IntegerArrayArray(arr) => {
+                for tokens in arr {
+                    self.decode_tokens(tokens)?;
+                }
+            }
+            _ => {}
+        }
+
         // Validate encoding_format
         if let Some(ref format) = self.encoding_format {
             match format.as_str() {
@@ -511,33 +1121,68 @@ impl CreateEmbeddingRequest {
             }
         }
 
-        // Validate dimensions
-        if let Some(dimensions) = self.dimensions {
-            if dimensions == 0 {
-                return Err("dimensions must be greater than 0".to_string());
-            }
-            // OpenAI models typically support up to 1536 or 3072 dimensions
-            if dimensions > 3072 {
-                return Err("dimensions cannot exceed 3072".to_string());
-            }
+        // Validate dimensions is positive; the model bounds it from above
+        if self.dimensions == Some(0) {
+            return Err("dimensions must be greater than 0".to_string());
         }
 
         Ok(())
     }
 
     /// Get the input as a vector of strings for processing
+    ///
+    /// Token ID inputs are decoded with the vocabulary of the request's model, so they embed
+    /// like the equivalent text. Token IDs outside the vocabulary decode to an empty string;
+    /// `validate` rejects them.
     pub fn get_input_strings(&self) -> Vec<String> {
         match &self.input {
             EmbeddingInput::String(s) => vec![s.clone()],
             EmbeddingInput::StringArray(arr) => arr.clone(),
-            EmbeddingInput::IntegerArray(arr) => {
-                vec![format!("{:?}", arr)]
+            EmbeddingInput::IntegerArray(tokens) => {
+                vec![self.decode_tokens(tokens).unwrap_or_default()]
+            }
+            EmbeddingInput::IntegerArrayArray(arr) => arr
+                .iter()
+                .map(|tokens| self.decode_tokens(tokens).unwrap_or_default())
+                .collect(),
+        }
+    }
+
+    /// The number of tokens in each input, counted with the tokenizer of the request's model
+    ///
+    /// Token ID inputs count one token per ID.
+    pub fn input_token_counts(&self) -> Vec<u32> {
+        let encoding = Encoding::for_model(&self.model);
+        match &self.input {
+            EmbeddingInput::String(s) => vec![encoding.count_tokens(s)],
+            EmbeddingInput::StringArray(arr) => {
+                arr.iter().map(|s| encoding.count_tokens(s)).collect()
             }
+            EmbeddingInput::IntegerArray(tokens) => vec![tokens.len() as u32],
             EmbeddingInput::IntegerArrayArray(arr) => {
-                arr.iter().map(|inner| format!("{inner:?}")).collect()
+                arr.iter().map(|tokens| tokens.len() as u32).collect()
             }
         }
     }
+
+    /// Decode token IDs with the vocabulary of the request's model
+    fn decode_tokens(&self, tokens: &[i32]) -> Result<String, String> {
+        let encoding = Encoding::for_model(&self.model);
+        let mut ids = Vec::with_capacity(tokens.len());
+        for &token in tokens {
+            match u32::try_from(token) {
+                Ok(id) if encoding.is_valid_token(id) => ids.push(id),
+                _ => {
+                    return Err(format!(
+                        "Invalid token in prompt: {token}. Minimum value is 0, maximum value is {} (inclusive).",
+                        encoding.max_token()
+                    ));
+                }
+            }
+        }
+
+        Ok(encoding.decode(&ids).unwrap_or_default())
+    }
 }
 
 #[cfg(test)]
@@ -555,6 +1200,7 @@ mod tests {
             top_p: Some(1.0),
             n: Some(1),
             stream: Some(false),
+            stream_options: None,
             logprobs: None,
             echo: None,
             stop: None,
@@ -656,6 +1302,32 @@ mod tests {
         assert_eq!(strings, vec!["Hello", "World"]);
     }
 
+    #[test]
+    fn test_token_id_input() {
+        let json = r#"{"model": "text-embedding-3-small", "input": [[9906, 1917], [100276]]}"#;
+        let req: CreateEmbeddingRequest = serde_json::from_str(json).unwrap();
+        assert!(req.validate().is_ok());
+        assert_eq!(
+            req.get_input_strings(),
+            vec!["Hello world", "<|endofprompt|>"]
+        );
+
+        let json = r#"{"model": "text-embedding-3-small", "input": [9906, 100277]}"#;
+        let req: CreateEmbeddingRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            req.validate().unwrap_err(),
+            "Invalid token in prompt: 100277. Minimum value is 0, maximum value is 100276 (inclusive)."
+        );
+
+        let json = r#"{"model": "text-embedding-3-small", "input": [-1]}"#;
+        let req: CreateEmbeddingRequest = serde_json::from_str(json).unwrap();
+        assert!(
+            req.validate()
+                .unwrap_err()
+                .starts_with("Invalid token in prompt: -1.")
+        );
+    }
+
     #[test]
     fn test_complete_json_deserialization() {
         // Test full completion request with all fields
@@ -760,6 +1432,349 @@ mod tests {
         assert!(req.is_err());
     }
 
+    #[test]
+    fn test_stream_options_require_stream() {
+        let json = r#"{
+            "model": "gpt-3.5-turbo",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "stream_options": {"include_usage": true}
+        }"#;
+        let mut req: CreateChatCompletionRequest = serde_json::from_str(json).unwrap();
+        assert!(req.validate().is_err());
+
+        req.stream = Some(true);
+        assert!(req.validate().is_ok());
+        assert!(StreamOptions::includes_usage(req.stream_options.as_ref()));
+
+        let json = r#"{
+            "model": "text-davinci-003",
+            "prompt": "Hi",
+            "stream_options": {"include_usage": true}
+        }"#;
+        let mut req: CreateCompletionRequest = serde_json::from_str(json).unwrap();
+        assert!(req.validate().is_err());
+
+        req.stream = Some(true);
+        assert!(req.validate().is_ok());
+    }
+
+    #[test]
+    fn test_tool_choice() {
+        let json = r#"{
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": "Hi"}],
+            "tools": [
+                {"type": "function", "function": {"name": "get_weather"}},
+                {"type": "function", "function": {"name": "get_time"}}
+            ],
+            "tool_choice": {"type": "function", "function": {"name": "get_time"}}
+        }"#;
+        let mut req: CreateChatCompletionRequest = serde_json::from_str(json).unwrap();
+        assert_eq!(
+            req.tool_choice,
+            Some(ChatCompletionToolChoice::function("get_time"))
+        );
+        assert!(req.validate().is_ok());
+        assert!(req.requires_tool_call());
+        let callable = req.callable_tools();
+        assert_eq!(callable.len(), 1);
+        assert_eq!(callable[0].function.name, "get_time");
+
+        for (value, choice) in [
+            ("none", ChatCompletionToolChoice::None),
+            ("auto", ChatCompletionToolChoice::Auto),
+            ("required", ChatCompletionToolChoice::Required),
+        ] {
+            let parsed: ChatCompletionToolChoice =
+                serde_json::from_value(serde_json::json!(value)).unwrap();
+            assert_eq!(parsed, choice);
+            assert_eq!(serde_json::to_value(&choice).unwrap(), value);
+        }
+        assert!(serde_json::from_str::<ChatCompletionToolChoice>(r#""sometimes""#).is_err());
+
+        req.tool_choice = Some(ChatCompletionToolChoice::None);
+        assert!(req.callable_tools().is_empty());
+        assert!(!req.requires_tool_call());
+
+        req.tool_choice = None;
+        assert_eq!(req.callable_tools().len(), 2);
+
+        // Named functions must be declared in tools
+        req.tool_choice = Some(ChatCompletionToolChoice::function("get_stock_price"));
+        let error = req.validate().unwrap_err().message;
+        assert!(error.contains("no function named 'get_stock_price'"));
+
+        // tool_choice requires tools
+        req.tools = None;
+        req.tool_choice = Some(ChatCompletionToolChoice::Auto);
+        assert!(req.validate().is_err());
+
+        // So does parallel_tool_calls
+        req.tool_choice = None;
+        req.parallel_tool_calls = Some(false);
+        assert!(req.validate().is_err());
+    }
+
+    #[test]
+    fn test_tool_call_id_validation() {
+        let mut req: CreateChatCompletionRequest = serde_json::from_str(
+            r#"{
+                "model": "gpt-4o",
+                "messages": [
+                    {"role": "user", "content": "What's the weather?"},
+                    {"role": "assistant", "tool_calls": [
+                        {"id": "call_1", "type": "function", "function": {"name": "get_weather", "arguments": "{}"}}
+                    ]},
+                    {"role": "tool", "tool_call_id": "call_1", "content": "Sunny"}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(req.validate().is_ok());
+
+        req.messages[2].tool_call_id = Some("call_2".to_string());
+        let error = req.validate().unwrap_err().message;
+        assert!(error.starts_with("Invalid message at index 2: tool_call_id 'call_2'"));
+
+        // The tool call must come before its result
+        req.messages.swap(1, 2);
+        req.messages[1].tool_call_id = Some("call_1".to_string());
+        assert!(req.validate().is_err());
+
+        // Only assistant messages make tool calls
+        req.messages.swap(1, 2);
+        req.messages[1].role = ChatCompletionRole::User;
+        req.messages[1].content = Some(ChatCompletionContent::Text("Use call_1".to_string()));
+        let error = req.validate().unwrap_err().message;
+        assert!(error.starts_with("Invalid message at index 2: tool_call_id 'call_1'"));
+    }
+
+    #[test]
+    fn test_content_parts() {
+        let png = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+        let mut req: CreateChatCompletionRequest = serde_json::from_value(serde_json::json!({
+            "model": "gpt-4o",
+            "messages": [
+                {"role": "user", "content": [
+                    {"type": "text", "text": "What's in these images?"},
+                    {"type": "image_url", "image_url": {"url": png, "detail": "low"}},
+                    {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}},
+                    {"type": "input_audio", "input_audio": {"data": "UklGRg==", "format": "wav"}}
+                ]}
+            ]
+        }))
+        .unwrap();
+        assert!(req.validate().is_ok());
+
+        let content = req.messages[0].content.as_ref().unwrap();
+        assert_eq!(content.text(), "What's in these images?");
+        assert_eq!(content.images().count(), 2);
+        assert_eq!(
+            content.images().next().unwrap().detail,
+            Some(ImageDetail::Low)
+        );
+        assert_eq!(content.audio().next().unwrap().format, AudioFormat::Wav);
+
+        // Unsupported image types are rejected with the path to the URL
+        let ChatCompletionContent::Parts(parts) = req.messages[0].content.as_mut().unwrap() else {
+            panic!("expected content parts");
+        };
+        parts[2] = ChatCompletionContentPart::ImageUrl {
+            image_url: ImageUrl {
+                url: "data:image/bmp;base64,Qk0=".to_string(),
+                detail: None,
+            },
+        };
+        let error = req.validate().unwrap_err();
+        assert!(error.message.contains("unsupported image"));
+        assert_eq!(
+            error.param.as_deref(),
+            Some("messages.[0].content.[2].image_url.url")
+        );
+
+        // So are malformed URLs and audio that is not base64
+        req.messages[0] = serde_json::from_value(serde_json::json!({"role": "user", "content": [
+            {"type": "image_url", "image_url": {"url": "not a url"}}
+        ]}))
+        .unwrap();
+        let error = req.validate().unwrap_err();
+        assert_eq!(
+            error.param.as_deref(),
+            Some("messages.[0].content.[0].image_url.url")
+        );
+
+        req.messages[0] = serde_json::from_value(serde_json::json!({"role": "user", "content": [
+            {"type": "input_audio", "input_audio": {"data": "not base64!", "format": "mp3"}}
+        ]}))
+        .unwrap();
+        let error = req.validate().unwrap_err();
+        assert_eq!(
+            error.param.as_deref(),
+            Some("messages.[0].content.[0].input_audio.data")
+        );
+
+        // Only user messages may carry images
+        req.messages[0] = serde_json::from_value(serde_json::json!({"role": "system", "content": [
+            {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+        ]}))
+        .unwrap();
+        let error = req.validate().unwrap_err().message;
+        assert!(error.contains("only supported for messages with role 'user'"));
+
+        // Text parts are fine for any role, but content may not be an empty list
+        req.messages[0] = serde_json::from_value(serde_json::json!({"role": "system", "content": [
+            {"type": "text", "text": "You are a helpful assistant."}
+        ]}))
+        .unwrap();
+        assert!(req.validate().is_ok());
+        req.messages[0].content = Some(ChatCompletionContent::Parts(Vec::new()));
+        assert!(req.validate().is_err());
+
+        // Unknown part types do not deserialize
+        let part = serde_json::from_value::<ChatCompletionContentPart>(
+            serde_json::json!({"type": "video_url", "video_url": {"url": "https://example.com"}}),
+        );
+        assert!(part.is_err());
+    }
+
+    #[test]
+    fn test_response_format_validation() {
+        fn json_schema(req: &mut CreateChatCompletionRequest) -> &mut ResponseFormatJsonSchema {
+            match &mut req.response_format {
+                Some(ResponseFormat::JsonSchema { json_schema }) => json_schema,
+                _ => unreachable!(),
+            }
+        }
+
+        let mut req: CreateChatCompletionRequest = serde_json::from_str(
+            r#"{
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "Describe a cat"}],
+                "response_format": {"type": "json_object"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(req.response_format, Some(ResponseFormat::JsonObject));
+        let error = req.validate().unwrap_err().message;
+        assert!(error.contains("must contain the word 'json'"));
+        req.messages[0].content = Some("Describe a cat as JSON".into());
+        assert!(req.validate().is_ok());
+
+        req.response_format = serde_json::from_value(serde_json::json!({
+            "type": "json_schema",
+            "json_schema": {
+                "name": "cat",
+                "strict": true,
+                "schema": {
+                    "type": "object",
+                    "properties": {"name": {"type": "string"}, "lives": {"type": "integer"}},
+                    "required": ["name", "lives"],
+                    "additionalProperties": false
+                }
+            }
+        }))
+        .unwrap();
+        assert!(req.validate().is_ok());
+        json_schema(&mut req).schema.as_mut().unwrap()["required"] = serde_json::json!(["name"]);
+        let error = req.validate().unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid schema for response_format 'cat': In context=(), 'required' is required to be supplied and to be an array including every key in properties. Missing 'lives'."
+        );
+        assert_eq!(error.param.as_deref(), Some("response_format"));
+
+        // Without strict, schemas are not held to the rules of strict mode
+        json_schema(&mut req).strict = None;
+        assert!(req.validate().is_ok());
+
+        json_schema(&mut req).name = "my cat".to_string();
+        assert_eq!(
+            req.validate().unwrap_err().param.as_deref(),
+            Some("response_format.json_schema.name")
+        );
+
+        json_schema(&mut req).name = "cat".to_string();
+        json_schema(&mut req).schema = Some(serde_json::json!("object"));
+        assert!(req.validate().is_err());
+
+        assert!(
+            serde_json::from_value::<ResponseFormat>(serde_json::json!({"type": "yaml"})).is_err()
+        );
+    }
+
+    #[test]
+    fn test_strict_function_validation() {
+        let mut req: CreateChatCompletionRequest = serde_json::from_str(
+            r#"{
+                "model": "gpt-4o",
+                "messages": [{"role": "user", "content": "What's the weather in Paris?"}],
+                "tools": [
+                    {"type": "function", "function": {"name": "get_time"}},
+                    {"type": "function", "function": {
+                        "name": "get_weather",
+                        "strict": true,
+                        "parameters": {
+                            "type": "object",
+                            "properties": {
+                                "location": {"type": "string"},
+                                "unit": {"type": ["string", "null"], "enum": ["celsius", "fahrenheit", null]}
+                            },
+                            "required": ["location", "unit"],
+                            "additionalProperties": false
+                        }
+                    }}
+                ]
+            }"#,
+        )
+        .unwrap();
+        assert!(req.validate().is_ok());
+
+        let valid = req.tools.as_ref().unwrap()[1]
+            .function
+            .parameters
+            .clone()
+            .unwrap();
+        let set_parameters = |req: &mut CreateChatCompletionRequest,
+                              parameters: serde_json::Value| {
+            req.tools.as_mut().unwrap()[1].function.parameters = Some(parameters);
+        };
+
+        let mut invalid = valid.clone();
+        invalid
+            .as_object_mut()
+            .unwrap()
+            .remove("additionalProperties");
+        set_parameters(&mut req, invalid);
+        let error = req.validate().unwrap_err();
+        assert_eq!(
+            error.message,
+            "Invalid schema for function 'get_weather': In context=(), 'additionalProperties' is required to be supplied and to be false."
+        );
+        assert_eq!(error.param.as_deref(), Some("tools[1].function.parameters"));
+
+        let mut invalid = valid.clone();
+        invalid["required"] = serde_json::json!(["location"]);
+        set_parameters(&mut req, invalid);
+        assert!(
+            req.validate()
+                .unwrap_err()
+                .message
+                .ends_with("Missing 'unit'.")
+        );
+
+        let mut invalid = valid.clone();
+        invalid["properties"]["location"]["not"] = serde_json::json!({"const": ""});
+        set_parameters(&mut req, invalid);
+        assert_eq!(
+            req.validate().unwrap_err().message,
+            "Invalid schema for function 'get_weather': In context=('properties', 'location'), 'not' is not permitted."
+        );
+
+        // Functions that are not strict are not held to the rules of strict mode
+        req.tools.as_mut().unwrap()[1].function.strict = Some(false);
+        assert!(req.validate().is_ok());
+    }
+
     #[test]
     fn test_request_validation_edge_cases() {
         // Test boundary values for completion request
@@ -771,6 +1786,7 @@ mod tests {
             top_p: Some(0.0),
             n: Some(1),
             stream: None,
+            stream_options: None,
             logprobs: Some(0),
             echo: None,
             stop: None,
@@ -784,7 +1800,7 @@ mod tests {
         assert!(request.validate().is_ok());
 
         // Test maximum valid values
-        request.max_tokens = Some(4096);
+        request.max_tokens = Some(100_000);
         request.temperature = Some(2.0);
         request.top_p = Some(1.0);
         request.n = Some(20);
@@ -793,7 +1809,7 @@ mod tests {
         assert!(request.validate().is_ok());
 
         // Test just over the boundary
-        request.max_tokens = Some(4097);
+        request.logprobs = Some(6);
         assert!(request.validate().is_err());
     }
 
@@ -809,12 +1825,20 @@ mod tests {
         };
         assert!(request.validate().is_ok());
 
-        // Test maximum valid values
-        request.dimensions = Some(3072);
+        // Test the maximum number of inputs
+        request.input = EmbeddingInput::StringArray(vec!["test".to_string(); 2048]);
         assert!(request.validate().is_ok());
 
         // Test just over the boundary
-        request.dimensions = Some(3073);
+        request.input = EmbeddingInput::StringArray(vec!["test".to_string(); 2049]);
+        assert_eq!(
+            request.validate().unwrap_err(),
+            "Too many inputs. The max number of inputs is 2048. Please reduce the number of inputs and try again."
+        );
+
+        // Test zero dimensions
+        request.input = EmbeddingInput::String("test".to_string());
+        request.dimensions = Some(0);
         assert!(request.validate().is_err());
 
         // Test invalid encoding format
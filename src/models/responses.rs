@@ -1,3 +1,4 @@
+use base64::prelude::*;
 use serde::Serialize;
 use std::collections::HashMap;
 
@@ -50,7 +51,7 @@ pub struct CompletionLogprobs {
 }
 
 /// Usage statistics for a completion request
-#[derive(Debug, Serialize)]
+#[derive(Debug, Clone, Serialize)]
 pub struct CompletionUsage {
     /// Number of tokens in the prompt
     pub prompt_tokens: u32,
@@ -60,6 +61,41 @@ pub struct CompletionUsage {
     pub total_tokens: u32,
 }
 
+/// A streamed chunk of a completion response
+#[derive(Debug, Serialize)]
+pub struct CreateCompletionChunk {
+    /// A unique identifier for the completion. Each chunk has the same ID
+    pub id: String,
+    /// The object type, which is always "text_completion"
+    pub object: String,
+    /// The Unix timestamp (in seconds) of when the completion was created
+    pub created: u64,
+    /// The model used for the completion
+    pub model: String,
+    /// The list of completion choices carried by this chunk
+    pub choices: Vec<CompletionChunkChoice>,
+    /// Usage statistics for the entire request, only set on the final chunk when
+    /// `stream_options.include_usage` is true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<CompletionUsage>,
+    /// This fingerprint represents the backend configuration that the model was run with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+/// A completion choice within a streamed chunk
+#[derive(Debug, Serialize)]
+pub struct CompletionChunkChoice {
+    /// The generated text fragment
+    pub text: String,
+    /// The index of the choice in the list of choices
+    pub index: u32,
+    /// The log probabilities of the tokens in this fragment
+    pub logprobs: Option<CompletionLogprobs>,
+    /// The reason the model stopped generating tokens, only set on the last chunk of a choice
+    pub finish_reason: Option<String>,
+}
+
 /// Response from the chat completion API
 #[derive(Debug, Serialize)]
 pub struct CreateChatCompletionResponse {
@@ -162,6 +198,82 @@ pub struct ChatCompletionTopLogprob {
     pub bytes: Option<Vec<u8>>,
 }
 
+/// A streamed chunk of a chat completion response
+#[derive(Debug, Serialize)]
+pub struct CreateChatCompletionChunk {
+    /// A unique identifier for the chat completion. Each chunk has the same ID
+    pub id: String,
+    /// The object type, which is always "chat.completion.chunk"
+    pub object: String,
+    /// The Unix timestamp (in seconds) of when the chat completion was created
+    pub created: u64,
+    /// The model used for the chat completion
+    pub model: String,
+    /// A list of chat completion choices carried by this chunk
+    pub choices: Vec<ChatCompletionChunkChoice>,
+    /// Usage statistics for the entire request, only set on the final chunk when
+    /// `stream_options.include_usage` is true
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<CompletionUsage>,
+    /// This fingerprint represents the backend configuration that the model was run with
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub system_fingerprint: Option<String>,
+}
+
+/// A chat completion choice within a streamed chunk
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionChunkChoice {
+    /// The index of the choice in the list of choices
+    pub index: u32,
+    /// A chat completion delta generated by streamed model responses
+    pub delta: ChatCompletionStreamDelta,
+    /// The reason the model stopped generating tokens, only set on the last chunk of a choice
+    pub finish_reason: Option<String>,
+}
+
+/// A chat completion delta generated by streamed model responses
+#[derive(Debug, Default, Serialize)]
+pub struct ChatCompletionStreamDelta {
+    /// The role of the author of this message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub role: Option<String>,
+    /// The contents of the chunk message
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    /// The tool call fragments carried by this chunk
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ChatCompletionMessageToolCallChunk>>,
+    /// The refusal message generated by the model
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub refusal: Option<String>,
+}
+
+/// A fragment of a tool call in a streamed chat completion
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionMessageToolCallChunk {
+    /// The index of the tool call in the message's list of tool calls
+    pub index: u32,
+    /// The ID of the tool call, only sent with the first fragment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    /// The type of the tool, only sent with the first fragment
+    #[serde(rename = "type", skip_serializing_if = "Option::is_none")]
+    pub tool_type: Option<String>,
+    /// The function name and argument fragment
+    pub function: ChatCompletionFunctionCallChunk,
+}
+
+/// A fragment of a function call in a streamed chat completion
+#[derive(Debug, Serialize)]
+pub struct ChatCompletionFunctionCallChunk {
+    /// The name of the function to call, only sent with the first fragment
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// A fragment of the JSON encoded arguments
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub arguments: Option<String>,
+}
+
 /// Response from the embeddings API
 #[derive(Debug, Serialize)]
 pub struct CreateEmbeddingResponse {
@@ -181,11 +293,21 @@ pub struct EmbeddingData {
     /// The object type, which is always "embedding"
     pub object: String,
     /// The embedding vector
-    pub embedding: Vec<f64>,
+    pub embedding: EmbeddingVector,
     /// The index of the embedding in the list of embeddings
     pub index: u32,
 }
 
+/// An embedding vector in the requested `encoding_format`
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum EmbeddingVector {
+    /// A list of floats (`encoding_format: "float"`)
+    Float(Vec<f64>),
+    /// Base64 of the little-endian float32 values (`encoding_format: "base64"`)
+    Base64(String),
+}
+
 /// Usage statistics for an embedding request
 #[derive(Debug, Serialize)]
 pub struct EmbeddingUsage {
@@ -195,6 +317,28 @@ pub struct EmbeddingUsage {
     pub total_tokens: u32,
 }
 
+/// A model object, as returned by the models API
+#[derive(Debug, Clone, Serialize)]
+pub struct Model {
+    /// The model identifier
+    pub id: String,
+    /// The object type, which is always "model"
+    pub object: String,
+    /// The Unix timestamp (in seconds) when the model was created
+    pub created: u64,
+    /// The organization that owns the model
+    pub owned_by: String,
+}
+
+/// Response from the list models API
+#[derive(Debug, Serialize)]
+pub struct ListModelsResponse {
+    /// The object type, which is always "list"
+    pub object: String,
+    /// The available models
+    pub data: Vec<Model>,
+}
+
 /// Error response structure that matches OpenAI API format
 #[derive(Debug, Serialize)]
 pub struct ErrorResponse {
@@ -263,6 +407,61 @@ impl CompletionChoice {
     }
 }
 
+impl CreateCompletionChunk {
+    /// Create a new completion chunk
+    pub fn new(
+        id: String,
+        model: String,
+        created: u64,
+        choices: Vec<CompletionChunkChoice>,
+    ) -> Self {
+        Self {
+            id,
+            object: "text_completion".to_string(),
+            created,
+            model,
+            choices,
+            usage: None,
+            system_fingerprint: None,
+        }
+    }
+
+    /// Set the usage statistics carried by this chunk
+    pub fn with_usage(mut self, usage: CompletionUsage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Whether this chunk carries generated text, rather than only a finish reason or usage
+    pub fn carries_tokens(&self) -> bool {
+        self.choices.iter().any(|choice| !choice.text.is_empty())
+    }
+}
+
+impl CompletionChunkChoice {
+    /// Create a new chunk choice carrying a text fragment
+    pub fn new(text: String, index: u32) -> Self {
+        Self {
+            text,
+            index,
+            logprobs: None,
+            finish_reason: None,
+        }
+    }
+
+    /// Set log probabilities for this fragment
+    pub fn with_logprobs(mut self, logprobs: CompletionLogprobs) -> Self {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
+    /// Set the finish reason for this chunk choice
+    pub fn with_finish_reason(mut self, finish_reason: String) -> Self {
+        self.finish_reason = Some(finish_reason);
+        self
+    }
+}
+
 impl CompletionUsage {
     /// Create new completion usage statistics
     pub fn new(prompt_tokens: u32, completion_tokens: u32) -> Self {
@@ -365,6 +564,122 @@ impl ChatCompletionMessageToolCall {
     }
 }
 
+impl CreateChatCompletionChunk {
+    /// Create a new chat completion chunk
+    pub fn new(
+        id: String,
+        model: String,
+        created: u64,
+        choices: Vec<ChatCompletionChunkChoice>,
+    ) -> Self {
+        Self {
+            id,
+            object: "chat.completion.chunk".to_string(),
+            created,
+            model,
+            choices,
+            usage: None,
+            system_fingerprint: None,
+        }
+    }
+
+    /// Set the usage statistics carried by this chunk
+    pub fn with_usage(mut self, usage: CompletionUsage) -> Self {
+        self.usage = Some(usage);
+        self
+    }
+
+    /// Whether this chunk carries generated content, refusal or tool call tokens, rather
+    /// than only a role, a finish reason or usage
+    pub fn carries_tokens(&self) -> bool {
+        self.choices.iter().any(|choice| {
+            choice.delta.content.is_some()
+                || choice.delta.refusal.is_some()
+                || choice.delta.tool_calls.is_some()
+        })
+    }
+}
+
+impl ChatCompletionChunkChoice {
+    /// Create a new chunk choice carrying a delta
+    pub fn new(index: u32, delta: ChatCompletionStreamDelta) -> Self {
+        Self {
+            index,
+            delta,
+            finish_reason: None,
+        }
+    }
+
+    /// Set the finish reason for this chunk choice
+    pub fn with_finish_reason(mut self, finish_reason: String) -> Self {
+        self.finish_reason = Some(finish_reason);
+        self
+    }
+}
+
+impl ChatCompletionStreamDelta {
+    /// Create the opening delta that announces the assistant role
+    pub fn role(role: String) -> Self {
+        Self {
+            role: Some(role),
+            content: Some(String::new()),
+            ..Default::default()
+        }
+    }
+
+    /// Create a delta carrying a piece of message content
+    pub fn content(content: String) -> Self {
+        Self {
+            content: Some(content),
+            ..Default::default()
+        }
+    }
+
+    /// Create a delta carrying a piece of a refusal message
+    pub fn refusal(refusal: String) -> Self {
+        Self {
+            refusal: Some(refusal),
+            ..Default::default()
+        }
+    }
+
+    /// Create a delta carrying a single tool call fragment
+    pub fn tool_call(tool_call: ChatCompletionMessageToolCallChunk) -> Self {
+        Self {
+            tool_calls: Some(vec![tool_call]),
+            ..Default::default()
+        }
+    }
+}
+
+impl ChatCompletionMessageToolCallChunk {
+    /// Create the first fragment of a tool call, carrying its ID and function name
+    pub fn start(index: u32, id: String, function_name: String) -> Self {
+        Self {
+            index,
+            id: Some(id),
+            tool_type: Some("function".to_string()),
+            function: ChatCompletionFunctionCallChunk {
+                name: Some(function_name),
+                arguments: Some(String::new()),
+            },
+        }
+    }
+
+    /// Create a fragment carrying a piece of the tool call arguments
+    pub fn arguments(index: u32, arguments: String) -> Self {
+        Self {
+            index,
+            id: None,
+            tool_type: None,
+            function: ChatCompletionFunctionCallChunk {
+                name: None,
+                arguments: Some(arguments),
+            },
+        }
+    }
+}
+
 impl CreateEmbeddingResponse {
     /// Create a new embedding response
     pub fn new(data: Vec<EmbeddingData>, model: String, usage: EmbeddingUsage) -> Self {
@@ -379,7 +694,7 @@ impl CreateEmbeddingResponse {
 
 impl EmbeddingData {
     /// Create a new embedding object
-    pub fn new(embedding: Vec<f64>, index: u32) -> Self {
+    pub fn new(embedding: EmbeddingVector, index: u32) -> Self {
         Self {
             object: "embedding".to_string(),
             embedding,
@@ -388,6 +703,45 @@ impl EmbeddingData {
     }
 }
 
+impl EmbeddingVector {
+    /// Encode values as base64 of their little-endian float32 bytes, like the real API
+    pub fn base64(values: &[f64]) -> Self {
+        let bytes: Vec<u8> = values
+            .iter()
+            .flat_map(|&value| (value as f32).to_le_bytes())
+            .collect();
+        EmbeddingVector::Base64(BASE64_STANDARD.encode(bytes))
+    }
+
+    /// The values of the vector, decoding base64 if needed
+    pub fn to_floats(&self) -> Vec<f64> {
+        match self {
+            EmbeddingVector::Float(values) => values.clone(),
+            EmbeddingVector::Base64(encoded) => BASE64_STANDARD
+                .decode(encoded)
+                .unwrap_or_default()
+                .chunks_exact(4)
+                .map(|chunk| {
+                    f64::from(f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+                })
+                .collect(),
+        }
+    }
+
+    /// The number of dimensions of the vector
+    pub fn len(&self) -> usize {
+        match self {
+            EmbeddingVector::Float(values) => values.len(),
+            EmbeddingVector::Base64(_) => self.to_floats().len(),
+        }
+    }
+
+    /// Whether the vector has no dimensions
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
 impl EmbeddingUsage {
     /// Create new embedding usage statistics
     pub fn new(prompt_tokens: u32) -> Self {
@@ -398,6 +752,28 @@ impl EmbeddingUsage {
     }
 }
 
+impl Model {
+    /// Create a new model object
+    pub fn new(id: String, created: u64, owned_by: String) -> Self {
+        Self {
+            id,
+            object: "model".to_string(),
+            created,
+            owned_by,
+        }
+    }
+}
+
+impl ListModelsResponse {
+    /// Create a new list models response
+    pub fn new(data: Vec<Model>) -> Self {
+        Self {
+            object: "list".to_string(),
+            data,
+        }
+    }
+}
+
 impl ErrorResponse {
     /// Create a new error response
     pub fn new(message: String, error_type: String) -> Self {
@@ -471,7 +847,7 @@ mod tests {
     #[test]
     fn test_embedding_response_serialization() {
         let usage = EmbeddingUsage::new(8);
-        let embedding = EmbeddingData::new(vec![0.1, 0.2, 0.3], 0);
+        let embedding = EmbeddingData::new(EmbeddingVector::Float(vec![0.1, 0.2, 0.3]), 0);
         let response = CreateEmbeddingResponse::new(
             vec![embedding],
             "text-embedding-ada-002".to_string(),
@@ -484,6 +860,22 @@ mod tests {
         assert!(json.contains("\"embedding\":[0.1,0.2,0.3]"));
     }
 
+    #[test]
+    fn test_base64_embedding_serialization() {
+        // The bytes of the float32 values 1.0 and -2.0, as produced by numpy
+        let embedding = EmbeddingVector::base64(&[1.0, -2.0]);
+        assert_eq!(
+            embedding,
+            EmbeddingVector::Base64("AACAPwAAAMA=".to_string())
+        );
+        assert_eq!(
+            serde_json::to_value(&embedding).unwrap(),
+            serde_json::json!("AACAPwAAAMA=")
+        );
+        assert_eq!(embedding.to_floats(), vec![1.0, -2.0]);
+        assert_eq!(embedding.len(), 2);
+    }
+
     #[test]
     fn test_error_response_serialization() {
         let error = ErrorResponse::invalid_request_error("Invalid model specified".to_string())
@@ -513,6 +905,36 @@ mod tests {
         assert_eq!(logprobs.token_logprobs.len(), 2);
     }
 
+    #[test]
+    fn test_chat_completion_chunk_serialization() {
+        let role_chunk = CreateChatCompletionChunk::new(
+            "chatcmpl-123".to_string(),
+            "gpt-3.5-turbo".to_string(),
+            1677649420,
+            vec![ChatCompletionChunkChoice::new(
+                0,
+                ChatCompletionStreamDelta::role("assistant".to_string()),
+            )],
+        );
+
+        let json = serde_json::to_string(&role_chunk).expect("Failed to serialize");
+        assert!(json.contains("\"object\":\"chat.completion.chunk\""));
+        assert!(json.contains("\"delta\":{\"role\":\"assistant\",\"content\":\"\"}"));
+        assert!(json.contains("\"finish_reason\":null"));
+
+        let tool_chunk = ChatCompletionChunkChoice::new(
+            0,
+            ChatCompletionStreamDelta::tool_call(ChatCompletionMessageToolCallChunk::arguments(
+                1,
+                "{\"a\"".to_string(),
+            )),
+        );
+        let json = serde_json::to_string(&tool_chunk).expect("Failed to serialize");
+        assert!(json.contains("\"index\":1"));
+        assert!(!json.contains("\"id\""));
+        assert!(!json.contains("\"name\""));
+    }
+
     #[test]
     fn test_usage_calculations() {
         let completion_usage = CompletionUsage::new(100, 50);
@@ -0,0 +1,521 @@
+//! # Rate Limiting
+//!
+//! This module simulates the per-key rate limits of the OpenAI API so client-side throttlers
+//! can be exercised against the mock. Each API key gets a requests-per-minute bucket
+//! (`--rate-limit-rpm`) and a tokens-per-minute bucket (`--rate-limit-tpm`). Both buckets
+//! refill continuously, reaching their full capacity after one minute.
+//!
+//! Every request takes one request from its key's request bucket. The tokens a response uses,
+//! taken from the `usage` the generators report, are taken from the token bucket once the
+//! response is produced. A request arriving while either bucket is exhausted is rejected with
+//! a `429` `rate_limit_exceeded` error and a `retry-after` header.
+//!
+//! ## Headers
+//!
+//! Responses carry the same headers as the real API, for each configured limit:
+//!
+//! ```text
+//! x-ratelimit-limit-requests: 60
+//! x-ratelimit-limit-tokens: 150000
+//! x-ratelimit-remaining-requests: 59
+//! x-ratelimit-remaining-tokens: 149984
+//! x-ratelimit-reset-requests: 1s
+//! x-ratelimit-reset-tokens: 6ms
+//! ```
+//!
+//! The reset headers give the time until the bucket is full again.
+
+use poem::{
+    Endpoint, IntoResponse, Middleware, Request, Response, Result,
+    http::{HeaderName, HeaderValue, StatusCode, header},
+};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::models::responses::ErrorResponse;
+
+/// Tokens used by a response, attached by the handlers as a response extension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenUsage(pub u32);
+
+impl TokenUsage {
+    /// Attach the token usage to a response
+    pub fn attach(self, mut response: Response) -> Response {
+        response.extensions_mut().insert(self);
+        response
+    }
+}
+
+/// A bucket that refills continuously to its capacity over one minute
+#[derive(Debug, Clone)]
+struct Bucket {
+    capacity: f64,
+    available: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn new(capacity: u32, now: Instant) -> Self {
+        Self {
+            capacity: capacity as f64,
+            available: capacity as f64,
+            updated: now,
+        }
+    }
+
+    /// Units regained per second
+    fn refill_rate(&self) -> f64 {
+        self.capacity / 60.0
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_rate()).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Time until `amount` units are available
+    ///
+    /// A bucket with no capacity never refills, so it reports one minute, the period the
+    /// limit is expressed over.
+    fn time_until(&self, amount: f64) -> Duration {
+        let missing = (amount - self.available).max(0.0);
+        if missing == 0.0 {
+            Duration::ZERO
+        } else if self.refill_rate() > 0.0 {
+            Duration::from_secs_f64(missing / self.refill_rate())
+        } else {
+            Duration::from_secs(60)
+        }
+    }
+
+    fn status(&self) -> BucketStatus {
+        BucketStatus {
+            limit: self.capacity as u32,
+            remaining: self.available.max(0.0).floor() as u32,
+            reset: self.time_until(self.capacity),
+        }
+    }
+}
+
+/// A snapshot of one bucket, as reported in the `x-ratelimit-*` headers
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BucketStatus {
+    /// The bucket capacity per minute
+    pub limit: u32,
+    /// The units currently available
+    pub remaining: u32,
+    /// The time until the bucket is full again
+    pub reset: Duration,
+}
+
+/// A snapshot of a key's buckets
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimitStatus {
+    /// The request bucket, if a requests-per-minute limit is configured
+    pub requests: Option<BucketStatus>,
+    /// The token bucket, if a tokens-per-minute limit is configured
+    pub tokens: Option<BucketStatus>,
+}
+
+impl RateLimitStatus {
+    /// Add the `x-ratelimit-*` headers to a response
+    pub fn apply_headers(&self, response: &mut Response) {
+        let headers = response.headers_mut();
+        for (kind, bucket) in [("requests", self.requests), ("tokens", self.tokens)] {
+            let Some(bucket) = bucket else {
+                continue;
+            };
+            let values = [
+                ("limit", bucket.limit.to_string()),
+                ("remaining", bucket.remaining.to_string()),
+                ("reset", format_duration(bucket.reset)),
+            ];
+            for (name, value) in values {
+                let name = HeaderName::try_from(format!("x-ratelimit-{name}-{kind}"));
+                if let (Ok(name), Ok(value)) = (name, HeaderValue::from_str(&value)) {
+                    headers.insert(name, value);
+                }
+            }
+        }
+    }
+}
+
+/// A request rejected because one of its key's buckets is exhausted
+#[derive(Debug, Clone, PartialEq)]
+pub struct RateLimitExceeded {
+    /// The exhausted bucket, `requests` or `tokens`
+    pub kind: &'static str,
+    /// The time until the request would be admitted
+    pub retry_after: Duration,
+    /// The key's buckets at the time of the rejection
+    pub status: RateLimitStatus,
+}
+
+impl RateLimitExceeded {
+    /// Convert the rejection into an OpenAI-formatted `429` response
+    pub fn to_response(&self) -> Response {
+        let bucket = match self.kind {
+            "requests" => self.status.requests,
+            _ => self.status.tokens,
+        };
+        let limit = bucket.map(|bucket| bucket.limit).unwrap_or_default();
+        let message = format!(
+            "Rate limit reached for {} per min (RPM/TPM) on api key ***: Limit {limit}. Please try again in {}.",
+            self.kind,
+            format_duration(self.retry_after)
+        );
+        let error_response = ErrorResponse::new(message, self.kind.to_string())
+            .with_code("rate_limit_exceeded".to_string());
+
+        let json_body = match serde_json::to_string(&error_response) {
+            Ok(json) => json,
+            Err(_) => {
+                r#"{"error":{"message":"Rate limit reached","type":"requests","code":"rate_limit_exceeded"}}"#
+                    .to_string()
+            }
+        };
+
+        // retry-after is a whole number of seconds, rounded up so the retry is admitted
+        let retry_after = self.retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        let mut response = Response::builder()
+            .status(StatusCode::TOO_MANY_REQUESTS)
+            .header("content-type", "application/json")
+            .header(header::RETRY_AFTER, retry_after)
+            .body(json_body);
+        self.status.apply_headers(&mut response);
+        response
+    }
+}
+
+/// The buckets of a single API key
+#[derive(Debug, Clone)]
+struct KeyBuckets {
+    requests: Option<Bucket>,
+    tokens: Option<Bucket>,
+}
+
+impl KeyBuckets {
+    fn refill(&mut self, now: Instant) {
+        self.requests
+            .iter_mut()
+            .for_each(|bucket| bucket.refill(now));
+        self.tokens.iter_mut().for_each(|bucket| bucket.refill(now));
+    }
+
+    fn status(&self) -> RateLimitStatus {
+        RateLimitStatus {
+            requests: self.requests.as_ref().map(Bucket::status),
+            tokens: self.tokens.as_ref().map(Bucket::status),
+        }
+    }
+}
+
+/// Per-key requests-per-minute and tokens-per-minute buckets
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    requests_per_minute: Option<u32>,
+    tokens_per_minute: Option<u32>,
+    keys: Mutex<HashMap<String, KeyBuckets>>,
+}
+
+impl RateLimiter {
+    /// Create a rate limiter; a `None` limit leaves that dimension unlimited
+    pub fn new(requests_per_minute: Option<u32>, tokens_per_minute: Option<u32>) -> Self {
+        Self {
+            requests_per_minute,
+            tokens_per_minute,
+            keys: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether no limit is configured
+    pub fn is_disabled(&self) -> bool {
+        self.requests_per_minute.is_none() && self.tokens_per_minute.is_none()
+    }
+
+    /// Admit a request for a key, taking one request from its request bucket
+    ///
+    /// The request is rejected if the request bucket has less than one request left or the
+    /// token bucket is used up.
+    pub fn acquire(&self, key: &str) -> Result<RateLimitStatus, RateLimitExceeded> {
+        self.acquire_at(key, Instant::now())
+    }
+
+    /// Take the tokens a response used from a key's token bucket
+    ///
+    /// The bucket may go below zero, in which case further requests are rejected until it
+    /// has refilled.
+    pub fn consume_tokens(&self, key: &str, tokens: u32) -> RateLimitStatus {
+        self.consume_tokens_at(key, tokens, Instant::now())
+    }
+
+    fn acquire_at(&self, key: &str, now: Instant) -> Result<RateLimitStatus, RateLimitExceeded> {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        let buckets = self.buckets(&mut keys, key, now);
+
+        if let Some(requests) = &buckets.requests
+            && requests.available < 1.0
+        {
+            return Err(RateLimitExceeded {
+                kind: "requests",
+                retry_after: requests.time_until(1.0),
+                status: buckets.status(),
+            });
+        }
+
+        if let Some(tokens) = &buckets.tokens
+            && tokens.available <= 0.0
+        {
+            return Err(RateLimitExceeded {
+                kind: "tokens",
+                retry_after: tokens.time_until(1.0),
+                status: buckets.status(),
+            });
+        }
+
+        if let Some(requests) = &mut buckets.requests {
+            requests.available -= 1.0;
+        }
+        Ok(buckets.status())
+    }
+
+    fn consume_tokens_at(&self, key: &str, tokens: u32, now: Instant) -> RateLimitStatus {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        let buckets = self.buckets(&mut keys, key, now);
+
+        if let Some(bucket) = &mut buckets.tokens {
+            bucket.available -= tokens as f64;
+        }
+        buckets.status()
+    }
+
+    /// Look up a key's buckets, creating full ones for new keys, and refill them
+    fn buckets<'a>(
+        &self,
+        keys: &'a mut HashMap<String, KeyBuckets>,
+        key: &str,
+        now: Instant,
+    ) -> &'a mut KeyBuckets {
+        let buckets = keys.entry(key.to_string()).or_insert_with(|| KeyBuckets {
+            requests: self
+                .requests_per_minute
+                .map(|capacity| Bucket::new(capacity, now)),
+            tokens: self
+                .tokens_per_minute
+                .map(|capacity| Bucket::new(capacity, now)),
+        });
+        buckets.refill(now);
+        buckets
+    }
+}
+
+/// Format a duration the way the `x-ratelimit-reset-*` headers do (`120ms`, `1.5s`, `6m0s`)
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    if millis < 1000 {
+        return format!("{millis}ms");
+    }
+
+    let minutes = millis / 60_000;
+    let seconds = (millis % 60_000) as f64 / 1000.0;
+    let seconds = format!("{seconds:.3}");
+    let seconds = seconds.trim_end_matches('0').trim_end_matches('.');
+    if minutes > 0 {
+        format!("{minutes}m{seconds}s")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+/// Middleware that enforces the per-key rate limits and adds the `x-ratelimit-*` headers
+///
+/// The key is the Bearer token of the request, so the middleware belongs inside
+/// [`AuthMiddleware`](crate::auth::AuthMiddleware).
+#[derive(Clone, Default)]
+pub struct RateLimitMiddleware {
+    limiter: Arc<RateLimiter>,
+}
+
+impl RateLimitMiddleware {
+    /// Create a rate limit middleware
+    pub fn new(limiter: RateLimiter) -> Self {
+        Self {
+            limiter: Arc::new(limiter),
+        }
+    }
+}
+
+impl<E> Middleware<E> for RateLimitMiddleware
+where
+    E: Endpoint,
+{
+    type Output = RateLimitEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        RateLimitEndpoint {
+            inner: ep,
+            limiter: self.limiter.clone(),
+        }
+    }
+}
+
+/// Wrapper endpoint that enforces rate limits
+pub struct RateLimitEndpoint<E> {
+    inner: E,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<E> Endpoint for RateLimitEndpoint<E>
+where
+    E: Endpoint,
+{
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        if self.limiter.is_disabled() {
+            return self.inner.call(req).await.map(IntoResponse::into_response);
+        }
+
+        let key = req
+            .headers()
+            .get(header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim_start_matches("Bearer ").to_string())
+            .unwrap_or_default();
+
+        let mut status = match self.limiter.acquire(&key) {
+            Ok(status) => status,
+            Err(exceeded) => {
+                tracing::debug!("Rate limit reached for {}", exceeded.kind);
+                return Ok(exceeded.to_response());
+            }
+        };
+
+        let mut resp = self.inner.call(req).await?.into_response();
+        if let Some(TokenUsage(tokens)) = resp.extensions().get::<TokenUsage>().copied() {
+            status = self.limiter.consume_tokens(&key, tokens);
+        }
+        status.apply_headers(&mut resp);
+
+        Ok(resp)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{EndpointExt, Route, handler, post, test::TestClient};
+
+    #[handler]
+    async fn usage_handler() -> Response {
+        TokenUsage(40).attach(Response::builder().body("ok"))
+    }
+
+    #[test]
+    fn test_request_bucket_refills() {
+        let limiter = RateLimiter::new(Some(2), None);
+        let start = Instant::now();
+
+        let status = limiter.acquire_at("sk-a", start).unwrap();
+        assert_eq!(status.requests.unwrap().remaining, 1);
+        assert!(status.tokens.is_none());
+        limiter.acquire_at("sk-a", start).unwrap();
+
+        let exceeded = limiter.acquire_at("sk-a", start).unwrap_err();
+        assert_eq!(exceeded.kind, "requests");
+        assert_eq!(exceeded.retry_after, Duration::from_secs(30));
+
+        // Other keys have their own buckets
+        assert!(limiter.acquire_at("sk-b", start).is_ok());
+
+        // One request is regained every 30 seconds
+        let later = start + Duration::from_secs(31);
+        assert!(limiter.acquire_at("sk-a", later).is_ok());
+    }
+
+    #[test]
+    fn test_zero_limit_is_always_exhausted() {
+        let limiter = RateLimiter::new(Some(0), Some(0));
+        let start = Instant::now();
+
+        let exceeded = limiter.acquire_at("sk-a", start).unwrap_err();
+        assert_eq!(exceeded.kind, "requests");
+        assert_eq!(exceeded.retry_after, Duration::from_secs(60));
+        let requests = exceeded.status.requests.unwrap();
+        assert_eq!(requests.limit, 0);
+        assert_eq!(requests.remaining, 0);
+        assert_eq!(requests.reset, Duration::ZERO);
+
+        let later = start + Duration::from_secs(120);
+        assert!(limiter.acquire_at("sk-a", later).is_err());
+
+        let tokens_only = RateLimiter::new(None, Some(0));
+        let exceeded = tokens_only.acquire_at("sk-a", start).unwrap_err();
+        assert_eq!(exceeded.kind, "tokens");
+        assert_eq!(exceeded.retry_after, Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_token_bucket_exhaustion() {
+        let limiter = RateLimiter::new(None, Some(600));
+        let start = Instant::now();
+
+        limiter.acquire_at("sk-a", start).unwrap();
+        let status = limiter.consume_tokens_at("sk-a", 700, start);
+        let tokens = status.tokens.unwrap();
+        assert_eq!(tokens.remaining, 0);
+        assert_eq!(tokens.reset, Duration::from_secs(70));
+
+        let exceeded = limiter.acquire_at("sk-a", start).unwrap_err();
+        assert_eq!(exceeded.kind, "tokens");
+        assert!(exceeded.retry_after > Duration::from_secs(10));
+
+        let later = start + Duration::from_secs(11);
+        assert!(limiter.acquire_at("sk-a", later).is_ok());
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(120)), "120ms");
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1.5s");
+        assert_eq!(format_duration(Duration::from_secs(1)), "1s");
+        assert_eq!(format_duration(Duration::from_secs(360)), "6m0s");
+        assert_eq!(format_duration(Duration::from_millis(61_250)), "1m1.25s");
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_middleware() {
+        let app = Route::new()
+            .at("/chat/completions", post(usage_handler))
+            .with(RateLimitMiddleware::new(RateLimiter::new(
+                Some(1),
+                Some(100),
+            )));
+        let client = TestClient::new(app);
+
+        let resp = client
+            .post("/chat/completions")
+            .header("authorization", "Bearer sk-test")
+            .send()
+            .await;
+        resp.assert_status_is_ok();
+        resp.assert_header("x-ratelimit-limit-requests", "1");
+        resp.assert_header("x-ratelimit-remaining-requests", "0");
+        resp.assert_header("x-ratelimit-limit-tokens", "100");
+        resp.assert_header("x-ratelimit-remaining-tokens", "60");
+
+        let resp = client
+            .post("/chat/completions")
+            .header("authorization", "Bearer sk-test")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::TOO_MANY_REQUESTS);
+        resp.assert_header_exist("retry-after");
+        resp.assert_header("x-ratelimit-remaining-requests", "0");
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["error"]["code"], "rate_limit_exceeded");
+        assert_eq!(body["error"]["type"], "requests");
+    }
+}
@@ -0,0 +1,618 @@
+//! # Model Registry
+//!
+//! This module holds the catalog of models the mock serves. The catalog backs the models API
+//! (`/v1/models` and `/v1/models/{id}`) and decides which models each endpoint accepts:
+//! requests naming an unknown model get a `404` `model_not_found` error, and requests sending
+//! a model to an endpoint it does not support get a `404` naming the mismatch, like the real
+//! API.
+//!
+//! Without configuration the registry holds a built-in catalog of current OpenAI models. A
+//! custom catalog is loaded from a YAML or JSON file given by the `--models` option:
+//!
+//! ```yaml
+//! models:
+//!   - id: gpt-4o
+//!     capabilities: [chat]
+//!     context_window: 128000
+//!     owned_by: system
+//!   - id: my-embedder
+//!     capabilities: [embedding]
+//!     context_window: 8191
+//!     embedding_dimensions: 768
+//! ```
+//!
+//! `owned_by` defaults to `openai` and `created` to `0`. Embedding models must declare their
+//! default `embedding_dimensions`; requests may ask for shorter vectors with `dimensions`
+//! unless the model sets `supports_dimensions: false`.
+//!
+//! Chat and legacy completion requests whose prompt tokens plus `max_tokens` exceed a model's
+//! `context_window`, and embedding inputs longer than it, get a `400`
+//! `context_length_exceeded` error. Models without a
+//! `context_window` accept prompts of any length.
+
+use poem::{Response, http::StatusCode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::Path;
+
+use crate::models::responses::{ErrorResponse, Model};
+
+/// An API surface a model can serve
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Capability {
+    /// `/v1/chat/completions`
+    Chat,
+    /// `/v1/completions`
+    Completion,
+    /// `/v1/embeddings`
+    Embedding,
+}
+
+impl Capability {
+    /// The endpoint serving this capability
+    pub fn endpoint(self) -> &'static str {
+        match self {
+            Capability::Chat => "v1/chat/completions",
+            Capability::Completion => "v1/completions",
+            Capability::Embedding => "v1/embeddings",
+        }
+    }
+
+    /// How the error for an unsupported model describes the capability
+    fn model_kind(self) -> &'static str {
+        match self {
+            Capability::Chat => "a chat model",
+            Capability::Completion => "a completions model",
+            Capability::Embedding => "an embedding model",
+        }
+    }
+}
+
+/// A model in the registry
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModelDefinition {
+    /// The model identifier
+    pub id: String,
+
+    /// The organization that owns the model
+    #[serde(default = "default_owned_by")]
+    pub owned_by: String,
+
+    /// The Unix timestamp (in seconds) when the model was created
+    #[serde(default)]
+    pub created: u64,
+
+    /// The endpoints the model can be used with
+    pub capabilities: Vec<Capability>,
+
+    /// The maximum number of tokens in a request and its completion
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context_window: Option<u32>,
+
+    /// The length of the vectors returned when a request does not set `dimensions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub embedding_dimensions: Option<u32>,
+
+    /// Whether embedding requests may shorten the vectors with `dimensions`
+    #[serde(default = "default_supports_dimensions")]
+    pub supports_dimensions: bool,
+}
+
+fn default_owned_by() -> String {
+    "openai".to_string()
+}
+
+fn default_supports_dimensions() -> bool {
+    true
+}
+
+impl ModelDefinition {
+    /// Whether the model can be used with an endpoint
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+
+    /// The model object returned by the models API
+    pub fn to_model(&self) -> Model {
+        Model::new(self.id.clone(), self.created, self.owned_by.clone())
+    }
+
+    /// Check the `dimensions` an embedding request asks for
+    ///
+    /// Models that do not support `dimensions` reject it, and the others accept up to their
+    /// default `embedding_dimensions`.
+    pub fn check_dimensions(&self, dimensions: Option<u32>) -> Result<(), ErrorResponse> {
+        let Some(dimensions) = dimensions else {
+            return Ok(());
+        };
+
+        if !self.supports_dimensions {
+            return Err(ErrorResponse::invalid_request_error(
+                "This model does not support specifying dimensions.".to_string(),
+            )
+            .with_param("dimensions".to_string()));
+        }
+
+        if let Some(max_dimensions) = self.embedding_dimensions
+            && dimensions > max_dimensions
+        {
+            return Err(ErrorResponse::invalid_request_error(format!(
+                "Invalid value for 'dimensions' = {dimensions}. Must be less than or equal to {max_dimensions}."
+            ))
+            .with_param("dimensions".to_string()));
+        }
+
+        Ok(())
+    }
+
+    /// Check that a prompt and the requested completion fit in the model's context window
+    pub fn check_context_length(
+        &self,
+        capability: Capability,
+        prompt_tokens: u32,
+        max_tokens: Option<u32>,
+    ) -> Result<(), ContextLengthExceeded> {
+        let Some(context_window) = self.context_window else {
+            return Ok(());
+        };
+
+        let requested = u64::from(prompt_tokens) + u64::from(max_tokens.unwrap_or(0));
+        if requested > u64::from(context_window) {
+            return Err(ContextLengthExceeded {
+                capability,
+                context_window,
+                prompt_tokens,
+                max_tokens,
+            });
+        }
+
+        Ok(())
+    }
+}
+
+/// The on-disk representation of a models file
+#[derive(Debug, Clone, Deserialize)]
+pub struct ModelFile {
+    pub models: Vec<ModelDefinition>,
+}
+
+/// The built-in catalog: id, owner, created, capability, context window, dimensions
+type BuiltinModel = (
+    &'static str,
+    &'static str,
+    u64,
+    Capability,
+    u32,
+    Option<u32>,
+);
+
+/// Built-in embedding models that predate the `dimensions` parameter
+const FIXED_DIMENSION_MODELS: &[&str] = &["text-embedding-ada-002"];
+
+#[rustfmt::skip]
+const BUILTIN_MODELS: &[BuiltinModel] = &[
+    ("gpt-4o", "system", 1715367049, Capability::Chat, 128_000, None),
+    ("gpt-4o-mini", "system", 1721172741, Capability::Chat, 128_000, None),
+    ("gpt-4-turbo", "system", 1712361441, Capability::Chat, 128_000, None),
+    ("gpt-4", "openai", 1687882411, Capability::Chat, 8_192, None),
+    ("gpt-3.5-turbo", "openai", 1677610602, Capability::Chat, 16_385, None),
+    ("gpt-3.5-turbo-instruct", "system", 1692901427, Capability::Completion, 4_096, None),
+    ("davinci-002", "system", 1692634301, Capability::Completion, 16_384, None),
+    ("babbage-002", "system", 1692634615, Capability::Completion, 16_384, None),
+    ("text-davinci-003", "openai-internal", 1669599635, Capability::Completion, 4_097, None),
+    ("text-embedding-3-small", "system", 1705948997, Capability::Embedding, 8_191, Some(1536)),
+    ("text-embedding-3-large", "system", 1705953180, Capability::Embedding, 8_191, Some(3072)),
+    ("text-embedding-ada-002", "openai-internal", 1671217299, Capability::Embedding, 8_191, Some(1536)),
+];
+
+/// The catalog of models served by the mock
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    models: Vec<ModelDefinition>,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl ModelRegistry {
+    /// Create a registry from model definitions, validating them
+    pub fn new(models: Vec<ModelDefinition>) -> Result<Self, RegistryError> {
+        let mut ids = HashSet::new();
+        for model in &models {
+            if model.id.is_empty() {
+                return Err(RegistryError::InvalidModel(
+                    "model id cannot be empty".to_string(),
+                ));
+            }
+            if !ids.insert(model.id.as_str()) {
+                return Err(RegistryError::InvalidModel(format!(
+                    "model '{}' is listed more than once",
+                    model.id
+                )));
+            }
+            if model.capabilities.is_empty() {
+                return Err(RegistryError::InvalidModel(format!(
+                    "model '{}' has no capabilities",
+                    model.id
+                )));
+            }
+            if model.supports(Capability::Embedding)
+                && model.embedding_dimensions.unwrap_or_default() == 0
+            {
+                return Err(RegistryError::InvalidModel(format!(
+                    "embedding model '{}' needs embedding_dimensions",
+                    model.id
+                )));
+            }
+        }
+
+        Ok(Self { models })
+    }
+
+    /// The built-in catalog of OpenAI models
+    pub fn builtin() -> Self {
+        let models = BUILTIN_MODELS
+            .iter()
+            .map(
+                |&(id, owned_by, created, capability, context_window, embedding_dimensions)| {
+                    ModelDefinition {
+                        id: id.to_string(),
+                        owned_by: owned_by.to_string(),
+                        created,
+                        capabilities: vec![capability],
+                        context_window: Some(context_window),
+                        embedding_dimensions,
+                        supports_dimensions: !FIXED_DIMENSION_MODELS.contains(&id),
+                    }
+                },
+            )
+            .collect();
+        Self { models }
+    }
+
+    /// Load a registry from a YAML (`.yaml`/`.yml`) or JSON file
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self, RegistryError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| RegistryError::Io(format!("{}: {e}", path.display())))?;
+
+        let is_yaml = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("yaml") || ext.eq_ignore_ascii_case("yml"));
+
+        let file: ModelFile = if is_yaml {
+            serde_yaml::from_str(&contents).map_err(|e| RegistryError::Parse(e.to_string()))?
+        } else {
+            serde_json::from_str(&contents).map_err(|e| RegistryError::Parse(e.to_string()))?
+        };
+        Self::new(file.models)
+    }
+
+    /// The models in catalog order
+    pub fn models(&self) -> &[ModelDefinition] {
+        &self.models
+    }
+
+    /// Look up a model by id
+    pub fn get(&self, id: &str) -> Option<&ModelDefinition> {
+        self.models.iter().find(|model| model.id == id)
+    }
+
+    /// Look up the model a request names, checking that it supports the endpoint
+    pub fn check(&self, id: &str, capability: Capability) -> Result<&ModelDefinition, ModelError> {
+        let model = self
+            .get(id)
+            .ok_or_else(|| ModelError::NotFound(id.to_string()))?;
+
+        if !model.supports(capability) {
+            return Err(ModelError::Unsupported {
+                model: id.to_string(),
+                capability,
+            });
+        }
+
+        Ok(model)
+    }
+}
+
+/// A request naming a model that cannot serve it
+#[derive(Debug, Clone, PartialEq)]
+pub enum ModelError {
+    /// The model is not in the registry
+    NotFound(String),
+    /// The model does not support the endpoint
+    Unsupported {
+        model: String,
+        capability: Capability,
+    },
+}
+
+impl ModelError {
+    /// Convert the error into an OpenAI-formatted error response
+    pub fn to_error_response(&self) -> ErrorResponse {
+        match self {
+            ModelError::NotFound(model) => ErrorResponse::invalid_request_error(format!(
+                "The model `{model}` does not exist or you do not have access to it."
+            ))
+            .with_code("model_not_found".to_string()),
+            ModelError::Unsupported { capability, .. } => {
+                ErrorResponse::invalid_request_error(format!(
+                    "This is not {} and thus not supported in the {} endpoint.",
+                    capability.model_kind(),
+                    capability.endpoint()
+                ))
+                .with_param("model".to_string())
+            }
+        }
+    }
+
+    /// Convert the error into a `404 Not Found` response
+    pub fn to_response(&self) -> Response {
+        let json_body = match serde_json::to_string(&self.to_error_response()) {
+            Ok(json) => json,
+            Err(_) => r#"{"error":{"message":"The model does not exist","type":"invalid_request_error","code":"model_not_found"}}"#
+                .to_string(),
+        };
+
+        Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header("content-type", "application/json")
+            .body(json_body)
+    }
+}
+
+impl fmt::Display for ModelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_error_response().error.message)
+    }
+}
+
+impl std::error::Error for ModelError {}
+
+/// A request too long for a model's context window
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContextLengthExceeded {
+    /// The endpoint the request was sent to
+    pub capability: Capability,
+    /// The model's context window
+    pub context_window: u32,
+    /// The tokens in the prompt
+    pub prompt_tokens: u32,
+    /// The completion tokens requested
+    pub max_tokens: Option<u32>,
+}
+
+impl ContextLengthExceeded {
+    /// Convert the error into an OpenAI-formatted error response
+    ///
+    /// The message follows the format of the endpoint the request was sent to.
+    pub fn to_error_response(&self) -> ErrorResponse {
+        let context_window = self.context_window;
+        let prompt_tokens = self.prompt_tokens;
+        let requested = u64::from(prompt_tokens) + u64::from(self.max_tokens.unwrap_or(0));
+
+        let error = match (self.capability, self.max_tokens) {
+            (Capability::Chat, Some(max_tokens)) => ErrorResponse::invalid_request_error(format!(
+                "This model's maximum context length is {context_window} tokens. However, you requested {requested} tokens ({prompt_tokens} in the messages, {max_tokens} in the completion). Please reduce the length of the messages or completion."
+            ))
+            .with_param("messages".to_string()),
+            (Capability::Chat, None) => ErrorResponse::invalid_request_error(format!(
+                "This model's maximum context length is {context_window} tokens. However, your messages resulted in {prompt_tokens} tokens. Please reduce the length of the messages."
+            ))
+            .with_param("messages".to_string()),
+            (_, max_tokens) => ErrorResponse::invalid_request_error(format!(
+                "This model's maximum context length is {context_window} tokens, however you requested {requested} tokens ({prompt_tokens} in your prompt; {} for the completion). Please reduce your prompt; or completion length.",
+                max_tokens.unwrap_or(0)
+            )),
+        };
+
+        error.with_code("context_length_exceeded".to_string())
+    }
+
+    /// Convert the error into a `400 Bad Request` response
+    pub fn to_response(&self) -> Response {
+        let json_body = match serde_json::to_string(&self.to_error_response()) {
+            Ok(json) => json,
+            Err(_) => r#"{"error":{"message":"This model's maximum context length was exceeded","type":"invalid_request_error","code":"context_length_exceeded"}}"#
+                .to_string(),
+        };
+
+        Response::builder()
+            .status(StatusCode::BAD_REQUEST)
+            .header("content-type", "application/json")
+            .body(json_body)
+    }
+}
+
+impl fmt::Display for ContextLengthExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_error_response().error.message)
+    }
+}
+
+impl std::error::Error for ContextLengthExceeded {}
+
+/// Model registry configuration error types
+#[derive(Debug, Clone)]
+pub enum RegistryError {
+    Io(String),
+    Parse(String),
+    InvalidModel(String),
+}
+
+impl fmt::Display for RegistryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RegistryError::Io(msg) => write!(f, "Failed to read models file: {msg}"),
+            RegistryError::Parse(msg) => write!(f, "Failed to parse models file: {msg}"),
+            RegistryError::InvalidModel(msg) => write!(f, "Invalid model: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for RegistryError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_catalog() {
+        let registry = ModelRegistry::default();
+        assert!(registry.get("gpt-4o").unwrap().supports(Capability::Chat));
+        assert_eq!(
+            registry
+                .get("text-embedding-3-large")
+                .unwrap()
+                .embedding_dimensions,
+            Some(3072)
+        );
+        assert!(ModelRegistry::new(registry.models().to_vec()).is_ok());
+    }
+
+    #[test]
+    fn test_check_model() {
+        let registry = ModelRegistry::default();
+        assert!(registry.check("gpt-4o", Capability::Chat).is_ok());
+
+        let error = registry.check("gpt-5-ultra", Capability::Chat).unwrap_err();
+        assert_eq!(error, ModelError::NotFound("gpt-5-ultra".to_string()));
+        let error_response = error.to_error_response();
+        assert_eq!(
+            error_response.error.code.as_deref(),
+            Some("model_not_found")
+        );
+
+        let error = registry
+            .check("text-embedding-3-small", Capability::Chat)
+            .unwrap_err();
+        assert!(matches!(error, ModelError::Unsupported { .. }));
+        assert_eq!(
+            error.to_string(),
+            "This is not a chat model and thus not supported in the v1/chat/completions endpoint."
+        );
+    }
+
+    #[test]
+    fn test_check_dimensions() {
+        let registry = ModelRegistry::default();
+        let small = registry.get("text-embedding-3-small").unwrap();
+        assert!(small.check_dimensions(None).is_ok());
+        assert!(small.check_dimensions(Some(256)).is_ok());
+        assert!(small.check_dimensions(Some(1536)).is_ok());
+        assert_eq!(
+            small
+                .check_dimensions(Some(1537))
+                .unwrap_err()
+                .error
+                .message,
+            "Invalid value for 'dimensions' = 1537. Must be less than or equal to 1536."
+        );
+
+        let ada = registry.get("text-embedding-ada-002").unwrap();
+        assert!(ada.check_dimensions(None).is_ok());
+        let error = ada.check_dimensions(Some(1536)).unwrap_err();
+        assert_eq!(
+            error.error.message,
+            "This model does not support specifying dimensions."
+        );
+        assert_eq!(error.error.param.as_deref(), Some("dimensions"));
+    }
+
+    #[test]
+    fn test_check_context_length() {
+        let registry = ModelRegistry::default();
+        let gpt4 = registry.get("gpt-4").unwrap();
+        assert!(
+            gpt4.check_context_length(Capability::Chat, 8_000, Some(192))
+                .is_ok()
+        );
+
+        let error = gpt4
+            .check_context_length(Capability::Chat, 245, Some(8_000))
+            .unwrap_err();
+        let error_response = error.to_error_response();
+        assert_eq!(
+            error_response.error.code.as_deref(),
+            Some("context_length_exceeded")
+        );
+        assert_eq!(error_response.error.param.as_deref(), Some("messages"));
+        assert_eq!(
+            error.to_string(),
+            "This model's maximum context length is 8192 tokens. However, you requested 8245 tokens (245 in the messages, 8000 in the completion). Please reduce the length of the messages or completion."
+        );
+
+        let error = gpt4
+            .check_context_length(Capability::Chat, 9_000, None)
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "This model's maximum context length is 8192 tokens. However, your messages resulted in 9000 tokens. Please reduce the length of the messages."
+        );
+
+        let error = registry
+            .get("gpt-3.5-turbo-instruct")
+            .unwrap()
+            .check_context_length(Capability::Completion, 62, Some(4_100))
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "This model's maximum context length is 4096 tokens, however you requested 4162 tokens (62 in your prompt; 4100 for the completion). Please reduce your prompt; or completion length."
+        );
+        assert_eq!(error.to_response().status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_parse_models_file() {
+        let file: ModelFile = serde_yaml::from_str(
+            r#"
+models:
+  - id: local-llm
+    capabilities: [chat, completion]
+    context_window: 4096
+  - id: local-embedder
+    capabilities: [embedding]
+    embedding_dimensions: 384
+"#,
+        )
+        .unwrap();
+        let registry = ModelRegistry::new(file.models).unwrap();
+
+        let model = registry.get("local-llm").unwrap();
+        assert_eq!(model.owned_by, "openai");
+        assert!(model.supports(Capability::Completion));
+        assert!(registry.get("gpt-4o").is_none());
+    }
+
+    #[test]
+    fn test_invalid_models() {
+        let embedder = ModelDefinition {
+            id: "embedder".to_string(),
+            owned_by: default_owned_by(),
+            created: 0,
+            capabilities: vec![Capability::Embedding],
+            context_window: None,
+            embedding_dimensions: None,
+            supports_dimensions: true,
+        };
+        assert!(matches!(
+            ModelRegistry::new(vec![embedder.clone()]),
+            Err(RegistryError::InvalidModel(_))
+        ));
+
+        let embedder = ModelDefinition {
+            embedding_dimensions: Some(8),
+            ..embedder
+        };
+        assert!(matches!(
+            ModelRegistry::new(vec![embedder.clone(), embedder]),
+            Err(RegistryError::InvalidModel(msg)) if msg.contains("more than once")
+        ));
+    }
+}
@@ -0,0 +1,967 @@
+//! # JSON Schema Values
+//!
+//! This module generates JSON values that validate against a JSON Schema, such as the
+//! `parameters` of a function tool. The chat completions generator uses it to fill in tool
+//! call arguments, so applications receive arguments they can actually deserialize.
+//!
+//! ## Supported Keywords
+//!
+//! - **Types**: `object`, `array`, `string`, `integer`, `number`, `boolean` and `null`,
+//!   including type lists such as `["string", "null"]`
+//! - **Objects**: `properties` and `required`
+//! - **Arrays**: `items`, `minItems`, `maxItems` and `uniqueItems`
+//! - **Strings**: `minLength`, `maxLength` and the `date-time`, `date`, `time`, `email`,
+//!   `uri`, `uuid`, `ipv4`, `ipv6` and `hostname` formats
+//! - **Numbers**: `minimum`, `maximum`, `exclusiveMinimum`, `exclusiveMaximum` and `multipleOf`
+//! - **Composition**: `enum`, `const`, `anyOf`, `oneOf`, `allOf` and local `$ref`s into
+//!   `$defs` or `definitions`
+//!
+//! `pattern` is not supported. Generated arrays hold at most 64 items and strings at most
+//! 1024 characters, whatever `minItems` and `minLength` ask for.
+//!
+//! ## Values
+//!
+//! Values are deterministic: the same schema and context always produce the same value.
+//! Where possible they are drawn from the context, usually the last user message. A
+//! `location` property picks up the place in "What's the weather in Paris?", an enum picks
+//! the option the message mentions, and a number picks a number from the message that lies
+//! within its bounds. Required properties are always filled in; optional properties only
+//! when the context supplies a value for them.
+//!
+//! ## Strict Mode
+//!
+//! [`check_strict`] applies the rules the OpenAI API enforces on schemas marked
+//! `"strict": true`: the root is an object, every object sets `additionalProperties: false`
+//! and lists all its properties in `required`, and keywords such as `allOf`, `not` or
+//! `patternProperties` are not permitted. Violations are described with the context of the
+//! offending subschema, in the API's words.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use openai_mock::schema::generate_value;
+//! use serde_json::json;
+//!
+//! let schema = json!({
+//!     "type": "object",
+//!     "properties": {
+//!         "location": {"type": "string"},
+//!         "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]}
+//!     },
+//!     "required": ["location", "unit"]
+//! });
+//!
+//! let value = generate_value(&schema, "What's the weather in Paris in fahrenheit?");
+//! assert_eq!(value, json!({"location": "Paris", "unit": "fahrenheit"}));
+//! ```
+
+use regex::Regex;
+use serde_json::{Map, Number, Value};
+use std::sync::LazyLock;
+
+/// How deeply nested schemas are followed before giving up with `null`
+const MAX_DEPTH: usize = 16;
+
+/// The most items a generated array holds
+const MAX_ITEMS: usize = 64;
+
+/// How many draws per item a `uniqueItems` array makes before settling for fewer items
+const UNIQUE_ATTEMPTS: usize = 8;
+
+/// The longest a generated string is padded to, in characters
+const MAX_LENGTH: usize = 1024;
+
+/// Phrases introducing a place, such as "in Paris" or "near New York"
+static PLACE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"\b(?:in|at|near|for|from|to)\s+([A-Z][\w'.-]*(?:(?:,\s*|\s+)[A-Z][\w'.-]*)*)")
+        .expect("valid place regex")
+});
+
+/// Numbers in the context
+static NUMBER: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"-?\d+(?:\.\d+)?").expect("valid number regex"));
+
+/// Arithmetic expressions in the context, such as "2 + 2" or "(3 * 4) / 2"
+static EXPRESSION: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"[\d(][\d\s.()]*[-+*/^%][\d\s.()+*/^%-]*[\d)]").expect("valid expression regex")
+});
+
+/// Words filling string properties that the context has no value for
+const WORDS: &[&str] = &[
+    "alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel",
+];
+
+/// Keywords that strict mode does not permit
+const UNSUPPORTED_STRICT_KEYWORDS: &[&str] = &[
+    "allOf",
+    "not",
+    "if",
+    "then",
+    "else",
+    "dependentRequired",
+    "dependentSchemas",
+    "patternProperties",
+    "unevaluatedProperties",
+    "propertyNames",
+    "minProperties",
+    "maxProperties",
+    "unevaluatedItems",
+    "contains",
+    "minContains",
+    "maxContains",
+    "uniqueItems",
+];
+
+/// Generate a value for a schema, drawing on the context where possible
+pub fn generate_value(schema: &Value, context: &str) -> Value {
+    let generator = ValueGenerator {
+        root: schema,
+        context,
+    };
+    generator.value(schema, "", 0, true).unwrap_or(Value::Null)
+}
+
+/// Check a schema against the rules of strict mode
+///
+/// Returns the API's description of the first violation, such as
+/// `In context=('properties', 'location'), 'additionalProperties' is required to be supplied and to be false.`
+pub fn check_strict(schema: &Value) -> Result<(), String> {
+    let root_type = schema.get("type").and_then(Value::as_str);
+    if root_type != Some("object") {
+        return Err(format!(
+            "schema must be a JSON Schema of 'type: \"object\"', got 'type: \"{}\"'.",
+            root_type.unwrap_or("None")
+        ));
+    }
+    check_strict_subschema(schema, &mut Vec::new())
+}
+
+/// Check a subschema, and the subschemas it contains, against the rules of strict mode
+///
+/// `context` holds the keys leading to the subschema, formatted as in the API's messages.
+fn check_strict_subschema(schema: &Value, context: &mut Vec<String>) -> Result<(), String> {
+    let in_context = |context: &[String], message: &str| {
+        let context = match context {
+            [] => "()".to_string(),
+            [key] => format!("({key},)"),
+            keys => format!("({})", keys.join(", ")),
+        };
+        Err(format!("In context={context}, {message}"))
+    };
+
+    if context.len() > MAX_DEPTH * 2 {
+        return in_context(
+            context,
+            &format!("schema exceeds the maximum nesting depth of {MAX_DEPTH}."),
+        );
+    }
+    let Some(object) = schema.as_object() else {
+        return in_context(context, "schema must be a JSON object.");
+    };
+
+    if let Some(keyword) = UNSUPPORTED_STRICT_KEYWORDS
+        .iter()
+        .find(|keyword| object.contains_key(**keyword))
+    {
+        return in_context(context, &format!("'{keyword}' is not permitted."));
+    }
+
+    let is_object = object.contains_key("properties")
+        || match object.get("type") {
+            Some(Value::String(name)) => name == "object",
+            Some(Value::Array(names)) => names.iter().any(|name| name == "object"),
+            _ => false,
+        };
+    if is_object {
+        if object.get("additionalProperties") != Some(&Value::Bool(false)) {
+            return in_context(
+                context,
+                "'additionalProperties' is required to be supplied and to be false.",
+            );
+        }
+
+        let required: Vec<&str> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        if let Some(properties) = object.get("properties").and_then(Value::as_object)
+            && let Some(missing) = properties
+                .keys()
+                .find(|name| !required.contains(&name.as_str()))
+        {
+            return in_context(
+                context,
+                &format!(
+                    "'required' is required to be supplied and to be an array including every key in properties. Missing '{missing}'."
+                ),
+            );
+        }
+    }
+
+    // Check the subschemas, extending the context with the keys leading to each
+    for keyword in ["properties", "$defs", "definitions"] {
+        if let Some(subschemas) = object.get(keyword).and_then(Value::as_object) {
+            for (name, subschema) in subschemas {
+                context.extend([format!("'{keyword}'"), format!("'{name}'")]);
+                check_strict_subschema(subschema, context)?;
+                context.truncate(context.len() - 2);
+            }
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(subschemas) = object.get(keyword).and_then(Value::as_array) {
+            for (index, subschema) in subschemas.iter().enumerate() {
+                context.extend([format!("'{keyword}'"), index.to_string()]);
+                check_strict_subschema(subschema, context)?;
+                context.truncate(context.len() - 2);
+            }
+        }
+    }
+    if let Some(items) = object.get("items") {
+        context.push("'items'".to_string());
+        check_strict_subschema(items, context)?;
+        context.pop();
+    }
+
+    Ok(())
+}
+
+/// Generates the values of a single schema
+struct ValueGenerator<'a> {
+    /// The schema that `$ref`s are resolved against
+    root: &'a Value,
+    /// The text values are drawn from
+    context: &'a str,
+}
+
+impl ValueGenerator<'_> {
+    /// Generate the value of a schema at a property path
+    ///
+    /// Returns `None` for optional values the context does not supply, unless `required`.
+    fn value(&self, schema: &Value, path: &str, depth: usize, required: bool) -> Option<Value> {
+        if depth > MAX_DEPTH {
+            return required.then_some(Value::Null);
+        }
+
+        let schema = self.resolve(schema);
+        let Some(object) = schema.as_object() else {
+            // `true` and `{}` accept anything
+            return required.then(|| Value::String(self.word(path)));
+        };
+
+        if let Some(value) = object.get("const") {
+            return Some(value.clone());
+        }
+
+        if let Some(options) = object.get("enum").and_then(Value::as_array) {
+            return self.choose(options, path, required);
+        }
+
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(branch) =
+                object
+                    .get(keyword)
+                    .and_then(Value::as_array)
+                    .and_then(|branches| {
+                        branches
+                            .iter()
+                            .find(|branch| !is_null_schema(self.resolve(branch)))
+                    })
+            {
+                return self.value(branch, path, depth + 1, required);
+            }
+        }
+
+        if let Some(branches) = object.get("allOf").and_then(Value::as_array) {
+            let merged = Value::Object(self.merge_all_of(branches));
+            return self.value(&merged, path, depth + 1, required);
+        }
+
+        self.typed_value(object, path, depth, required)
+    }
+
+    /// Generate a value for the `type` of a schema
+    fn typed_value(
+        &self,
+        object: &Map<String, Value>,
+        path: &str,
+        depth: usize,
+        required: bool,
+    ) -> Option<Value> {
+        match schema_type(object) {
+            "object" => Some(self.object(object, path, depth)),
+            "array" => self.array(object, path, depth, required),
+            "string" => self.string(object, path, required),
+            "integer" => self.number(object, path, true, required),
+            "number" => self.number(object, path, false, required),
+            "boolean" => required.then_some(Value::Bool(true)),
+            "null" => required.then_some(Value::Null),
+            _ => required.then(|| Value::String(self.word(path))),
+        }
+    }
+
+    /// Generate an object with its required properties and the optional ones the context fills
+    fn object(&self, object: &Map<String, Value>, path: &str, depth: usize) -> Value {
+        let required: Vec<&str> = object
+            .get("required")
+            .and_then(Value::as_array)
+            .map(|names| names.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+
+        let mut result = Map::new();
+        if let Some(properties) = object.get("properties").and_then(Value::as_object) {
+            for (name, property) in properties {
+                let is_required = required.contains(&name.as_str());
+                let property_path = join_path(path, name);
+                if let Some(value) = self.value(property, &property_path, depth + 1, is_required) {
+                    result.insert(name.clone(), value);
+                }
+            }
+        }
+        Value::Object(result)
+    }
+
+    /// Generate an array with at least `minItems` and at most `maxItems` items
+    fn array(
+        &self,
+        object: &Map<String, Value>,
+        path: &str,
+        depth: usize,
+        required: bool,
+    ) -> Option<Value> {
+        let min_items = object.get("minItems").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let max_items = object
+            .get("maxItems")
+            .and_then(Value::as_u64)
+            .map(|n| n as usize);
+        let unique = object
+            .get("uniqueItems")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+
+        if !required && min_items == 0 {
+            return None;
+        }
+
+        let count = min_items
+            .max(1)
+            .min(max_items.unwrap_or(usize::MAX))
+            .min(MAX_ITEMS);
+        let items = object.get("items").map(|items| self.resolve(items));
+        let options = items
+            .and_then(|items| items.get("enum"))
+            .and_then(Value::as_array);
+
+        // Unique items draw again, with a fresh path, until enough distinct values exist
+        let attempts = if unique {
+            count.saturating_mul(UNIQUE_ATTEMPTS)
+        } else {
+            count
+        };
+        let mut values: Vec<Value> = Vec::with_capacity(count);
+        for attempt in 0..attempts {
+            if values.len() == count {
+                break;
+            }
+            let item_path = format!("{path}[{attempt}]");
+            let value = match (items, options) {
+                // Enum items take consecutive options so unique items stay unique
+                (_, Some(options)) if !options.is_empty() => {
+                    let start = self.choose_index(options, path);
+                    options[(start + attempt) % options.len()].clone()
+                }
+                (Some(items), _) => self
+                    .value(items, &item_path, depth + 1, true)
+                    .unwrap_or(Value::Null),
+                (None, _) => Value::String(self.word(&item_path)),
+            };
+            if unique && values.contains(&value) {
+                continue;
+            }
+            values.push(value);
+        }
+        Some(Value::Array(values))
+    }
+
+    /// Generate a string, honoring its format and length bounds
+    fn string(&self, object: &Map<String, Value>, path: &str, required: bool) -> Option<Value> {
+        let format = object.get("format").and_then(Value::as_str);
+        let drawn = self.drawn_string(property_name(path));
+
+        let value = match (format, drawn) {
+            (Some(format), _) if format_value(format).is_some() => format_value(format),
+            (_, Some(drawn)) => Some(drawn),
+            (_, None) if required => Some(self.word(path)),
+            _ => None,
+        }?;
+
+        let min_length = object.get("minLength").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let max_length = object
+            .get("maxLength")
+            .and_then(Value::as_u64)
+            .map(|n| n as usize);
+        Some(Value::String(fit_length(
+            value,
+            min_length.min(MAX_LENGTH),
+            max_length,
+        )))
+    }
+
+    /// Draw a string for a property from the context
+    fn drawn_string(&self, name: &str) -> Option<String> {
+        let name = name.to_lowercase();
+        let context = self.context.trim();
+        if context.is_empty() {
+            return None;
+        }
+
+        if [
+            "location",
+            "city",
+            "place",
+            "address",
+            "destination",
+            "origin",
+            "region",
+        ]
+        .iter()
+        .any(|key| name.contains(key))
+        {
+            return PLACE
+                .captures(context)
+                .map(|captures| captures[1].trim_end_matches(['.', ',']).to_string());
+        }
+
+        if ["expression", "formula", "equation"]
+            .iter()
+            .any(|key| name.contains(key))
+        {
+            return EXPRESSION
+                .find(context)
+                .map(|found| found.as_str().trim().to_string());
+        }
+
+        if [
+            "query", "search", "question", "prompt", "text", "message", "input", "topic",
+            "keywords",
+        ]
+        .iter()
+        .any(|key| name == *key || name.ends_with(&format!("_{key}")))
+            || name == "q"
+        {
+            return Some(context.trim_end_matches(['?', '!', '.']).trim().to_string());
+        }
+
+        None
+    }
+
+    /// Generate a number within its bounds, preferring a number from the context
+    fn number(
+        &self,
+        object: &Map<String, Value>,
+        path: &str,
+        integer: bool,
+        required: bool,
+    ) -> Option<Value> {
+        let bounds = NumberBounds::new(object, integer);
+
+        let drawn = NUMBER
+            .find_iter(self.context)
+            .filter_map(|found| found.as_str().parse::<f64>().ok())
+            .find(|&n| (!integer || n.fract() == 0.0) && bounds.contains(n));
+        if drawn.is_none() && !required {
+            return None;
+        }
+
+        let value = drawn.unwrap_or_else(|| bounds.pick(hash(path) as usize));
+        Some(if integer {
+            Value::Number(Number::from(value as i64))
+        } else {
+            Number::from_f64(value).map_or(Value::Null, Value::Number)
+        })
+    }
+
+    /// Choose an enum option: the one mentioned in the context, or a deterministic one
+    fn choose(&self, options: &[Value], path: &str, required: bool) -> Option<Value> {
+        let context = self.context.to_lowercase();
+        let mentioned = options.iter().find(|option| {
+            option.as_str().is_some_and(|option| {
+                !option.is_empty() && context.contains(&option.to_lowercase())
+            })
+        });
+
+        match mentioned {
+            Some(option) => Some(option.clone()),
+            None if required && !options.is_empty() => {
+                Some(options[self.choose_index(options, path)].clone())
+            }
+            None => None,
+        }
+    }
+
+    /// The index of the option chosen when the context mentions none
+    fn choose_index(&self, options: &[Value], path: &str) -> usize {
+        let context = self.context.to_lowercase();
+        options
+            .iter()
+            .position(|option| {
+                option.as_str().is_some_and(|option| {
+                    !option.is_empty() && context.contains(&option.to_lowercase())
+                })
+            })
+            .unwrap_or_else(|| hash(path) as usize % options.len())
+    }
+
+    /// A deterministic placeholder word for a property
+    fn word(&self, path: &str) -> String {
+        WORDS[hash(&format!("{path}\u{0}{}", self.context)) as usize % WORDS.len()].to_string()
+    }
+
+    /// Follow a local `$ref`
+    fn resolve<'s>(&'s self, schema: &'s Value) -> &'s Value {
+        let mut schema = schema;
+        for _ in 0..MAX_DEPTH {
+            let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+                break;
+            };
+            let Some(pointer) = reference.strip_prefix('#') else {
+                break;
+            };
+            match self.root.pointer(pointer) {
+                Some(target) => schema = target,
+                None => break,
+            }
+        }
+        schema
+    }
+
+    /// Merge the branches of an `allOf` into a single schema
+    fn merge_all_of(&self, branches: &[Value]) -> Map<String, Value> {
+        let mut merged = Map::new();
+        let mut properties = Map::new();
+        let mut required = Vec::new();
+
+        for branch in branches {
+            let Some(object) = self.resolve(branch).as_object() else {
+                continue;
+            };
+            for (key, value) in object {
+                match key.as_str() {
+                    "properties" => {
+                        if let Some(branch_properties) = value.as_object() {
+                            properties.extend(branch_properties.clone());
+                        }
+                    }
+                    "required" => {
+                        if let Some(names) = value.as_array() {
+                            required.extend(names.iter().cloned());
+                        }
+                    }
+                    _ => {
+                        merged.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+
+        if !properties.is_empty() {
+            merged.insert("properties".to_string(), Value::Object(properties));
+            merged
+                .entry("type")
+                .or_insert_with(|| Value::String("object".to_string()));
+        }
+        if !required.is_empty() {
+            merged.insert("required".to_string(), Value::Array(required));
+        }
+        merged
+    }
+}
+
+/// The numeric bounds of a schema
+struct NumberBounds {
+    min: Option<f64>,
+    max: Option<f64>,
+    multiple_of: Option<f64>,
+    integer: bool,
+}
+
+impl NumberBounds {
+    fn new(object: &Map<String, Value>, integer: bool) -> Self {
+        let get = |key: &str| object.get(key).and_then(Value::as_f64);
+        // Exclusive bounds are nudged inwards: by one for integers, a hundredth otherwise
+        let step = if integer { 1.0 } else { 0.01 };
+
+        let min = match (get("minimum"), get("exclusiveMinimum")) {
+            (Some(min), Some(exclusive)) => Some(min.max(exclusive + step)),
+            (min, exclusive) => min.or(exclusive.map(|n| n + step)),
+        };
+        let max = match (get("maximum"), get("exclusiveMaximum")) {
+            (Some(max), Some(exclusive)) => Some(max.min(exclusive - step)),
+            (max, exclusive) => max.or(exclusive.map(|n| n - step)),
+        };
+
+        Self {
+            min: if integer { min.map(f64::ceil) } else { min },
+            max: if integer { max.map(f64::floor) } else { max },
+            multiple_of: get("multipleOf").filter(|&n| n > 0.0),
+            integer,
+        }
+    }
+
+    fn contains(&self, n: f64) -> bool {
+        self.min.is_none_or(|min| n >= min)
+            && self.max.is_none_or(|max| n <= max)
+            && self
+                .multiple_of
+                .is_none_or(|multiple| (n / multiple).fract() == 0.0)
+    }
+
+    /// Pick a deterministic number within the bounds
+    fn pick(&self, seed: usize) -> f64 {
+        let (low, high) = match (self.min, self.max) {
+            (Some(min), Some(max)) => (min, max.max(min)),
+            (Some(min), None) => (min, min + 10.0),
+            (None, Some(max)) => ((max - 10.0).min(max), max),
+            (None, None) => (1.0, 10.0),
+        };
+
+        let mut value = if self.integer {
+            // Wide bounds saturate the span, which stays above zero
+            let span = ((high - low) as u64).saturating_add(1);
+            low + (seed as u64 % span) as f64
+        } else {
+            // Two decimal places keep the value readable
+            let fraction = (seed % 101) as f64 / 100.0;
+            ((low + (high - low) * fraction) * 100.0).round() / 100.0
+        };
+
+        if let Some(multiple) = self.multiple_of {
+            value = (value / multiple).ceil() * multiple;
+            if self.max.is_some_and(|max| value > max) {
+                value -= multiple;
+            }
+        }
+        value.clamp(low, high)
+    }
+}
+
+/// The type of a schema, preferring a non-null type from a type list
+fn schema_type(object: &Map<String, Value>) -> &str {
+    match object.get("type") {
+        Some(Value::String(name)) => name,
+        Some(Value::Array(names)) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .find(|name| *name != "null")
+            .unwrap_or("null"),
+        _ if object.contains_key("properties") => "object",
+        _ if object.contains_key("items") => "array",
+        _ => "string",
+    }
+}
+
+/// Whether a schema only accepts `null`
+fn is_null_schema(schema: &Value) -> bool {
+    schema.get("type").and_then(Value::as_str) == Some("null")
+}
+
+/// A fixed value for a string format
+fn format_value(format: &str) -> Option<String> {
+    let value = match format {
+        "date-time" => "2024-01-15T09:30:00Z",
+        "date" => "2024-01-15",
+        "time" => "09:30:00",
+        "email" => "user@example.com",
+        "uri" | "url" | "uri-reference" | "iri" => "https://example.com",
+        "uuid" => "123e4567-e89b-12d3-a456-426614174000",
+        "ipv4" => "192.0.2.1",
+        "ipv6" => "2001:db8::1",
+        "hostname" => "example.com",
+        _ => return None,
+    };
+    Some(value.to_string())
+}
+
+/// Pad or truncate a string to fit length bounds, counted in characters
+fn fit_length(value: String, min_length: usize, max_length: Option<usize>) -> String {
+    let mut value = value;
+    let length = value.chars().count();
+    if length < min_length {
+        value.extend(std::iter::repeat_n('x', min_length - length));
+    }
+    match max_length {
+        Some(max_length) if value.chars().count() > max_length => {
+            value.chars().take(max_length).collect()
+        }
+        _ => value,
+    }
+}
+
+/// The last property name of a path such as `filters.city`
+fn property_name(path: &str) -> &str {
+    let name = path.rsplit('.').next().unwrap_or(path);
+    name.split('[').next().unwrap_or(name)
+}
+
+/// Append a property name to a path
+fn join_path(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+/// A stable FNV-1a hash
+fn hash(input: &str) -> u64 {
+    input.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_draws_values_from_context() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "location": {"type": "string", "description": "City and state"},
+                "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]},
+                "days": {"type": "integer", "minimum": 1, "maximum": 14}
+            },
+            "required": ["location"]
+        });
+
+        let value = generate_value(&schema, "Forecast for New York, NY for 5 days in celsius");
+        assert_eq!(
+            value,
+            json!({"location": "New York, NY", "unit": "celsius", "days": 5})
+        );
+
+        // Optional properties are left out when the context has no value for them
+        let value = generate_value(&schema, "weather please");
+        assert_eq!(value.as_object().unwrap().len(), 1);
+        assert!(value["location"].is_string());
+    }
+
+    #[test]
+    fn test_required_values_respect_constraints() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string", "minLength": 10, "maxLength": 12},
+                "email": {"type": "string", "format": "email"},
+                "when": {"type": "string", "format": "date-time"},
+                "count": {"type": "integer", "exclusiveMinimum": 5, "maximum": 7},
+                "ratio": {"type": "number", "minimum": 0.5, "maximum": 0.75},
+                "step": {"type": "integer", "minimum": 1, "maximum": 100, "multipleOf": 25},
+                "verbose": {"type": "boolean"},
+                "note": {"type": ["string", "null"]},
+                "tags": {
+                    "type": "array",
+                    "items": {"type": "string", "enum": ["a", "b", "c"]},
+                    "minItems": 2,
+                    "maxItems": 3,
+                    "uniqueItems": true
+                },
+                "filter": {
+                    "type": "object",
+                    "properties": {"field": {"const": "status"}},
+                    "required": ["field"]
+                }
+            },
+            "required": ["name", "email", "when", "count", "ratio", "step", "verbose", "note", "tags", "filter"]
+        });
+
+        let value = generate_value(&schema, "");
+        let name = value["name"].as_str().unwrap();
+        assert!((10..=12).contains(&name.chars().count()));
+        assert_eq!(value["email"], "user@example.com");
+        assert_eq!(value["when"], "2024-01-15T09:30:00Z");
+        assert!((6..=7).contains(&value["count"].as_i64().unwrap()));
+        assert!((0.5..=0.75).contains(&value["ratio"].as_f64().unwrap()));
+        assert_eq!(value["step"].as_i64().unwrap() % 25, 0);
+        assert_eq!(value["verbose"], true);
+        assert!(value["note"].is_string());
+        let tags = value["tags"].as_array().unwrap();
+        assert_eq!(tags.len(), 2);
+        assert_ne!(tags[0], tags[1]);
+        assert_eq!(value["filter"], json!({"field": "status"}));
+
+        // Deterministic
+        assert_eq!(value, generate_value(&schema, ""));
+    }
+
+    #[test]
+    fn test_huge_bounds() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "tags": {"type": "array", "items": {"type": "string"}, "minItems": u64::MAX},
+                "words": {"type": "array", "minItems": 10_000_000_000u64},
+                "name": {"type": "string", "minLength": 10_000_000_000u64}
+            },
+            "required": ["tags", "words", "name"]
+        });
+
+        let value = generate_value(&schema, "");
+        assert_eq!(value["tags"].as_array().unwrap().len(), MAX_ITEMS);
+        assert_eq!(value["words"].as_array().unwrap().len(), MAX_ITEMS);
+        assert_eq!(value["name"].as_str().unwrap().chars().count(), MAX_LENGTH);
+    }
+
+    #[test]
+    fn test_wide_integer_bounds() {
+        let schema = json!({"type": "integer", "minimum": 0, "maximum": 1e20});
+        let value = generate_value(&schema, "").as_f64().unwrap();
+        assert!((0.0..=1e20).contains(&value));
+
+        let schema = json!({"type": "integer", "minimum": -1e20, "maximum": 1e20});
+        let value = generate_value(&schema, "").as_f64().unwrap();
+        assert!((-1e20..=1e20).contains(&value));
+    }
+
+    #[test]
+    fn test_refs_and_composition() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "address": {"$ref": "#/$defs/Address"},
+                "contact": {"anyOf": [{"type": "null"}, {"type": "string", "format": "uri"}]},
+                "order": {"allOf": [
+                    {"properties": {"id": {"type": "integer"}}, "required": ["id"]},
+                    {"properties": {"sku": {"type": "string"}}, "required": ["sku"]}
+                ]}
+            },
+            "required": ["address", "contact", "order"],
+            "$defs": {
+                "Address": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}, "zip": {"type": "string"}},
+                    "required": ["city", "zip"]
+                }
+            }
+        });
+
+        let value = generate_value(&schema, "Ship it to Berlin");
+        assert_eq!(value["address"]["city"], "Berlin");
+        assert!(value["address"]["zip"].is_string());
+        assert_eq!(value["contact"], "https://example.com");
+        assert!(value["order"]["id"].is_i64());
+        assert!(value["order"]["sku"].is_string());
+    }
+
+    #[test]
+    fn test_check_strict() {
+        let mut schema = json!({
+            "type": "object",
+            "properties": {
+                "name": {"type": "string"},
+                "address": {
+                    "type": "object",
+                    "properties": {"city": {"type": "string"}},
+                    "required": ["city"],
+                    "additionalProperties": false
+                },
+                "tags": {"type": "array", "items": {"type": "string"}}
+            },
+            "required": ["name", "address", "tags"],
+            "additionalProperties": false
+        });
+        assert_eq!(check_strict(&schema), Ok(()));
+
+        schema["properties"]["address"]
+            .as_object_mut()
+            .unwrap()
+            .remove("additionalProperties");
+        assert_eq!(
+            check_strict(&schema).unwrap_err(),
+            "In context=('properties', 'address'), 'additionalProperties' is required to be supplied and to be false."
+        );
+
+        schema["properties"]["address"]["additionalProperties"] = json!(false);
+        schema["required"] = json!(["name", "address"]);
+        assert_eq!(
+            check_strict(&schema).unwrap_err(),
+            "In context=(), 'required' is required to be supplied and to be an array including every key in properties. Missing 'tags'."
+        );
+
+        schema["required"] = json!(["name", "address", "tags"]);
+        schema["properties"]["tags"]["uniqueItems"] = json!(true);
+        assert_eq!(
+            check_strict(&schema).unwrap_err(),
+            "In context=('properties', 'tags'), 'uniqueItems' is not permitted."
+        );
+
+        // Schemas nested too deeply are rejected instead of going unchecked
+        let mut deep = json!({"type": "string"});
+        for _ in 0..=MAX_DEPTH {
+            deep = json!({
+                "type": "object",
+                "properties": {"child": deep},
+                "required": ["child"],
+                "additionalProperties": false
+            });
+        }
+        let error = check_strict(&deep).unwrap_err();
+        assert!(error.starts_with("In context=('properties', 'child', "));
+        assert!(error.ends_with("schema exceeds the maximum nesting depth of 16."));
+
+        assert_eq!(
+            check_strict(&json!({"type": "array", "items": {"type": "string"}})).unwrap_err(),
+            "schema must be a JSON Schema of 'type: \"object\"', got 'type: \"array\"'."
+        );
+    }
+
+    #[test]
+    fn test_unique_items_fill_min_items() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "ids": {
+                    "type": "array",
+                    "items": {"type": "integer", "minimum": 1, "maximum": 10},
+                    "minItems": 5,
+                    "uniqueItems": true
+                },
+                "words": {
+                    "type": "array",
+                    "items": {"type": "string"},
+                    "minItems": 4,
+                    "uniqueItems": true
+                }
+            },
+            "required": ["ids", "words"]
+        });
+
+        let value = generate_value(&schema, "");
+        for (name, len) in [("ids", 5), ("words", 4)] {
+            let items = value[name].as_array().unwrap();
+            assert_eq!(items.len(), len, "{name}: {items:?}");
+            for (index, item) in items.iter().enumerate() {
+                assert!(!items[..index].contains(item), "{name}: {items:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_expressions_and_queries() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "expression": {"type": "string"},
+                "query": {"type": "string"}
+            },
+            "required": ["expression", "query"]
+        });
+
+        let value = generate_value(&schema, "Calculate (3 + 4) * 2 for me?");
+        assert_eq!(value["expression"], "(3 + 4) * 2");
+        assert_eq!(value["query"], "Calculate (3 + 4) * 2 for me");
+    }
+}
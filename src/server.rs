@@ -6,10 +6,17 @@
 //!
 //! ## Features
 //!
-//! - **Routing**: Configures endpoints for completions and embeddings
+//! - **Routing**: Configures endpoints for completions, embeddings and models
 //! - **Authentication**: Applies API key validation middleware
+//! - **Admin API**: Runtime expectation management under `/admin`, guarded by the admin key
+//! - **Request Journal**: Records API requests and responses for inspection via `/admin/requests`
+//! - **Rate Limiting**: Enforces per-key request and token budgets with `x-ratelimit-*` headers
+//! - **Fault Injection**: Fails requests on purpose according to the configured fault rules
+//! - **Record and Replay**: Proxies to a real upstream into a cassette, or replays a cassette
 //! - **Error Handling**: Provides standardized error responses
-//! - **CORS**: Enables cross-origin requests for browser clients
+//! - **Request Timeouts**: Fails requests exceeding `request_timeout_secs` with a `504` error
+//! - **CORS**: Enables cross-origin requests from the configured origins (optional)
+//! - **Request Logging**: Traces every request (optional)
 //! - **Graceful Shutdown**: Supports clean server shutdown
 //!
 //! ## Usage
@@ -26,17 +33,30 @@
 //! ```
 
 use poem::{
-    EndpointExt, IntoResponse, Response, Result, Route,
-    http::{Method, StatusCode, header},
+    EndpointExt, IntoResponse, Response, Result, Route, delete, get,
+    http::{HeaderName, HeaderValue, Method, StatusCode},
     middleware::{Cors, Tracing},
     post,
 };
 use serde_json;
 
 use crate::{
-    auth::AuthMiddleware,
+    auth::{AdminAuthMiddleware, AuthMiddleware},
+    cassette::CassetteMiddleware,
     config::Config,
-    handlers::{create_chat_completion, create_completion, create_embedding},
+    faults::{FaultInjector, FaultMiddleware},
+    handlers::{
+        admin::{
+            clear_expectations, clear_requests, create_expectation, delete_expectation,
+            list_expectations, list_requests,
+        },
+        create_chat_completion, create_completion, create_embedding,
+        models::{list_models, retrieve_model},
+    },
+    journal::JournalMiddleware,
+    rate_limit::{RateLimitMiddleware, RateLimiter},
+    state::AppState,
+    timeout::TimeoutMiddleware,
 };
 
 /// Create the main application with default configuration
@@ -46,51 +66,92 @@ pub fn create_app() -> impl poem::Endpoint {
 
 /// Create the application with default API key
 pub fn create_app_with_default_key() -> impl poem::Endpoint {
-    let protected_routes = Route::new()
-        .at("/completions", post(create_completion))
-        .at("/chat/completions", post(create_chat_completion))
-        .at("/embeddings", post(create_embedding))
-        .with(AuthMiddleware::with_default_key());
-
-    let app = Route::new()
-        .at("/health", poem::get(health_check))
-        .at("/", poem::get(root_handler))
-        .at("/v1/models", poem::get(list_models_handler))
-        .nest("/v1", protected_routes);
-
-    // Always apply both middleware for consistent type
-    app.with(Tracing).with(create_cors_middleware())
+    create_app_with_config(Config::default())
 }
 
 /// Create the application with custom configuration
 pub fn create_app_with_config(config: Config) -> impl poem::Endpoint {
+    let state = AppState::from_config(&config);
+    let faults = config.load_faults().unwrap_or_else(|e| {
+        tracing::error!("{e}");
+        FaultInjector::default()
+    });
+
     let protected_routes = Route::new()
         .at("/completions", post(create_completion))
         .at("/chat/completions", post(create_chat_completion))
         .at("/embeddings", post(create_embedding))
+        .with(CassetteMiddleware::from_config(&config))
+        .with(JournalMiddleware::new(state.journal.clone()))
+        .with(FaultMiddleware::new(faults))
+        .with(RateLimitMiddleware::new(RateLimiter::new(
+            config.rate_limit_rpm,
+            config.rate_limit_tpm,
+        )))
         .with(AuthMiddleware::new(&config.api_key));
 
+    let admin_routes = Route::new()
+        .at(
+            "/expectations",
+            get(list_expectations)
+                .post(create_expectation)
+                .delete(clear_expectations),
+        )
+        .at("/expectations/:id", delete(delete_expectation))
+        .at("/requests", get(list_requests).delete(clear_requests))
+        .with(AdminAuthMiddleware::new(&config.admin_key));
+
+    let cors = create_cors_middleware(&config);
     let app = Route::new()
         .at("/health", poem::get(health_check))
         .at("/", poem::get(root_handler))
-        .at("/v1/models", poem::get(list_models_handler))
-        .nest("/v1", protected_routes);
-
-    // Always apply both middleware for consistent type
-    app.with(Tracing).with(create_cors_middleware())
+        .at("/v1/models", get(list_models))
+        .at("/v1/models/:id", get(retrieve_model))
+        .nest("/v1", protected_routes)
+        .nest("/admin", admin_routes)
+        .data(state);
+
+    app.with(TimeoutMiddleware::new(config.request_timeout()))
+        .with_if(config.enable_logging, Tracing)
+        .with_if(config.enable_cors, cors)
 }
 
-/// Create CORS middleware with OpenAI API compatible settings
-fn create_cors_middleware() -> Cors {
-    Cors::new()
-        .allow_method(Method::GET)
-        .allow_method(Method::POST)
-        .allow_method(Method::OPTIONS)
-        .allow_header(header::CONTENT_TYPE)
-        .allow_header(header::AUTHORIZATION)
-        .allow_header("x-api-key")
-        .allow_origin("*")
-        .max_age(86400) // 24 hours in seconds
+/// Create CORS middleware from the configured origins, methods and headers
+///
+/// An origin of `*` allows any origin; other origins containing `*` are matched as wildcards.
+/// Entries that are not valid methods or header names are skipped; `Config::validate` reports
+/// them before the server starts.
+fn create_cors_middleware(config: &Config) -> Cors {
+    let mut cors = Cors::new().max_age(86400); // 24 hours in seconds
+
+    // Poem allows any origin when none are listed
+    let any_origin = config
+        .cors_allowed_origins
+        .iter()
+        .any(|origin| origin == "*");
+    for origin in config.cors_allowed_origins.iter().filter(|_| !any_origin) {
+        cors = if origin.contains('*') {
+            cors.allow_origin_regex(origin)
+        } else if let Ok(origin) = HeaderValue::from_str(origin) {
+            cors.allow_origin(origin)
+        } else {
+            cors
+        };
+    }
+
+    for method in &config.cors_allowed_methods {
+        if let Ok(method) = Method::from_bytes(method.as_bytes()) {
+            cors = cors.allow_method(method);
+        }
+    }
+
+    for header in &config.cors_allowed_headers {
+        if let Ok(header) = HeaderName::from_bytes(header.as_bytes()) {
+            cors = cors.allow_header(header);
+        }
+    }
+
+    cors
 }
 
 /// Health check endpoint
@@ -103,7 +164,8 @@ async fn health_check() -> Result<impl IntoResponse> {
         "endpoints": [
             "/v1/completions",
             "/v1/chat/completions",
-            "/v1/embeddings"
+            "/v1/embeddings",
+            "/v1/models"
         ]
     });
 
@@ -123,6 +185,7 @@ async fn root_handler() -> Result<impl IntoResponse> {
             "completions": "/v1/completions",
             "chat_completions": "/v1/chat/completions",
             "embeddings": "/v1/embeddings",
+            "models": "/v1/models",
             "health": "/health"
         },
         "authentication": {
@@ -139,58 +202,6 @@ async fn root_handler() -> Result<impl IntoResponse> {
         .body(info_response.to_string()))
 }
 
-/// Handler for GET /v1/models
-#[poem::handler]
-async fn list_models_handler() -> Result<impl IntoResponse> {
-    // Example static models, can be expanded as needed
-    let models = serde_json::json!({
-        "object": "list",
-        "data": [
-            {
-                "id": "text-davinci-003",
-                "object": "model",
-                "created": 1677610602,
-                "owned_by": "openai",
-                "permission": [],
-                "root": "text-davinci-003",
-                "parent": null
-            },
-            {
-                "id": "gpt-3.5-turbo",
-                "object": "model",
-                "created": 1677610602,
-                "owned_by": "openai",
-                "permission": [],
-                "root": "gpt-3.5-turbo",
-                "parent": null
-            },
-            {
-                "id": "gpt-4",
-                "object": "model",
-                "created": 1677610602,
-                "owned_by": "openai",
-                "permission": [],
-                "root": "gpt-4",
-                "parent": null
-            },
-            {
-                "id": "text-embedding-ada-002",
-                "object": "model",
-                "created": 1677610602,
-                "owned_by": "openai",
-                "permission": [],
-                "root": "text-embedding-ada-002",
-                "parent": null
-            }
-        ]
-    });
-
-    Ok(Response::builder()
-        .status(StatusCode::OK)
-        .header("content-type", "application/json")
-        .body(models.to_string()))
-}
-
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -305,6 +316,40 @@ mod tests {
         resp.assert_status_is_ok();
     }
 
+    #[tokio::test]
+    async fn test_cors_configuration() {
+        let config = Config::builder()
+            .cors_allowed_origins(["https://app.example.com"])
+            .build();
+        let cli = TestClient::new(create_app_with_config(config));
+
+        let resp = cli
+            .get("/health")
+            .header("origin", "https://app.example.com")
+            .send()
+            .await;
+        resp.assert_status_is_ok();
+        resp.assert_header("access-control-allow-origin", "https://app.example.com");
+
+        let resp = cli
+            .get("/health")
+            .header("origin", "https://other.example.com")
+            .send()
+            .await;
+        resp.assert_status(StatusCode::FORBIDDEN);
+
+        // Without CORS, no CORS headers are sent
+        let config = Config::builder().enable_cors(false).build();
+        let cli = TestClient::new(create_app_with_config(config));
+        let resp = cli
+            .get("/health")
+            .header("origin", "https://app.example.com")
+            .send()
+            .await;
+        resp.assert_status_is_ok();
+        resp.assert_header_is_not_exist("access-control-allow-origin");
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
@@ -0,0 +1,112 @@
+//! # Shared Application State
+//!
+//! This module holds the state shared by all request handlers. The server attaches a single
+//! [`AppState`] to the application with `EndpointExt::data`, and handlers read it through a
+//! `Data<&AppState>` extractor. Handlers mounted without state fall back to the built-in
+//! generators, so they remain usable on their own.
+
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::expectations::ExpectationStore;
+use crate::fixtures::{Fixture, FixtureSet};
+use crate::generators::EmbeddingMode;
+use crate::journal::RequestJournal;
+use crate::latency::LatencyProfiles;
+use crate::models::requests::CreateChatCompletionRequest;
+use crate::registry::ModelRegistry;
+
+/// State shared by all request handlers
+#[derive(Debug, Clone, Default)]
+pub struct AppState {
+    /// Scripted response fixtures, checked before the generators
+    pub fixtures: Arc<FixtureSet>,
+
+    /// Expectations registered through the admin API, checked before the fixtures
+    pub expectations: Arc<ExpectationStore>,
+
+    /// Journal of the requests received by the API endpoints
+    pub journal: Arc<RequestJournal>,
+
+    /// Latency profiles applied to generated responses
+    pub latency: Arc<LatencyProfiles>,
+
+    /// The models served by the API
+    pub models: Arc<ModelRegistry>,
+
+    /// How embedding vectors are derived from the input text
+    pub embedding_mode: EmbeddingMode,
+}
+
+impl AppState {
+    /// Create application state with the given fixtures
+    pub fn new(fixtures: FixtureSet) -> Self {
+        Self {
+            fixtures: Arc::new(fixtures),
+            expectations: Arc::new(ExpectationStore::default()),
+            journal: Arc::new(RequestJournal::default()),
+            latency: Arc::new(LatencyProfiles::default()),
+            models: Arc::new(ModelRegistry::default()),
+            embedding_mode: EmbeddingMode::default(),
+        }
+    }
+
+    /// Replace the request journal with one keeping at most `capacity` entries
+    pub fn with_journal_capacity(mut self, capacity: usize) -> Self {
+        self.journal = Arc::new(RequestJournal::new(capacity));
+        self
+    }
+
+    /// Replace the latency profiles
+    pub fn with_latency(mut self, latency: LatencyProfiles) -> Self {
+        self.latency = Arc::new(latency);
+        self
+    }
+
+    /// Replace the model registry
+    pub fn with_models(mut self, models: ModelRegistry) -> Self {
+        self.models = Arc::new(models);
+        self
+    }
+
+    /// Replace the embedding mode
+    pub fn with_embedding_mode(mut self, mode: EmbeddingMode) -> Self {
+        self.embedding_mode = mode;
+        self
+    }
+
+    /// Find the fixture answering a chat completion request
+    ///
+    /// Runtime expectations take precedence over the fixtures file. Using an expectation
+    /// counts towards its `times` limit.
+    pub fn find_fixture(&self, request: &CreateChatCompletionRequest) -> Option<Fixture> {
+        self.expectations
+            .take_match(request)
+            .or_else(|| self.fixtures.find_match(request).cloned())
+    }
+
+    /// Create application state from the server configuration
+    ///
+    /// Fixtures, latency and models files that fail to load are logged and ignored, leaving
+    /// the generators in charge, responses undelayed and the built-in models in place;
+    /// `Config::validate` reports such files before the server starts.
+    pub fn from_config(config: &Config) -> Self {
+        let fixtures = config.load_fixtures().unwrap_or_else(|e| {
+            tracing::error!("{e}");
+            FixtureSet::default()
+        });
+        let latency = config.load_latency().unwrap_or_else(|e| {
+            tracing::error!("{e}");
+            LatencyProfiles::default()
+        });
+        let models = config.load_models().unwrap_or_else(|e| {
+            tracing::error!("{e}");
+            ModelRegistry::default()
+        });
+        Self::new(fixtures)
+            .with_journal_capacity(config.journal_capacity)
+            .with_latency(latency)
+            .with_models(models)
+            .with_embedding_mode(config.embedding_mode)
+    }
+}
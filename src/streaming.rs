@@ -0,0 +1,123 @@
+//! # Server-Sent Events Streaming
+//!
+//! This module turns fully generated responses into the `text/event-stream` bodies that the
+//! OpenAI API returns when a request sets `stream: true`. The generators produce the chunk
+//! objects; this module splits text into token-sized deltas and frames the chunks as SSE events.
+//!
+//! ## Wire Format
+//!
+//! Every chunk is sent as a `data:` line holding its JSON serialization, and the stream is
+//! terminated with a literal `data: [DONE]` event, exactly like the real API:
+//!
+//! ```text
+//! data: {"id":"chatcmpl-abc","object":"chat.completion.chunk",...}
+//!
+//! data: [DONE]
+//!
+//! ```
+
+use futures::{StreamExt, stream};
+use poem::web::sse::{Event, SSE};
+use serde::Serialize;
+use std::time::Duration;
+
+/// The sentinel payload that terminates every OpenAI event stream
+pub const DONE_EVENT: &str = "[DONE]";
+
+/// Split text into token-sized pieces for streaming
+///
+/// Pieces keep their leading whitespace (`"Hello"`, `" world"`, `"!"`), mirroring how BPE
+/// tokens are emitted by the real API. Concatenating the pieces yields the original text.
+pub fn split_into_deltas(text: &str) -> Vec<String> {
+    let mut pieces = Vec::new();
+    let mut current = String::new();
+
+    for c in text.chars() {
+        let starts_new_piece = if c.is_whitespace() {
+            // Whitespace attaches to the front of the next word
+            !current.is_empty() && !current.chars().all(char::is_whitespace)
+        } else if c.is_alphanumeric() {
+            current
+                .chars()
+                .last()
+                .is_some_and(|last| !last.is_alphanumeric() && !last.is_whitespace())
+        } else {
+            // Punctuation is emitted as its own piece
+            current.chars().any(|c| !c.is_whitespace())
+        };
+
+        if starts_new_piece {
+            pieces.push(std::mem::take(&mut current));
+        }
+        current.push(c);
+    }
+
+    if !current.is_empty() {
+        pieces.push(current);
+    }
+
+    pieces
+}
+
+/// Serialize chunks into SSE events, followed by the `[DONE]` terminator
+pub fn to_events<T: Serialize>(chunks: &[T]) -> Result<Vec<Event>, serde_json::Error> {
+    let mut events = Vec::with_capacity(chunks.len() + 1);
+    for chunk in chunks {
+        events.push(Event::message(serde_json::to_string(chunk)?));
+    }
+    events.push(Event::message(DONE_EVENT));
+    Ok(events)
+}
+
+/// Build an SSE response that waits before emitting each event
+///
+/// `delays[i]` is the wait before event `i`; events past the end of `delays`, such as the
+/// `[DONE]` terminator, are emitted right away.
+pub fn into_paced_sse(events: Vec<Event>, delays: Vec<Duration>) -> SSE {
+    let delays = delays.into_iter().chain(std::iter::repeat(Duration::ZERO));
+    let paced = stream::iter(events.into_iter().zip(delays)).then(|(event, delay)| async move {
+        if !delay.is_zero() {
+            tokio::time::sleep(delay).await;
+        }
+        event
+    });
+    SSE::new(paced)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_into_deltas_round_trips() {
+        let text = "Hello! How can I assist you today?";
+        let pieces = split_into_deltas(text);
+
+        assert_eq!(pieces.concat(), text);
+        assert_eq!(pieces[0], "Hello");
+        assert_eq!(pieces[1], "!");
+        assert_eq!(pieces[2], " How");
+    }
+
+    #[test]
+    fn test_split_into_deltas_json() {
+        let text = r#"{"location": "San Francisco, CA"}"#;
+        let pieces = split_into_deltas(text);
+
+        assert!(pieces.len() > 1);
+        assert_eq!(pieces.concat(), text);
+    }
+
+    #[test]
+    fn test_split_into_deltas_empty() {
+        assert!(split_into_deltas("").is_empty());
+    }
+
+    #[test]
+    fn test_to_events_appends_done() {
+        let events = to_events(&[serde_json::json!({"a": 1})]).unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].to_string(), "data: {\"a\":1}\n\n");
+        assert_eq!(events[1].to_string(), "data: [DONE]\n\n");
+    }
+}
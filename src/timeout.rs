@@ -0,0 +1,132 @@
+//! # Request Timeouts
+//!
+//! This module bounds how long the server works on a request, as configured by
+//! `--request-timeout-secs`. Requests that take longer, for example because a latency profile
+//! or a `timeout` fault holds them up, are answered with a `504 Gateway Timeout` error in the
+//! OpenAI format:
+//!
+//! ```json
+//! {
+//!   "error": {
+//!     "message": "Request timed out after 30 seconds.",
+//!     "type": "server_error",
+//!     "code": "timeout"
+//!   }
+//! }
+//! ```
+//!
+//! The timeout covers the time until the response starts. Once a streamed response has
+//! started, its remaining chunks are not cut off.
+
+use poem::{Endpoint, IntoResponse, Middleware, Request, Response, Result, http::StatusCode};
+use std::time::Duration;
+
+use crate::models::responses::ErrorResponse;
+
+/// Middleware that fails requests taking longer than a timeout
+#[derive(Debug, Clone, Copy)]
+pub struct TimeoutMiddleware {
+    timeout: Duration,
+}
+
+impl TimeoutMiddleware {
+    /// Create a timeout middleware
+    pub fn new(timeout: Duration) -> Self {
+        Self { timeout }
+    }
+}
+
+impl<E> Middleware<E> for TimeoutMiddleware
+where
+    E: Endpoint,
+{
+    type Output = TimeoutEndpoint<E>;
+
+    fn transform(&self, ep: E) -> Self::Output {
+        TimeoutEndpoint {
+            inner: ep,
+            timeout: self.timeout,
+        }
+    }
+}
+
+/// Wrapper endpoint that enforces the timeout
+pub struct TimeoutEndpoint<E> {
+    inner: E,
+    timeout: Duration,
+}
+
+impl<E> Endpoint for TimeoutEndpoint<E>
+where
+    E: Endpoint,
+{
+    type Output = Response;
+
+    async fn call(&self, req: Request) -> Result<Self::Output> {
+        match tokio::time::timeout(self.timeout, self.inner.call(req)).await {
+            Ok(result) => result.map(IntoResponse::into_response),
+            Err(_) => {
+                tracing::warn!("Request timed out after {:?}", self.timeout);
+                Ok(create_timeout_response(self.timeout))
+            }
+        }
+    }
+}
+
+/// Create the OpenAI-formatted error response for a timed out request
+fn create_timeout_response(timeout: Duration) -> Response {
+    let error_response = ErrorResponse::new(
+        format!("Request timed out after {} seconds.", timeout.as_secs()),
+        "server_error".to_string(),
+    )
+    .with_code("timeout".to_string());
+
+    let json_body = match serde_json::to_string(&error_response) {
+        Ok(json) => json,
+        Err(_) => {
+            r#"{"error":{"message":"Request timed out","type":"server_error","code":"timeout"}}"#
+                .to_string()
+        }
+    };
+
+    Response::builder()
+        .status(StatusCode::GATEWAY_TIMEOUT)
+        .header("content-type", "application/json")
+        .body(json_body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use poem::{EndpointExt, Route, get, handler, test::TestClient};
+
+    #[handler]
+    async fn slow_handler() -> &'static str {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        "done"
+    }
+
+    #[tokio::test]
+    async fn test_timeout_middleware() {
+        let client = TestClient::new(
+            Route::new()
+                .at("/slow", get(slow_handler))
+                .with(TimeoutMiddleware::new(Duration::from_millis(50))),
+        );
+
+        let resp = client.get("/slow").send().await;
+        resp.assert_status(StatusCode::GATEWAY_TIMEOUT);
+        let body: serde_json::Value = resp.json().await.value().deserialize();
+        assert_eq!(body["error"]["code"], "timeout");
+        assert_eq!(body["error"]["type"], "server_error");
+
+        let client = TestClient::new(
+            Route::new()
+                .at("/slow", get(slow_handler))
+                .with(TimeoutMiddleware::new(Duration::from_secs(5))),
+        );
+        let resp = client.get("/slow").send().await;
+        resp.assert_status_is_ok();
+        resp.assert_text("done").await;
+    }
+}
@@ -0,0 +1,424 @@
+//! # Tokenizer
+//!
+//! This module counts tokens the way OpenAI models do, so the `usage` reported by the mock
+//! matches what clients compute with tiktoken. It bundles the two BPE vocabularies used by
+//! current models:
+//!
+//! - **o200k_base**: GPT-4o, GPT-4.1, GPT-5 and the o-series reasoning models
+//! - **cl100k_base**: GPT-4, GPT-3.5 and the `text-embedding-*` models
+//!
+//! Models that match neither family, such as custom models from a models file, use
+//! cl100k_base.
+//!
+//! ## Chat Messages
+//!
+//! Chat prompts are counted with the overhead of the chat format: every message costs three
+//! tokens on top of its role and content, a `name` one more, and the reply is primed with
+//! three further tokens. Attached images are counted as described in
+//! [`images`](crate::images).
+//!
+//! Tool definitions are counted with the approximation from the OpenAI cookbook: the API
+//! renders them into the system prompt in an undocumented format, so each function costs its
+//! `name:description` line and a `name:type:description` line per top-level parameter, plus
+//! fixed overheads. Nested parameter schemas are not counted.
+//!
+//! ## Usage
+//!
+//! ```rust
+//! use openai_mock::tokenizer::{Encoding, count_tokens};
+//!
+//! assert_eq!(Encoding::for_model("gpt-4o"), Encoding::O200kBase);
+//! assert_eq!(count_tokens("gpt-4", "Hello, world!"), 4);
+//! ```
+
+use serde_json::Value;
+use tiktoken_rs::CoreBPE;
+
+use crate::images;
+use crate::models::requests::{ChatCompletionMessage, ChatCompletionTool};
+
+/// Tokens added to every chat message for the role and message delimiters
+const TOKENS_PER_MESSAGE: u32 = 3;
+
+/// Tokens added to a chat message that has a `name`
+const TOKENS_PER_NAME: u32 = 1;
+
+/// Tokens that prime the assistant reply after the last message
+const TOKENS_PER_REPLY: u32 = 3;
+
+/// Tokens added to every tool definition
+const TOKENS_PER_FUNCTION: u32 = 7;
+
+/// Tokens added to a function that has parameters
+const TOKENS_PER_PARAMETERS: u32 = 3;
+
+/// Tokens added to every parameter that is not an `enum`
+const TOKENS_PER_PARAMETER: u32 = 3;
+
+/// Tokens added to every option of an `enum` parameter
+const TOKENS_PER_ENUM_OPTION: u32 = 3;
+
+/// Tokens closing the tool definitions
+const TOKENS_FOR_TOOLS: u32 = 12;
+
+/// Model name prefixes that use the o200k_base vocabulary
+const O200K_PREFIXES: &[&str] = &[
+    "gpt-4o",
+    "chatgpt-4o",
+    "gpt-4.1",
+    "gpt-4.5",
+    "gpt-5",
+    "o1",
+    "o3",
+    "o4",
+];
+
+/// A BPE vocabulary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    Cl100kBase,
+    O200kBase,
+}
+
+impl Encoding {
+    /// The vocabulary used by a model
+    pub fn for_model(model: &str) -> Self {
+        if O200K_PREFIXES
+            .iter()
+            .any(|prefix| model.starts_with(prefix))
+        {
+            Encoding::O200kBase
+        } else {
+            Encoding::Cl100kBase
+        }
+    }
+
+    /// The name of the vocabulary, as used by tiktoken
+    pub fn name(&self) -> &'static str {
+        match self {
+            Encoding::Cl100kBase => "cl100k_base",
+            Encoding::O200kBase => "o200k_base",
+        }
+    }
+
+    /// Split text into token IDs
+    ///
+    /// Special tokens such as `<|endoftext|>` are encoded as plain text.
+    pub fn encode(&self, text: &str) -> Vec<u32> {
+        self.bpe().encode_ordinary(text)
+    }
+
+    /// Join token IDs back into text
+    ///
+    /// Returns `None` if an ID is not in the vocabulary. Token sequences that split a
+    /// character are decoded lossily.
+    pub fn decode(&self, tokens: &[u32]) -> Option<String> {
+        self.bpe()
+            .decode_bytes(tokens)
+            .ok()
+            .map(|bytes| String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    /// The highest token ID in the vocabulary, including special tokens
+    pub fn max_token(&self) -> u32 {
+        match self {
+            Encoding::Cl100kBase => 100_276,
+            Encoding::O200kBase => 200_018,
+        }
+    }
+
+    /// Whether a token ID is in the vocabulary
+    pub fn is_valid_token(&self, token: u32) -> bool {
+        self.bpe().decode_bytes(&[token]).is_ok()
+    }
+
+    /// The number of tokens in a text
+    pub fn count_tokens(&self, text: &str) -> u32 {
+        self.encode(text).len() as u32
+    }
+
+    /// The first `max_tokens` tokens of a text, or `None` if the text already fits
+    ///
+    /// A character split by the cut is dropped rather than decoded lossily.
+    pub fn truncate(&self, text: &str, max_tokens: u32) -> Option<String> {
+        let tokens = self.encode(text);
+        if tokens.len() <= max_tokens as usize {
+            return None;
+        }
+
+        let bytes = self
+            .bpe()
+            .decode_bytes(&tokens[..max_tokens as usize])
+            .unwrap_or_default();
+        let valid = match std::str::from_utf8(&bytes) {
+            Ok(text) => text,
+            Err(error) => std::str::from_utf8(&bytes[..error.valid_up_to()]).unwrap_or_default(),
+        };
+        Some(valid.to_string())
+    }
+
+    fn bpe(&self) -> &'static CoreBPE {
+        match self {
+            Encoding::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+            Encoding::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        }
+    }
+}
+
+/// The number of tokens in a text for a model
+pub fn count_tokens(model: &str, text: &str) -> u32 {
+    Encoding::for_model(model).count_tokens(text)
+}
+
+/// The first `max_tokens` tokens of a text for a model, or `None` if the text already fits
+pub fn truncate_tokens(model: &str, text: &str, max_tokens: u32) -> Option<String> {
+    Encoding::for_model(model).truncate(text, max_tokens)
+}
+
+/// The number of prompt tokens of a chat conversation for a model
+///
+/// Includes the per-message overhead of the chat format, the tokens priming the reply and
+/// the tool definitions. Tool calls made by assistant messages count their function names
+/// and arguments, and attached images the tokens their detail level costs.
+pub fn count_chat_tokens(
+    model: &str,
+    messages: &[ChatCompletionMessage],
+    tools: &[ChatCompletionTool],
+) -> u32 {
+    let encoding = Encoding::for_model(model);
+    let message_tokens: u32 = messages
+        .iter()
+        .map(|message| {
+            let mut tokens = TOKENS_PER_MESSAGE + encoding.count_tokens(&message.role.to_string());
+            if let Some(content) = &message.content {
+                tokens += encoding.count_tokens(&content.text());
+                tokens += content
+                    .images()
+                    .map(|image| images::image_tokens(model, image))
+                    .sum::<u32>();
+            }
+            if let Some(name) = &message.name {
+                tokens += TOKENS_PER_NAME + encoding.count_tokens(name);
+            }
+            if let Some(tool_calls) = &message.tool_calls {
+                tokens += tool_calls
+                    .iter()
+                    .map(|call| {
+                        encoding.count_tokens(&call.function.name)
+                            + encoding.count_tokens(&call.function.arguments)
+                    })
+                    .sum::<u32>();
+            }
+            tokens
+        })
+        .sum();
+
+    message_tokens + TOKENS_PER_REPLY + count_tool_tokens(model, tools)
+}
+
+/// The number of prompt tokens the tool definitions of a chat request add for a model
+pub fn count_tool_tokens(model: &str, tools: &[ChatCompletionTool]) -> u32 {
+    if tools.is_empty() {
+        return 0;
+    }
+
+    let encoding = Encoding::for_model(model);
+    let function_tokens: u32 = tools
+        .iter()
+        .map(|tool| {
+            let function = &tool.function;
+            let mut tokens = TOKENS_PER_FUNCTION
+                + encoding.count_tokens(&format!(
+                    "{}:{}",
+                    function.name,
+                    description(function.description.as_deref())
+                ));
+
+            let properties = function
+                .parameters
+                .as_ref()
+                .and_then(|parameters| parameters.get("properties"))
+                .and_then(Value::as_object)
+                .filter(|properties| !properties.is_empty());
+            if let Some(properties) = properties {
+                tokens += TOKENS_PER_PARAMETERS;
+                for (name, property) in properties {
+                    match property.get("enum").and_then(Value::as_array) {
+                        Some(options) => {
+                            tokens += options
+                                .iter()
+                                .map(|option| {
+                                    TOKENS_PER_ENUM_OPTION
+                                        + encoding.count_tokens(&schema_text(option))
+                                })
+                                .sum::<u32>();
+                        }
+                        None => tokens += TOKENS_PER_PARAMETER,
+                    }
+                    tokens += encoding.count_tokens(&format!(
+                        "{name}:{}:{}",
+                        property.get("type").map(schema_text).unwrap_or_default(),
+                        description(property.get("description").and_then(Value::as_str))
+                    ));
+                }
+            }
+            tokens
+        })
+        .sum();
+
+    function_tokens + TOKENS_FOR_TOOLS
+}
+
+/// A description as the tool rendering shows it, without a trailing period
+fn description(text: Option<&str>) -> &str {
+    text.unwrap_or_default().trim_end_matches('.')
+}
+
+/// A schema value as the tool rendering shows it: strings bare, anything else as JSON
+fn schema_text(value: &Value) -> String {
+    match value.as_str() {
+        Some(text) => text.to_string(),
+        None => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::requests::ChatCompletionRole;
+
+    fn message(role: ChatCompletionRole, content: &str) -> ChatCompletionMessage {
+        ChatCompletionMessage {
+            role,
+            content: Some(content.into()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    #[test]
+    fn test_encoding_for_model() {
+        assert_eq!(Encoding::for_model("gpt-4o-mini"), Encoding::O200kBase);
+        assert_eq!(Encoding::for_model("o3-mini"), Encoding::O200kBase);
+        assert_eq!(Encoding::for_model("gpt-4-turbo"), Encoding::Cl100kBase);
+        assert_eq!(Encoding::for_model("gpt-3.5-turbo"), Encoding::Cl100kBase);
+        assert_eq!(
+            Encoding::for_model("text-embedding-3-small"),
+            Encoding::Cl100kBase
+        );
+        assert_eq!(Encoding::for_model("local-llm"), Encoding::Cl100kBase);
+    }
+
+    #[test]
+    fn test_count_tokens_matches_tiktoken() {
+        assert_eq!(count_tokens("gpt-4", "Hello, world!"), 4);
+        assert_eq!(count_tokens("gpt-4o", "Hello, world!"), 4);
+        assert_eq!(count_tokens("gpt-4", "tiktoken is great!"), 6);
+        assert_eq!(count_tokens("gpt-4", ""), 0);
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        for encoding in [Encoding::Cl100kBase, Encoding::O200kBase] {
+            let tokens = encoding.encode("The quick brown fox");
+            assert_eq!(
+                encoding.decode(&tokens).as_deref(),
+                Some("The quick brown fox")
+            );
+        }
+        assert_eq!(Encoding::Cl100kBase.decode(&[u32::MAX]), None);
+
+        for encoding in [Encoding::Cl100kBase, Encoding::O200kBase] {
+            assert!(encoding.is_valid_token(0));
+            assert!(encoding.is_valid_token(encoding.max_token()));
+            assert!(!encoding.is_valid_token(encoding.max_token() + 1));
+        }
+    }
+
+    #[test]
+    fn test_truncate_tokens() {
+        assert_eq!(
+            truncate_tokens("gpt-4", "Hello, world!", 2).as_deref(),
+            Some("Hello,")
+        );
+        assert_eq!(truncate_tokens("gpt-4", "Hello, world!", 4), None);
+        assert_eq!(truncate_tokens("gpt-4", "Hello", 0).as_deref(), Some(""));
+
+        // Cuts inside a multi-byte character drop the partial character
+        let text = "日本語のテキスト";
+        for max_tokens in 0..count_tokens("gpt-4", text) {
+            let truncated = truncate_tokens("gpt-4", text, max_tokens).unwrap();
+            assert!(text.starts_with(&truncated));
+            assert!(count_tokens("gpt-4", &truncated) <= max_tokens);
+        }
+    }
+
+    #[test]
+    fn test_count_chat_tokens() {
+        // Matches the example from the OpenAI cookbook for gpt-3.5-turbo and gpt-4
+        let messages = vec![
+            message(
+                ChatCompletionRole::System,
+                "You are a helpful, pattern-following assistant that translates corporate jargon into plain English.",
+            ),
+            message(
+                ChatCompletionRole::User,
+                "New synergies will help drive top-line growth.",
+            ),
+        ];
+        let tokens = count_chat_tokens("gpt-4", &messages, &[]);
+        let expected = 3
+            + 1
+            + count_tokens("gpt-4", &messages[0].text().unwrap())
+            + 3
+            + 1
+            + count_tokens("gpt-4", &messages[1].text().unwrap())
+            + 3;
+        assert_eq!(tokens, expected);
+
+        let mut named = messages.clone();
+        named[1].name = Some("example_user".to_string());
+        assert!(count_chat_tokens("gpt-4", &named, &[]) > tokens + 1);
+    }
+
+    #[test]
+    fn test_count_tool_tokens() {
+        let tool: ChatCompletionTool = serde_json::from_value(serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": "get_weather",
+                "description": "Get the current weather.",
+                "parameters": {
+                    "type": "object",
+                    "properties": {
+                        "location": {"type": "string", "description": "The city"},
+                        "unit": {"type": "string", "enum": ["celsius", "fahrenheit"]}
+                    }
+                }
+            }
+        }))
+        .unwrap();
+
+        let expected = 7
+            + count_tokens("gpt-4o", "get_weather:Get the current weather")
+            + 3
+            + 3
+            + count_tokens("gpt-4o", "location:string:The city")
+            + (3 + count_tokens("gpt-4o", "celsius"))
+            + (3 + count_tokens("gpt-4o", "fahrenheit"))
+            + count_tokens("gpt-4o", "unit:string:")
+            + 12;
+        assert_eq!(
+            count_tool_tokens("gpt-4o", std::slice::from_ref(&tool)),
+            expected
+        );
+        assert_eq!(count_tool_tokens("gpt-4o", &[]), 0);
+
+        let messages = vec![message(ChatCompletionRole::User, "Weather in Paris?")];
+        assert_eq!(
+            count_chat_tokens("gpt-4o", &messages, &[tool]),
+            count_chat_tokens("gpt-4o", &messages, &[]) + expected
+        );
+    }
+}
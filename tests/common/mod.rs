@@ -80,28 +80,22 @@ pub struct TestServer {
 impl TestServer {
     /// Creates and starts a new test server
     pub async fn start() -> Result<Self, Box<dyn std::error::Error>> {
-        let (config, base_url, port) = create_test_server();
-        let app = create_app_with_config(config.clone());
-        let listener = TcpListener::bind(config.bind_address());
-
-        let server_handle = tokio::spawn(async move {
-            if let Err(e) = Server::new(listener).run(app).await {
-                eprintln!("Test server error: {e}");
-            }
-        });
-
-        wait_for_server_ready(&base_url).await?;
-
-        Ok(TestServer {
-            base_url,
-            port,
-            server_handle: Some(server_handle),
-        })
+        let (config, _port) = create_test_config();
+        Self::start_with_config(config).await
     }
 
     /// Creates and starts a new test server with custom API key
     pub async fn start_with_key(api_key: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let (config, base_url, port) = create_test_server_with_key(api_key);
+        let (config, _base_url, _port) = create_test_server_with_key(api_key);
+        Self::start_with_config(config).await
+    }
+
+    /// Creates and starts a new test server from a customized test configuration
+    ///
+    /// The configuration should come from `create_test_config` so that it binds a free port.
+    pub async fn start_with_config(config: Config) -> Result<Self, Box<dyn std::error::Error>> {
+        let port = config.port;
+        let base_url = format!("http://127.0.0.1:{port}");
         let app = create_app_with_config(config.clone());
         let listener = TcpListener::bind(config.bind_address());
 
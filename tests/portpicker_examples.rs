@@ -206,11 +206,11 @@ async fn example_port_selection_patterns() {
 
     // Try to get ports in the higher range (useful for avoiding conflicts)
     while high_ports.len() < 3 && attempts < MAX_ATTEMPTS {
-        if let Some(port) = portpicker::pick_unused_port() {
-            if port > 40000 {
-                high_ports.push(port);
-                println!("Found high port: {port}");
-            }
+        if let Some(port) = portpicker::pick_unused_port()
+            && port > 40000
+        {
+            high_ports.push(port);
+            println!("Found high port: {port}");
         }
         attempts += 1;
     }
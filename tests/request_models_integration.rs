@@ -199,6 +199,7 @@ fn test_validation_errors() {
         top_p: None,
         n: None,
         stream: None,
+        stream_options: None,
         logprobs: None,
         echo: None,
         stop: None,
@@ -239,6 +240,7 @@ fn test_serialization_roundtrip() {
         top_p: Some(0.9),
         n: Some(2),
         stream: Some(false),
+        stream_options: None,
         logprobs: Some(2),
         echo: Some(true),
         stop: Some(StopSequences::Array(vec![